@@ -0,0 +1,43 @@
+use connector_arrow::api::{Connector, Statement};
+use connector_arrow::postgres::PostgresConnection;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const ROWS: u64 = 100_000;
+
+/// Requires a database at `POSTGRES_URL`. Skipped when it is not set.
+fn read(c: &mut Criterion) {
+    let Ok(url) = std::env::var("POSTGRES_URL") else {
+        eprintln!("POSTGRES_URL is not set, skipping read_postgres benchmark");
+        return;
+    };
+    let mut client = postgres::Client::connect(&url, postgres::NoTls).unwrap();
+    client
+        .batch_execute(&format!(
+            "DROP TABLE IF EXISTS connector_arrow_bench;
+            CREATE TABLE connector_arrow_bench (id BIGINT NOT NULL, val DOUBLE PRECISION, name TEXT, flag BOOLEAN);
+            INSERT INTO connector_arrow_bench
+            SELECT i, i * 0.5, 'name_' || i, CASE WHEN i % 3 = 0 THEN NULL ELSE i % 2 = 0 END
+            FROM generate_series(1, {ROWS}) AS i;"
+        ))
+        .unwrap();
+    let mut conn = PostgresConnection::new(client);
+
+    let mut group = c.benchmark_group("read_postgres");
+    group.throughput(Throughput::Elements(ROWS));
+    group.sample_size(20);
+    group.bench_function("query", |b| {
+        b.iter(|| {
+            let mut stmt = conn.query("SELECT * FROM connector_arrow_bench").unwrap();
+            let reader = stmt.start([]).unwrap();
+            let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                batches.iter().map(|b| b.num_rows() as u64).sum::<u64>(),
+                ROWS
+            );
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+use connector_arrow::api::{Connector, Statement};
+use connector_arrow::sqlite::SQLiteConnection;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const ROWS: u64 = 100_000;
+
+fn read(c: &mut Criterion) {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!(
+        "CREATE TABLE bench (id INTEGER NOT NULL, val REAL, name TEXT, flag INTEGER);
+        WITH RECURSIVE seq(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM seq WHERE i < {ROWS})
+        INSERT INTO bench
+        SELECT i, i * 0.5, 'name_' || i, CASE WHEN i % 3 = 0 THEN NULL ELSE i % 2 END FROM seq;"
+    ))
+    .unwrap();
+    let mut conn = SQLiteConnection::new(conn);
+
+    let mut group = c.benchmark_group("read_sqlite");
+    group.throughput(Throughput::Elements(ROWS));
+    group.sample_size(20);
+    group.bench_function("query", |b| {
+        b.iter(|| {
+            let mut stmt = conn.query("SELECT * FROM bench").unwrap();
+            let reader = stmt.start([]).unwrap();
+            let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                batches.iter().map(|b| b.num_rows() as u64).sum::<u64>(),
+                ROWS
+            );
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read);
+criterion_main!(benches);
@@ -4,13 +4,24 @@
 //! - [Connector], providing [Connector::query] and [Connector::append] functions,
 //! - [SchemaGet], for schema introspection,
 //! - [SchemaEdit], for basic schema migration commands,
+//! - [TypeMapper], for overriding the type mapping of a connection.
+//!
+//! For object-safe variants of these traits, see [crate::dynamic].
 
 use std::any::Any;
+use std::collections::HashMap;
+use std::time::Duration;
 
-use arrow::datatypes::{DataType, SchemaRef};
-use arrow::record_batch::RecordBatch;
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
 
-use crate::errors::{ConnectorError, TableCreateError, TableDropError};
+use crate::cancel::CancelHandle;
+use crate::errors::{
+    AppendCheckError, ColumnEditError, ConnectorError, TableCreateError, TableDropError,
+    TableEditError,
+};
+use crate::migration::{AppendPlan, MigrationPlan, MigrationStep};
 
 /// Ability to query data from a data store and append data into the data store.
 pub trait Connector {
@@ -25,8 +36,17 @@ pub trait Connector {
     /// Prepare a query to the data store, using data store's preferred query language.
     fn query<'a>(&'a mut self, query: &str) -> Result<Self::Stmt<'a>, ConnectorError>;
 
+    /// Prepare an appender for the given table, with default [AppendOptions].
+    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError> {
+        self.append_with(table_name, &AppendOptions::default())
+    }
+
     /// Prepare an appender for the given table.
-    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError>;
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError>;
 
     /// Describes how database types map into the arrow types.
     /// None means that querying this type will return [DataType::Binary] with field
@@ -37,6 +57,64 @@ pub trait Connector {
     /// Describes how arrow types map into the database types.
     /// None means that appending this type is not supported.
     fn type_arrow_into_db(_ty: &DataType) -> Option<String>;
+
+    /// Quotes an identifier (i.e. name of a table or a column) for use in queries of this
+    /// data store. Identifiers that are valid without quotes are returned unchanged.
+    fn quote_ident(ident: &str) -> String;
+
+    /// Overrides of the type mapping that were set on this connection.
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        None
+    }
+
+    /// Returns a handle that can cancel queries running on this connection from another thread.
+    ///
+    /// Must be obtained before executing the query, because the connection is borrowed while
+    /// the query is running.
+    ///
+    /// Supported by PostgreSQL, SQLite and MySQL (only for connections with
+    /// `MySQLConnection::with_cancel_opts`). DuckDB and SQL Server return
+    /// [ConnectorError::NotSupported], because their client crates do not expose interrupting
+    /// a running query.
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError>;
+
+    /// Checks that batches of `schema` can be appended into an existing table, before any data
+    /// is written.
+    ///
+    /// Fields are matched to the columns of the table by name. All problems are reported at once:
+    /// fields without a column, missing columns that do not allow nulls, nullability conflicts,
+    /// narrowing type conversions and types that cannot be stored.
+    fn check_append(
+        &mut self,
+        table_name: &str,
+        schema: &Schema,
+    ) -> Result<AppendPlan, AppendCheckError>
+    where
+        Self: SchemaGet + Sized,
+    {
+        let table = self.table_get(table_name)?;
+        crate::migration::plan_append::<Self>(self.type_mapper(), table_name, &table, schema)
+            .map_err(AppendCheckError::Incompatible)
+    }
+}
+
+/// User-provided overrides of the type mapping of a connection.
+///
+/// Consulted before the built-in mapping of [Connector::type_db_into_arrow]
+/// and [Connector::type_arrow_into_db]. Returning None falls back to the built-in mapping.
+///
+/// Applies to query results, [SchemaGet::table_get], [SchemaEdit::table_create] and [Append].
+/// See [crate::util::TypeRules] for a rule-based implementation.
+pub trait TypeMapper: Send + Sync {
+    /// Arrow type of a column of database type `db_ty`.
+    fn db_into_arrow(&self, _column_name: &str, _db_ty: &str) -> Option<DataType> {
+        None
+    }
+
+    /// Database type of a column of arrow type `ty`.
+    fn arrow_into_db(&self, _column_name: &str, _ty: &DataType) -> Option<String> {
+        None
+    }
 }
 
 /// A task that is to be executed in the data store, over a connection.
@@ -63,6 +141,33 @@ pub trait Statement<'conn> {
         &mut self,
         args: (&RecordBatch, usize),
     ) -> Result<Self::Reader<'_>, ConnectorError>;
+
+    /// Execute this statement once and convert the result into the expected schema.
+    ///
+    /// Values are produced directly into the expected types where the connector supports it
+    /// and are cast otherwise. Values that cannot be represented in the expected type
+    /// result in an error. Column names of the result are taken from the expected schema.
+    fn start_with_schema<'p, I>(
+        &mut self,
+        args: I,
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError>
+    where
+        I: IntoIterator<Item = &'p dyn ArrowValue>,
+    {
+        let args: Vec<_> = args.into_iter().collect();
+        let batch = crate::params::vec_to_record_batch(args)?;
+        self.start_batch_with_schema((&batch, 0), expected)
+    }
+
+    /// Execute this statement once and convert the result into the expected schema.
+    /// Query arguments are read from record batch, from the specified row.
+    /// See [Statement::start_with_schema].
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError>;
 }
 
 /// Reads result of the query, starting with the schema.
@@ -75,27 +180,319 @@ pub trait ResultReader<'stmt>: Iterator<Item = Result<RecordBatch, ConnectorErro
 /// that this field was created from.
 pub const METADATA_DB_TYPE: &str = "db_type";
 
+/// Key of the metadata on [arrow::datatypes::Field] that stores the default value of the column,
+/// as an SQL expression. Used by [SchemaEdit::table_create_with] and set by [SchemaGet::table_get].
+pub const METADATA_DEFAULT: &str = "default";
+
+/// Key of the metadata on [arrow::datatypes::Field] that is set by [SchemaGet::table_get] on
+/// columns whose values are generated by the data store. Value is either `identity`, for identity
+/// and auto-increment columns, or `computed`, for columns computed from other columns.
+pub const METADATA_GENERATED: &str = "generated";
+
+/// Key of the metadata on [arrow::datatypes::Field] that stores the comment of the column.
+/// Used by [SchemaEdit::table_create_with] and set by [SchemaGet::table_get].
+pub const METADATA_COMMENT: &str = "comment";
+
 /// Receive [RecordBatch]es that have to be written to a table in the data store.
 pub trait Append<'conn> {
     // TODO: add ON CONFLICT parameter
 
     fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError>;
 
-    fn finish(self) -> Result<(), ConnectorError>;
+    /// Appends all batches of the reader, as they are read.
+    fn append_reader<R: RecordBatchReader>(&mut self, reader: R) -> Result<(), ConnectorError>
+    where
+        Self: Sized,
+    {
+        for batch in reader {
+            self.append(batch?)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<AppendSummary, ConnectorError>;
+}
+
+/// Statistics of a finished append, returned by [Append::finish].
+#[derive(Debug, Clone, Default)]
+pub struct AppendSummary {
+    /// Number of rows written, as reported by the data store where available.
+    pub rows: u64,
+
+    /// Number of appended batches.
+    pub batches: u64,
+
+    /// Size of the appended batches in Arrow memory.
+    pub bytes: u64,
+
+    /// Time from creation of the appender until it was finished.
+    pub elapsed: Duration,
+
+    /// Values that were generated for the column of [AppendOptions::generated_keys],
+    /// in order of appended rows. An Int64 array.
+    pub generated_keys: Option<ArrayRef>,
+
+    /// Rows that could not be written, with [OnError::Reject]. Contains the appended fields,
+    /// followed by field [REJECTED_ERROR_FIELD] with the error message.
+    pub rejected: Option<RecordBatch>,
+}
+
+/// Name of the field of [AppendSummary::rejected] that contains error messages.
+pub const REJECTED_ERROR_FIELD: &str = "_error";
+
+/// Options of [Connector::append_with].
+#[derive(Debug, Clone, Default)]
+pub struct AppendOptions {
+    /// How the table is prepared before the first batch is written.
+    pub mode: AppendMode,
+
+    /// How fields of the appended batches are matched to the columns of the table.
+    pub columns: ColumnMatch,
+
+    /// Identity, serial or auto-increment column, whose generated values are returned in
+    /// [AppendSummary::generated_keys]. The column must be an integer and should not be appended.
+    ///
+    /// Postgres, DuckDB and SQL Server append with INSERT statements instead of bulk loading,
+    /// when this is set. MySQL reports only the first generated value of each statement,
+    /// so it relies on consecutive allocation of auto-increment values.
+    pub generated_keys: Option<String>,
+
+    /// What happens with rows that cannot be written.
+    pub on_error: OnError,
+}
+
+/// How the table is prepared before the first batch is written.
+///
+/// Tables are created from the schema of the first batch, with columns named according to
+/// [AppendOptions::columns]. When no batches are appended, the table is not changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppendMode {
+    /// Append into an existing table.
+    #[default]
+    Append,
+
+    /// Create the table, if it does not exist.
+    CreateIfMissing,
+
+    /// Create the table, if it does not exist, and add the columns that are missing from it.
+    /// Added columns are nullable, since the existing rows have no values for them.
+    EvolveSchema,
+
+    /// Write into a new table that replaces the existing table on [Append::finish].
+    /// The replacement is atomic in data stores that support transactional DDL.
+    Replace,
+}
+
+/// What happens with rows that cannot be written, because the data store rejected them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Return the error from [Append::append]. Errors carry [crate::ErrorContext] with the
+    /// index of the batch and, where the data store reports it, the row, column and value.
+    #[default]
+    Abort,
+
+    /// Divert the rows into [AppendSummary::rejected] and continue.
+    ///
+    /// Batches that fail are retried in halves, each within a savepoint,
+    /// until the failing rows are isolated. Not supported by DuckDB, which has no savepoints.
+    Reject,
+}
+
+/// How fields of the appended batches are matched to the columns of the table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ColumnMatch {
+    /// Fields are written into the columns at the same position.
+    /// Batches must contain all columns of the table.
+    #[default]
+    Position,
+
+    /// Fields are written into the columns with the same name.
+    /// Columns that don't have a matching field are set to their default values.
+    Name {
+        /// Names of the columns that receive the fields, by field name.
+        /// Fields that are not renamed are written into the column with the same name.
+        renames: HashMap<String, String>,
+    },
+}
+
+impl AppendOptions {
+    /// Options that match fields to columns by name.
+    pub fn by_name() -> Self {
+        AppendOptions {
+            columns: ColumnMatch::Name {
+                renames: HashMap::new(),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Returns values generated for `column` in [AppendSummary::generated_keys].
+    pub fn with_generated_keys(mut self, column: &str) -> Self {
+        self.generated_keys = Some(column.to_string());
+        self
+    }
+
+    /// Diverts rows that cannot be written into [AppendSummary::rejected].
+    pub fn with_rejected_rows(mut self) -> Self {
+        self.on_error = OnError::Reject;
+        self
+    }
+
+    /// Names of the columns that receive the fields of `schema`, in the order of the fields.
+    /// None when the fields are matched by position.
+    pub fn column_names(&self, schema: &Schema) -> Option<Vec<String>> {
+        let ColumnMatch::Name { renames } = &self.columns else {
+            return None;
+        };
+        let names = (schema.fields().iter())
+            .map(|f| renames.get(f.name()).unwrap_or(f.name()).clone())
+            .collect();
+        Some(names)
+    }
 }
 
 /// Schema introspection
 pub trait SchemaGet {
     fn table_list(&mut self) -> Result<Vec<String>, ConnectorError>;
 
+    /// Lists all relations that can be queried like a table: tables, views, materialized views,
+    /// partitioned tables, foreign and temporary tables.
+    /// MySQL does not list temporary tables.
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError>;
+
+    /// Returns schema of a relation. Works for all relations returned by [SchemaGet::relation_list].
     fn table_get(&mut self, name: &str) -> Result<SchemaRef, ConnectorError>;
 }
 
+/// A named object in the data store that can be queried like a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub name: String,
+    pub kind: RelationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// A regular table.
+    Table,
+    View,
+    MaterializedView,
+    /// A table that is split into multiple partitions.
+    Partitioned,
+    /// A table whose data is stored outside of the data store.
+    Foreign,
+    /// A table that is visible only within current session.
+    Temporary,
+}
+
 /// Schema migration
 pub trait SchemaEdit {
-    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError>;
+    /// Creates a table with default [TableOptions].
+    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
+        self.table_create_with(name, schema, &TableOptions::default())
+    }
+
+    /// Creates a table with columns of the schema and the constraints from options.
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError>;
+
+    /// Creates an empty table with the same columns as an existing table.
+    ///
+    /// Column types are copied from the DDL of the existing table, without a round trip
+    /// through Arrow types. Constraints, defaults and indexes are not necessarily copied.
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError>;
 
     fn table_drop(&mut self, name: &str) -> Result<(), TableDropError>;
+
+    /// Renames a table.
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError>;
+
+    /// Deletes all rows from a table.
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError>;
+
+    /// Adds a column to a table. Default value and comment are read from field metadata,
+    /// like in [SchemaEdit::table_create_with].
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError>;
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError>;
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError>;
+
+    /// Changes type of a column, converting existing values. Nullability is retained.
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError>;
+
+    /// Allows nulls in a column that does not allow them.
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError>;
+
+    /// Compares schema of an existing table with the desired schema and plans the steps
+    /// that migrate the table into the desired schema.
+    fn plan_migration(
+        &mut self,
+        table_name: &str,
+        desired: SchemaRef,
+    ) -> Result<MigrationPlan, ConnectorError>
+    where
+        Self: Connector + SchemaGet + Sized,
+    {
+        let current = self.table_get(table_name)?;
+        Ok(crate::migration::plan::<Self>(
+            self.type_mapper(),
+            table_name,
+            &current,
+            &desired,
+        ))
+    }
+
+    /// Renders a step of a [MigrationPlan] into DDL of this data store.
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError>;
+}
+
+/// Options of [SchemaEdit::table_create_with].
+#[derive(Debug, Clone, Default)]
+pub struct TableOptions {
+    /// Names of the columns that constitute the primary key.
+    pub primary_key: Vec<String>,
+
+    /// Sets of columns that must be unique, each producing one UNIQUE constraint.
+    pub unique: Vec<Vec<String>>,
+
+    /// Don't fail with [TableCreateError::TableExists] when the table already exists.
+    pub if_not_exists: bool,
+
+    /// Create a table that is visible only within current session.
+    ///
+    /// Not supported by SQL Server, where names of temporary tables start with `#`.
+    pub temporary: bool,
+
+    /// Default values of columns, as SQL expressions, by column name.
+    /// Takes precedence over [METADATA_DEFAULT] field metadata.
+    pub defaults: HashMap<String, String>,
+
+    /// Comments of columns, by column name.
+    /// Takes precedence over [METADATA_COMMENT] field metadata.
+    pub comments: HashMap<String, String>,
 }
 
 /// A value from the Arrow type system.
@@ -124,7 +521,7 @@ pub mod unimplemented {
             unimplemented!()
         }
 
-        fn finish(self) -> Result<(), crate::ConnectorError> {
+        fn finish(self) -> Result<super::AppendSummary, crate::ConnectorError> {
             unimplemented!()
         }
     }
@@ -0,0 +1,84 @@
+//! Cancellation of running queries and statement timeouts.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use connector_arrow::api::{Connector, Statement};
+//!
+//! # fn main() -> Result<(), connector_arrow::ConnectorError> {
+//! let conn = rusqlite::Connection::open_in_memory()?;
+//! let mut conn = connector_arrow::sqlite::SQLiteConnection::new(conn);
+//!
+//! // obtain the handle before the connection is borrowed by the statement
+//! let cancel = conn.cancel_handle()?;
+//!
+//! let mut stmt = conn.query("SELECT 1")?;
+//! let _timeout = cancel.timeout(Duration::from_secs(10));
+//! let batches = stmt.start([])?.collect::<Result<Vec<_>, _>>()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ConnectorError;
+
+/// Handle that cancels the query that is running on a connection.
+///
+/// Obtained with [crate::api::Connector::cancel_handle]. It can be cloned and sent to other
+/// threads, which can cancel the query while the connection is busy executing it.
+/// Cancelled query returns an error from the statement or the result reader.
+///
+/// Cancelling a connection that is not running a query has no effect.
+#[derive(Clone)]
+pub struct CancelHandle {
+    cancel: Arc<dyn Fn() -> Result<(), ConnectorError> + Send + Sync>,
+}
+
+impl CancelHandle {
+    /// Creates a handle that calls `cancel` to cancel the running query.
+    pub fn new(cancel: impl Fn() -> Result<(), ConnectorError> + Send + Sync + 'static) -> Self {
+        CancelHandle {
+            cancel: Arc::new(cancel),
+        }
+    }
+
+    /// Requests cancellation of the running query.
+    pub fn cancel(&self) -> Result<(), ConnectorError> {
+        (self.cancel)()
+    }
+
+    /// Cancels the running query when `timeout` elapses, unless the returned guard is dropped
+    /// before that.
+    ///
+    /// The timer runs in a separate thread.
+    pub fn timeout(&self, timeout: Duration) -> TimeoutGuard {
+        let (sender, receiver) = channel::<()>();
+        let handle = self.clone();
+        std::thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(timeout) {
+                log::debug!("statement timeout of {timeout:?} elapsed, cancelling");
+                if let Err(e) = handle.cancel() {
+                    log::warn!("cannot cancel query: {e}");
+                }
+            }
+        });
+        TimeoutGuard { _sender: sender }
+    }
+}
+
+impl std::fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancelHandle").finish_non_exhaustive()
+    }
+}
+
+/// Statement timeout, started with [CancelHandle::timeout].
+///
+/// Dropping the guard stops the timer.
+#[derive(Debug)]
+pub struct TimeoutGuard {
+    // dropping the sender wakes up the timer thread
+    _sender: Sender<()>,
+}
@@ -0,0 +1,97 @@
+//! Copying query results into tables, possibly across data stores.
+
+use std::time::{Duration, Instant};
+
+use arrow::record_batch::RecordBatch;
+
+use crate::api::{
+    Append, AppendMode, AppendOptions, AppendSummary, Connector, ResultReader, Statement,
+};
+use crate::util::coerce;
+use crate::ConnectorError;
+
+/// Options of [copy].
+#[derive(Default)]
+pub struct CopyOptions<'a> {
+    /// Options of the appender of the destination.
+    /// Use [AppendMode::CreateIfMissing] to create the table from the schema of the result.
+    pub append: AppendOptions,
+
+    /// Called after each batch is appended.
+    pub progress: Option<&'a mut dyn FnMut(&CopyProgress)>,
+}
+
+impl<'a> CopyOptions<'a> {
+    /// Calls `f` after each batch is appended.
+    pub fn with_progress(mut self, f: &'a mut dyn FnMut(&CopyProgress)) -> Self {
+        self.progress = Some(f);
+        self
+    }
+}
+
+/// Progress of a running [copy].
+#[derive(Debug, Clone, Default)]
+pub struct CopyProgress {
+    /// Number of rows appended so far.
+    pub rows: u64,
+
+    /// Number of batches appended so far.
+    pub batches: u64,
+
+    /// Size of the appended batches in Arrow memory.
+    pub bytes: u64,
+
+    /// Time since the copy was started.
+    pub elapsed: Duration,
+}
+
+/// Executes a query in `src` and appends its result into a table in `dst`.
+///
+/// Batches are appended as they are read, so the result is never held in memory as a whole.
+/// Columns of types that `dst` cannot store are converted with
+/// [coerce::coerce_schema_for] before they are appended.
+///
+/// When the result is empty, the table is still prepared according to [AppendOptions::mode]:
+/// it is created, evolved or replaced with an empty table.
+pub fn copy<S, D>(
+    src: &mut S,
+    query: &str,
+    dst: &mut D,
+    table_name: &str,
+    mut options: CopyOptions,
+) -> Result<AppendSummary, ConnectorError>
+where
+    S: Connector,
+    D: Connector,
+{
+    log::debug!("copy into {table_name}: {query}");
+    let started = Instant::now();
+
+    let mut stmt = src.query(query)?;
+    let mut reader = stmt.start([])?;
+    let schema = reader.get_schema()?;
+    let schema = coerce::coerce_schema_for::<D>(dst.type_mapper(), &schema);
+
+    let mut appender = dst.append_with(table_name, &options.append)?;
+    let mut progress = CopyProgress::default();
+    for batch in reader {
+        let batch = coerce::cast_batch(&batch?, &schema)?;
+
+        let (rows, bytes) = (batch.num_rows(), batch.get_array_memory_size());
+        appender.append(batch)?;
+
+        progress.rows += rows as u64;
+        progress.batches += 1;
+        progress.bytes += bytes as u64;
+
+        progress.elapsed = started.elapsed();
+        if let Some(f) = options.progress.as_mut() {
+            f(&progress);
+        }
+    }
+
+    if progress.batches == 0 && options.append.mode != AppendMode::Append {
+        appender.append(RecordBatch::new_empty(schema))?;
+    }
+    appender.finish()
+}
@@ -1,46 +1,140 @@
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
-use duckdb::{types::Value, Appender};
-use itertools::zip_eq;
+use duckdb::types::Value;
 use itertools::Itertools;
 
+use crate::api::{Append, AppendOptions, AppendSummary, OnError};
 use crate::impl_consume_unsupported;
 use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
-use crate::util::transport::{self, Consume, ConsumeTy};
-use crate::util::ArrayCellRef;
-use crate::{api::Append, ConnectorError};
+use crate::util::append::{write_batch, AppendStats, AppendTarget, Savepoint, WriteRows};
+use crate::util::encode::encode_batch;
+use crate::util::escape::escaped_ident;
+use crate::util::transport::{Consume, ConsumeTy};
+use crate::{ConnectorError, ErrorContext};
+
+use super::DuckDBConnection;
 
 pub struct DuckDBAppender<'conn> {
-    pub(super) inner: Appender<'conn>,
+    conn: &'conn mut DuckDBConnection,
+    target: AppendTarget,
+    stats: AppendStats,
+    committed: bool,
+}
+
+impl<'conn> DuckDBAppender<'conn> {
+    pub(super) fn new(
+        conn: &'conn mut DuckDBConnection,
+        table_name: &str,
+        options: AppendOptions,
+    ) -> Result<Self, ConnectorError> {
+        // DuckDB does not support savepoints
+        if options.on_error == OnError::Reject {
+            return Err(reject_not_supported());
+        }
+        conn.inner.execute_batch("BEGIN TRANSACTION")?;
+        Ok(DuckDBAppender {
+            conn,
+            stats: AppendStats::new(&options),
+            target: AppendTarget::new(table_name, options),
+            committed: false,
+        })
+    }
 }
 
 impl<'conn> Append<'conn> for DuckDBAppender<'conn> {
     fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        self.target.prepare(self.conn, &batch.schema())?;
+        write_batch(self, &batch, OnError::Abort)
+    }
+
+    fn finish(mut self) -> Result<AppendSummary, ConnectorError> {
+        self.target.finish(self.conn)?;
+        self.conn.inner.execute_batch("COMMIT")?;
+        self.committed = true;
+        self.stats.finish()
+    }
+}
+
+impl WriteRows for DuckDBAppender<'_> {
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError> {
         let schema = batch.schema();
-        let mut cell_refs = zip_eq(batch.columns(), schema.fields())
-            .map(|(array, field)| ArrayCellRef {
-                array,
-                field,
-                row_number: 0,
-            })
-            .collect_vec();
-
-        for row_number in 0..batch.num_rows() {
-            let mut row: Vec<Value> = Vec::new();
-
-            for cell_ref in &mut cell_refs {
-                cell_ref.row_number = row_number;
-                transport::transport(cell_ref.field, cell_ref as &_, &mut row).unwrap();
+        let table_name = &self.target.prepared_name().to_string();
+        let mut values = encode_batch::<Value>(batch)?;
+
+        let options = self.target.options();
+        let columns = options.column_names(&schema);
+        if columns.is_none() && options.generated_keys.is_none() {
+            let mut appender = self.conn.inner.appender(table_name)?;
+            for row_index in 0..batch.num_rows() {
+                let row = values.by_ref().take(batch.num_columns());
+                let row = duckdb::appender_params_from_iter(row);
+                appender
+                    .append_row(row)
+                    .map_err(|e| row_error(e, row_index))?;
             }
-            let row = duckdb::appender_params_from_iter(row);
-            self.inner.append_row(row)?;
+            appender.flush()?;
+            self.stats.rows(batch.num_rows() as u64);
+            return Ok(());
         }
 
+        // DuckDB appender cannot skip columns or return values, so INSERT statements are used
+        let column_list = columns
+            .map(|c| format!(" ({})", c.iter().map(|c| escaped_ident(c)).join(",")))
+            .unwrap_or_default();
+        let returning = (options.generated_keys.as_ref())
+            .map(|c| format!(" RETURNING {}::BIGINT", escaped_ident(c)))
+            .unwrap_or_default();
+        let query = format!(
+            "INSERT INTO {}{column_list} VALUES ({}){returning}",
+            escaped_ident(table_name),
+            (0..batch.num_columns()).map(|_| "?").join(","),
+        );
+        let mut stmt = self.conn.inner.prepare_cached(&query)?;
+        for row_index in 0..batch.num_rows() {
+            let row = duckdb::params_from_iter(values.by_ref().take(batch.num_columns()));
+            if returning.is_empty() {
+                stmt.execute(row).map_err(|e| row_error(e, row_index))?;
+            } else {
+                let mut keys = stmt.query(row).map_err(|e| row_error(e, row_index))?;
+                while let Some(key) = keys.next()? {
+                    self.stats.generated_key(key.get(0)?);
+                }
+            }
+            self.stats.rows(1);
+        }
         Ok(())
     }
 
-    fn finish(self) -> Result<(), ConnectorError> {
-        Ok(())
+    fn savepoint(&mut self, _command: Savepoint) -> Result<(), ConnectorError> {
+        Err(reject_not_supported())
+    }
+
+    fn stats(&mut self) -> &mut AppendStats {
+        &mut self.stats
+    }
+}
+
+fn reject_not_supported() -> ConnectorError {
+    ConnectorError::NotSupported {
+        connector_name: "DuckDB",
+        feature: "rejecting rows of appends",
+    }
+}
+
+fn row_error(err: duckdb::Error, row: usize) -> ConnectorError {
+    ConnectorError::from(err).with_context(ErrorContext {
+        row: Some(row),
+        ..Default::default()
+    })
+}
+
+impl Drop for DuckDBAppender<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = self.conn.inner.execute_batch("ROLLBACK") {
+                log::warn!("cannot roll back unfinished append: {e}");
+            }
+        }
     }
 }
 
@@ -6,23 +6,34 @@ mod schema;
 #[doc(hidden)]
 pub use append::DuckDBAppender;
 
-use arrow::datatypes::{DataType, TimeUnit};
+use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
 use arrow::record_batch::RecordBatch;
 use itertools::Itertools;
 
 use std::sync::Arc;
 
-use crate::api::{Connector, ResultReader, Statement};
-use crate::errors::ConnectorError;
-use crate::util::{transport, ArrayCellRef};
+use crate::api::{AppendOptions, Connector, ResultReader, Statement, TypeMapper};
+use crate::cancel::CancelHandle;
+use crate::errors::{ConnectorError, ErrorKind};
+use crate::util::{transport, ArrayCellRef, CastReader};
 
 pub struct DuckDBConnection {
     inner: duckdb::Connection,
+    type_mapper: Option<Arc<dyn TypeMapper>>,
 }
 
 impl DuckDBConnection {
     pub fn new(inner: duckdb::Connection) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            type_mapper: None,
+        }
+    }
+
+    /// Overrides the built-in type mapping for this connection.
+    pub fn with_type_mapper(mut self, mapper: impl TypeMapper + 'static) -> Self {
+        self.type_mapper = Some(Arc::new(mapper));
+        self
     }
 
     pub fn unwrap(self) -> duckdb::Connection {
@@ -34,6 +45,20 @@ impl DuckDBConnection {
     }
 }
 
+pub(crate) fn error_kind(err: &duckdb::Error) -> ErrorKind {
+    // DuckDB reports all errors with the same code, but prefixes messages with error type
+    let message = err.to_string();
+    let (ty, message) = message.split_once(" Error: ").unwrap_or(("", &message));
+    match ty {
+        "Constraint" | "Catalog" | "Binder" | "TransactionContext" => {
+            crate::util::error_message::error_kind_from_message(message)
+        }
+        "Parser" => ErrorKind::SyntaxError,
+        "INTERRUPT" => ErrorKind::Cancelled,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl Connector for DuckDBConnection {
     type Stmt<'conn>
         = DuckDBStatement<'conn>
@@ -48,13 +73,18 @@ impl Connector for DuckDBConnection {
     fn query<'a>(&'a mut self, query: &str) -> Result<Self::Stmt<'a>, ConnectorError> {
         let stmt = self.inner.prepare(query)?;
 
-        Ok(DuckDBStatement { stmt })
-    }
-    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError> {
-        Ok(DuckDBAppender {
-            inner: self.inner.appender(table_name)?,
+        Ok(DuckDBStatement {
+            stmt,
+            type_mapper: self.type_mapper.as_deref(),
         })
     }
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError> {
+        DuckDBAppender::new(self, table_name, options.clone())
+    }
 
     fn type_db_into_arrow(database_ty: &str) -> Option<DataType> {
         Some(match database_ty {
@@ -113,23 +143,37 @@ impl Connector for DuckDBConnection {
             DataType::Binary | DataType::FixedSizeBinary(_) | DataType::LargeBinary => "BLOB",
             DataType::Utf8 | DataType::LargeUtf8 => "VARCHAR",
 
-            DataType::Decimal128(_, _) => todo!(),
-            DataType::Decimal256(_, _) => todo!(),
-
             _ => return None,
         };
         Some(s.to_string())
     }
+
+    fn quote_ident(ident: &str) -> String {
+        crate::util::escape::escaped_ident(ident).to_string()
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        self.type_mapper.as_deref()
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        // duckdb crate does not expose interrupting a connection
+        Err(ConnectorError::NotSupported {
+            connector_name: "DuckDB",
+            feature: "query cancellation",
+        })
+    }
 }
 
 #[doc(hidden)]
 pub struct DuckDBStatement<'conn> {
     stmt: duckdb::Statement<'conn>,
+    type_mapper: Option<&'conn dyn TypeMapper>,
 }
 
 impl<'conn> Statement<'conn> for DuckDBStatement<'conn> {
     type Reader<'stmt>
-        = DuckDBReader<'stmt>
+        = CastReader<DuckDBReader<'stmt>>
     where
         Self: 'stmt;
 
@@ -147,7 +191,16 @@ impl<'conn> Statement<'conn> for DuckDBStatement<'conn> {
 
         // query
         let arrow = self.stmt.query_arrow(args.as_slice())?;
-        Ok(DuckDBReader { arrow })
+        let mapped_schema = map_schema(self.type_mapper, &arrow.get_schema());
+        Ok(CastReader::new(DuckDBReader { arrow }, mapped_schema))
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        self.start_batch(args)?.cast_into(expected)
     }
 }
 
@@ -169,3 +222,18 @@ impl Iterator for DuckDBReader<'_> {
         self.arrow.next().map(Ok)
     }
 }
+
+/// Applies the mapper to a schema of a result, which is already produced in arrow types.
+/// Database type names are reconstructed from the arrow types.
+fn map_schema(
+    mapper: Option<&dyn TypeMapper>,
+    schema: &arrow::datatypes::Schema,
+) -> Option<arrow::datatypes::SchemaRef> {
+    let db_types = (schema.fields().iter())
+        .map(|f| match f.data_type() {
+            DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => "DECIMAL".to_string(),
+            ty => DuckDBConnection::type_arrow_into_db(ty).unwrap_or_default(),
+        })
+        .collect_vec();
+    crate::util::map_schema(mapper, schema, db_types.iter().map(String::as_str))
+}
@@ -1,9 +1,17 @@
-use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
-use itertools::Itertools;
+use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::api::{SchemaEdit, SchemaGet};
-use crate::util::escape::escaped_ident;
-use crate::{ConnectorError, TableCreateError, TableDropError};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::api::{Relation, RelationKind, SchemaEdit, SchemaGet, TableOptions, TypeMapper};
+use crate::migration::MigrationStep;
+use crate::util::ddl::{
+    column_comment, column_default, table_constraints, with_column_comment, with_column_default,
+};
+use crate::util::escape::{escaped_ident, escaped_literal};
+use crate::{
+    ColumnEditError, ConnectorError, ErrorKind, TableCreateError, TableDropError, TableEditError,
+};
 
 use super::DuckDBConnection;
 
@@ -21,41 +29,139 @@ impl SchemaGet for DuckDBConnection {
         Ok(table_names)
     }
 
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        let query = "
+            SELECT table_name, FALSE, temporary
+            FROM duckdb_tables()
+            WHERE (database_name = current_database() OR temporary) AND schema_name = current_schema()
+            UNION ALL
+            SELECT view_name, TRUE, temporary
+            FROM duckdb_views()
+            WHERE (database_name = current_database() OR temporary) AND schema_name = current_schema()
+                AND NOT internal
+            ORDER BY 1;
+        ";
+        let mut statement = self.inner.prepare(query)?;
+        let mut res = statement.query([])?;
+
+        let mut relations = Vec::new();
+        while let Some(row) = res.next()? {
+            let name: String = row.get(0)?;
+            let is_view: bool = row.get(1)?;
+            let is_temporary: bool = row.get(2)?;
+
+            let kind = match (is_view, is_temporary) {
+                (true, _) => RelationKind::View,
+                (false, true) => RelationKind::Temporary,
+                (false, false) => RelationKind::Table,
+            };
+            relations.push(Relation { name, kind });
+        }
+        Ok(relations)
+    }
+
     fn table_get(&mut self, name: &str) -> Result<arrow::datatypes::SchemaRef, ConnectorError> {
         let query_schema = format!("SELECT * FROM {} WHERE FALSE;", escaped_ident(name));
         let mut statement = self.inner.prepare(&query_schema)?;
         let results = statement.query_arrow([])?;
 
-        Ok(results.get_schema())
+        let schema = results.get_schema();
+
+        // arrow result schema reports all columns as nullable and does not contain defaults
+        // temporary tables shadow the tables in current schema
+        let query_columns = "
+            SELECT database_name, column_name, is_nullable, column_default, comment
+            FROM duckdb_columns()
+            WHERE table_name = ? AND (
+                database_name = 'temp' OR
+                (database_name = current_database() AND schema_name = current_schema())
+            )
+            ORDER BY database_name = 'temp' DESC;
+        ";
+        let mut statement = self.inner.prepare(query_columns)?;
+        let columns = statement
+            .query_map([name], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<(String, String, bool, Option<String>, Option<String>)>, _>>()?;
+        let database = columns.first().map(|(db, ..)| db.clone());
+        let columns: HashMap<_, _> = (columns.into_iter())
+            .filter(|(db, ..)| Some(db) == database.as_ref())
+            .map(|(_, column, nullable, default, comment)| (column, (nullable, default, comment)))
+            .collect();
+        let fields: Vec<_> = (schema.fields().iter())
+            .map(|f| {
+                let (nullable, default, comment) =
+                    columns.get(f.name()).cloned().unwrap_or((true, None, None));
+                let field = f.as_ref().clone().with_nullable(nullable);
+                let field = with_column_default(field, default, None);
+                with_column_comment(field, comment)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+        let mapped = super::map_schema(self.type_mapper.as_deref(), &schema);
+        Ok(mapped.unwrap_or(schema))
     }
 }
 
 impl SchemaEdit for DuckDBConnection {
-    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError> {
         let column_defs = schema
             .fields()
             .iter()
-            .map(|field| {
-                let ty = ty_from_arrow(field.data_type());
-
-                let is_nullable =
-                    field.is_nullable() || matches!(field.data_type(), DataType::Null);
-                let not_null = if is_nullable { "" } else { " NOT NULL" };
-
-                let name = escaped_ident(field.name());
-                format!("{name} {ty}{not_null}")
-            })
+            .map(|field| column_def(self.type_mapper.as_deref(), name, field, options))
+            .collect::<Result<Vec<_>, _>>()?
             .join(",");
+        let constraints = table_constraints(options, escaped_ident);
 
-        let ddl = format!("CREATE TABLE {} ({column_defs});", escaped_ident(name));
+        let temporary = if options.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if options.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let mut ddl = format!(
+            "CREATE{temporary} TABLE{if_not_exists} {} ({column_defs}{constraints});",
+            escaped_ident(name)
+        );
+        for field in schema.fields() {
+            if let Some(comment) = column_comment(field, options) {
+                ddl += &comment_on_column(name, field.name(), comment);
+            }
+        }
 
-        let res = self.inner.execute(&ddl, []);
+        let res = self.inner.execute_batch(&ddl);
         match res {
             Ok(_) => Ok(()),
-            Err(e)
-                if e.to_string().starts_with("Catalog Error: Table with name")
-                    && e.to_string().contains("already exists!") =>
-            {
+            Err(e) if super::error_kind(&e) == ErrorKind::TableExists => {
+                Err(TableCreateError::TableExists)
+            }
+            Err(e) => Err(TableCreateError::Connector(ConnectorError::DuckDB(e))),
+        }
+    }
+
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        let ddl = format!(
+            "CREATE TABLE {} AS SELECT * FROM {} LIMIT 0;",
+            escaped_ident(name),
+            escaped_ident(like)
+        );
+        let res = self.inner.execute_batch(&ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if super::error_kind(&e) == ErrorKind::TableExists => {
                 Err(TableCreateError::TableExists)
             }
             Err(e) => Err(TableCreateError::Connector(ConnectorError::DuckDB(e))),
@@ -70,19 +176,227 @@ impl SchemaEdit for DuckDBConnection {
 
         match res {
             Ok(_) => Ok(()),
-            Err(e)
-                if e.to_string().starts_with("Catalog Error: Table with name ")
-                    && e.to_string().contains("does not exist!") =>
-            {
+            Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
                 Err(TableDropError::TableNonexistent)
             }
             Err(e) => Err(TableDropError::Connector(e.into())),
         }
     }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME TO {};",
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        self.table_edit(&ddl, Some(new_name))
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        let ddl = format!("TRUNCATE {};", escaped_ident(name));
+        self.table_edit(&ddl, None)
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_add(self.type_mapper.as_deref(), table_name, field)?;
+        self.column_edit(&ddl)
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        self.column_edit(&ddl_column_drop(table_name, column_name))
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {};",
+            escaped_ident(table_name),
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        self.column_edit(&ddl)
+    }
+
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        let mapper = self.type_mapper.as_deref();
+        let ddl = ddl_column_alter_type(mapper, table_name, column_name, data_type)?;
+        self.column_edit(&ddl)
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        self.column_edit(&ddl_column_drop_not_null(table_name, column_name))
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        let mapper = self.type_mapper.as_deref();
+        Ok(match step {
+            MigrationStep::ColumnAdd(field) => ddl_column_add(mapper, table_name, field)?,
+            MigrationStep::ColumnDrop(name) => ddl_column_drop(table_name, name),
+            MigrationStep::ColumnWiden(field) => {
+                ddl_column_alter_type(mapper, table_name, field.name(), field.data_type())?
+            }
+            MigrationStep::ColumnDropNotNull(field) => {
+                ddl_column_drop_not_null(table_name, field.name())
+            }
+        })
+    }
+}
+
+impl DuckDBConnection {
+    /// Executes DDL that edits a table. `new_name` is the name the table is renamed to, if any.
+    fn table_edit(&mut self, ddl: &str, new_name: Option<&str>) -> Result<(), TableEditError> {
+        let res = self.inner.execute_batch(ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
+                Err(TableEditError::TableNonexistent)
+            }
+            Err(e) if new_name.is_some_and(|name| is_rename_conflict(&e, name)) => {
+                Err(TableEditError::TableExists)
+            }
+            Err(e) => Err(TableEditError::Connector(e.into())),
+        }
+    }
+
+    fn column_edit(&mut self, ddl: &str) -> Result<(), ColumnEditError> {
+        let res = self.inner.execute_batch(ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
+                Err(ColumnEditError::TableNonexistent)
+            }
+            Err(e)
+                if e.to_string()
+                    .starts_with("Catalog Error: Column with name ")
+                    && e.to_string().contains("already exists") =>
+            {
+                Err(ColumnEditError::ColumnExists)
+            }
+            Err(e) if super::error_kind(&e) == ErrorKind::UndefinedColumn => {
+                Err(ColumnEditError::ColumnNonexistent)
+            }
+            Err(e) => Err(ColumnEditError::Connector(e.into())),
+        }
+    }
 }
 
-fn ty_from_arrow(data_type: &DataType) -> &'static str {
-    match data_type {
+/// Matches the catalog error of renaming an entry to `name`, which is taken by another entry.
+fn is_rename_conflict(err: &duckdb::Error, name: &str) -> bool {
+    let message = err.to_string();
+    message.starts_with("Catalog Error: Could not rename ")
+        && message.ends_with(&format!(
+            " to \"{name}\": another entry with this name already exists!"
+        ))
+}
+
+fn column_def(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+    options: &TableOptions,
+) -> Result<String, ConnectorError> {
+    let ty = db_type_of(mapper, table_name, field.name(), field.data_type())?;
+
+    let is_nullable = field.is_nullable() || matches!(field.data_type(), DataType::Null);
+    let not_null = if is_nullable { "" } else { " NOT NULL" };
+
+    let default = column_default(field, options)
+        .map(|d| format!(" DEFAULT {d}"))
+        .unwrap_or_default();
+
+    let name = escaped_ident(field.name());
+    Ok(format!("{name} {ty}{not_null}{default}"))
+}
+
+fn ddl_column_add(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    let options = TableOptions::default();
+    let mut ddl = format!(
+        "ALTER TABLE {} ADD COLUMN {};",
+        escaped_ident(table_name),
+        column_def(mapper, table_name, field, &options)?
+    );
+    if let Some(comment) = column_comment(field, &options) {
+        ddl += &comment_on_column(table_name, field.name(), comment);
+    }
+    Ok(ddl)
+}
+
+fn ddl_column_drop(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP COLUMN {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn ddl_column_alter_type(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    column_name: &str,
+    data_type: &DataType,
+) -> Result<String, ConnectorError> {
+    Ok(format!(
+        "ALTER TABLE {} ALTER COLUMN {} TYPE {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name),
+        db_type_of(mapper, table_name, column_name, data_type)?
+    ))
+}
+
+fn ddl_column_drop_not_null(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL;",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn comment_on_column(table_name: &str, column_name: &str, comment: &str) -> String {
+    format!(
+        "COMMENT ON COLUMN {}.{} IS {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name),
+        escaped_literal(comment)
+    )
+}
+
+fn db_type_of(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    column_name: &str,
+    data_type: &DataType,
+) -> Result<String, ConnectorError> {
+    mapper
+        .and_then(|m| m.arrow_into_db(column_name, data_type))
+        .or_else(|| ty_from_arrow(data_type).map(str::to_string))
+        .ok_or_else(|| {
+            crate::util::unsupported_column_type("DuckDB", table_name, column_name, data_type)
+        })
+}
+
+fn ty_from_arrow(data_type: &DataType) -> Option<&'static str> {
+    Some(match data_type {
         // there is no Null type in DuckDB, so we fallback to some other type that is nullable
         DataType::Null => "BIGINT",
 
@@ -102,30 +416,11 @@ fn ty_from_arrow(data_type: &DataType) -> &'static str {
         DataType::Timestamp(TimeUnit::Microsecond, _) => "TIMESTAMP",
         DataType::Timestamp(TimeUnit::Millisecond, _) => "BIGINT",
         DataType::Timestamp(TimeUnit::Second, _) => "BIGINT",
-        DataType::Date32 => unimplemented!(),
-        DataType::Date64 => unimplemented!(),
-        DataType::Time32(_) => unimplemented!(),
-        DataType::Time64(_) => unimplemented!(),
-        DataType::Duration(_) => unimplemented!(),
-        DataType::Interval(_) => unimplemented!(),
         DataType::Binary => "BLOB",
         DataType::FixedSizeBinary(_) => "BLOB",
         DataType::LargeBinary => "BLOB",
         DataType::Utf8 => "VARCHAR",
         DataType::LargeUtf8 => "VARCHAR",
-        DataType::List(_) => unimplemented!(),
-        DataType::FixedSizeList(_, _) => unimplemented!(),
-        DataType::LargeList(_) => unimplemented!(),
-        DataType::Struct(_) => unimplemented!(),
-        DataType::Union(_, _) => unimplemented!(),
-        DataType::Dictionary(_, _) => unimplemented!(),
-        DataType::Decimal128(_, _) => unimplemented!(),
-        DataType::Decimal256(_, _) => unimplemented!(),
-        DataType::Map(_, _) => unimplemented!(),
-        DataType::RunEndEncoded(_, _) => unimplemented!(),
-        DataType::BinaryView => todo!(),
-        DataType::Utf8View => todo!(),
-        DataType::ListView(_) => todo!(),
-        DataType::LargeListView(_) => todo!(),
-    }
+        _ => return None,
+    })
 }
@@ -0,0 +1,399 @@
+//! Object-safe variants of the traits in [crate::api], for selecting the data store at runtime.
+//!
+//! [Connector] uses generic associated types, so it cannot be used as a trait object.
+//! [DynConnector] is implemented for every connection that implements [Connector],
+//! [SchemaGet] and [SchemaEdit], and returns boxed statements, readers and appenders.
+//!
+//! ```
+//! use connector_arrow::api::Statement;
+//! use connector_arrow::dynamic::DynConnector;
+//!
+//! # fn main() -> Result<(), connector_arrow::ConnectorError> {
+//! let conn = rusqlite::Connection::open_in_memory()?;
+//! let mut conn: Box<dyn DynConnector> =
+//!     Box::new(connector_arrow::sqlite::SQLiteConnection::new(conn));
+//!
+//! let mut stmt = conn.query("SELECT 1 as a")?;
+//! let batches = stmt.start([])?.collect::<Result<Vec<_>, _>>()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::api::{
+    Append, AppendOptions, AppendSummary, Connector, Relation, ResultReader, SchemaEdit, SchemaGet,
+    Statement, TableOptions, TypeMapper,
+};
+use crate::cancel::CancelHandle;
+use crate::errors::{
+    AppendCheckError, ColumnEditError, ConnectorError, TableCreateError, TableDropError,
+    TableEditError,
+};
+use crate::migration::{AppendPlan, MigrationPlan, MigrationStep};
+
+/// Object-safe variant of [Connector], [SchemaGet] and [SchemaEdit].
+///
+/// Returned statements implement [Statement] and appenders implement [Append].
+pub trait DynConnector {
+    /// See [Connector::query].
+    fn query<'a>(
+        &'a mut self,
+        query: &str,
+    ) -> Result<Box<dyn DynStatement<'a> + 'a>, ConnectorError>;
+
+    /// See [Connector::append].
+    fn append<'a>(
+        &'a mut self,
+        table_name: &str,
+    ) -> Result<Box<dyn DynAppend + 'a>, ConnectorError>;
+
+    /// See [Connector::append_with].
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Box<dyn DynAppend + 'a>, ConnectorError>;
+
+    /// See [Connector::type_db_into_arrow].
+    fn type_db_into_arrow(&self, database_ty: &str) -> Option<DataType>;
+
+    /// See [Connector::type_arrow_into_db].
+    fn type_arrow_into_db(&self, ty: &DataType) -> Option<String>;
+
+    /// See [Connector::type_mapper].
+    fn type_mapper(&self) -> Option<&dyn TypeMapper>;
+
+    /// See [Connector::cancel_handle].
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError>;
+
+    /// See [Connector::check_append].
+    fn check_append(
+        &mut self,
+        table_name: &str,
+        schema: &Schema,
+    ) -> Result<AppendPlan, AppendCheckError>;
+
+    /// See [SchemaGet::table_list].
+    fn table_list(&mut self) -> Result<Vec<String>, ConnectorError>;
+
+    /// See [SchemaGet::relation_list].
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError>;
+
+    /// See [SchemaGet::table_get].
+    fn table_get(&mut self, name: &str) -> Result<SchemaRef, ConnectorError>;
+
+    /// See [SchemaEdit::table_create].
+    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError>;
+
+    /// See [SchemaEdit::table_create_with].
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError>;
+
+    /// See [SchemaEdit::table_create_like].
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError>;
+
+    /// See [SchemaEdit::table_drop].
+    fn table_drop(&mut self, name: &str) -> Result<(), TableDropError>;
+
+    /// See [SchemaEdit::table_rename].
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError>;
+
+    /// See [SchemaEdit::table_truncate].
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError>;
+
+    /// See [SchemaEdit::column_add].
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError>;
+
+    /// See [SchemaEdit::column_drop].
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError>;
+
+    /// See [SchemaEdit::column_rename].
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError>;
+
+    /// See [SchemaEdit::column_alter_type].
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError>;
+
+    /// See [SchemaEdit::column_drop_not_null].
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError>;
+
+    /// See [SchemaEdit::plan_migration].
+    fn plan_migration(
+        &mut self,
+        table_name: &str,
+        desired: SchemaRef,
+    ) -> Result<MigrationPlan, ConnectorError>;
+
+    /// See [SchemaEdit::migration_step_ddl].
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError>;
+}
+
+/// Object-safe variant of [Statement].
+///
+/// `Box<dyn DynStatement>` implements [Statement], so it can be used with
+/// [Statement::start] and [Statement::start_with_schema].
+pub trait DynStatement<'conn> {
+    /// See [Statement::start_batch].
+    fn start_batch_dyn<'s>(
+        &'s mut self,
+        args: (&RecordBatch, usize),
+    ) -> Result<Box<dyn ResultReader<'s> + 's>, ConnectorError>
+    where
+        'conn: 's;
+
+    /// See [Statement::start_batch_with_schema].
+    fn start_batch_with_schema_dyn<'s>(
+        &'s mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Box<dyn ResultReader<'s> + 's>, ConnectorError>
+    where
+        'conn: 's;
+}
+
+/// Object-safe variant of [Append].
+///
+/// `Box<dyn DynAppend>` implements [Append].
+pub trait DynAppend {
+    /// See [Append::append].
+    fn append_dyn(&mut self, batch: RecordBatch) -> Result<(), ConnectorError>;
+
+    /// See [Append::finish].
+    fn finish_dyn(self: Box<Self>) -> Result<AppendSummary, ConnectorError>;
+}
+
+impl<C> DynConnector for C
+where
+    C: Connector + SchemaGet + SchemaEdit,
+{
+    fn query<'a>(
+        &'a mut self,
+        query: &str,
+    ) -> Result<Box<dyn DynStatement<'a> + 'a>, ConnectorError> {
+        Ok(Box::new(Connector::query(self, query)?))
+    }
+
+    fn append<'a>(
+        &'a mut self,
+        table_name: &str,
+    ) -> Result<Box<dyn DynAppend + 'a>, ConnectorError> {
+        Ok(Box::new(Connector::append(self, table_name)?))
+    }
+
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Box<dyn DynAppend + 'a>, ConnectorError> {
+        Ok(Box::new(Connector::append_with(self, table_name, options)?))
+    }
+
+    fn type_db_into_arrow(&self, database_ty: &str) -> Option<DataType> {
+        C::type_db_into_arrow(database_ty)
+    }
+
+    fn type_arrow_into_db(&self, ty: &DataType) -> Option<String> {
+        C::type_arrow_into_db(ty)
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        Connector::type_mapper(self)
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        Connector::cancel_handle(self)
+    }
+
+    fn check_append(
+        &mut self,
+        table_name: &str,
+        schema: &Schema,
+    ) -> Result<AppendPlan, AppendCheckError> {
+        Connector::check_append(self, table_name, schema)
+    }
+
+    fn table_list(&mut self) -> Result<Vec<String>, ConnectorError> {
+        SchemaGet::table_list(self)
+    }
+
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        SchemaGet::relation_list(self)
+    }
+
+    fn table_get(&mut self, name: &str) -> Result<SchemaRef, ConnectorError> {
+        SchemaGet::table_get(self, name)
+    }
+
+    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
+        SchemaEdit::table_create(self, name, schema)
+    }
+
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError> {
+        SchemaEdit::table_create_with(self, name, schema, options)
+    }
+
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        SchemaEdit::table_create_like(self, name, like)
+    }
+
+    fn table_drop(&mut self, name: &str) -> Result<(), TableDropError> {
+        SchemaEdit::table_drop(self, name)
+    }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        SchemaEdit::table_rename(self, old_name, new_name)
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        SchemaEdit::table_truncate(self, name)
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        SchemaEdit::column_add(self, table_name, field)
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        SchemaEdit::column_drop(self, table_name, column_name)
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        SchemaEdit::column_rename(self, table_name, old_name, new_name)
+    }
+
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        SchemaEdit::column_alter_type(self, table_name, column_name, data_type)
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        SchemaEdit::column_drop_not_null(self, table_name, column_name)
+    }
+
+    fn plan_migration(
+        &mut self,
+        table_name: &str,
+        desired: SchemaRef,
+    ) -> Result<MigrationPlan, ConnectorError> {
+        SchemaEdit::plan_migration(self, table_name, desired)
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        SchemaEdit::migration_step_ddl(self, table_name, step)
+    }
+}
+
+impl<'conn, S: Statement<'conn>> DynStatement<'conn> for S {
+    fn start_batch_dyn<'s>(
+        &'s mut self,
+        args: (&RecordBatch, usize),
+    ) -> Result<Box<dyn ResultReader<'s> + 's>, ConnectorError>
+    where
+        'conn: 's,
+    {
+        Ok(Box::new(self.start_batch(args)?))
+    }
+
+    fn start_batch_with_schema_dyn<'s>(
+        &'s mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Box<dyn ResultReader<'s> + 's>, ConnectorError>
+    where
+        'conn: 's,
+    {
+        Ok(Box::new(self.start_batch_with_schema(args, expected)?))
+    }
+}
+
+impl<'conn> Statement<'conn> for Box<dyn DynStatement<'conn> + 'conn> {
+    type Reader<'stmt>
+        = Box<dyn ResultReader<'stmt> + 'stmt>
+    where
+        Self: 'stmt;
+
+    fn start_batch(
+        &mut self,
+        args: (&RecordBatch, usize),
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        self.as_mut().start_batch_dyn(args)
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        self.as_mut().start_batch_with_schema_dyn(args, expected)
+    }
+}
+
+impl<'stmt> ResultReader<'stmt> for Box<dyn ResultReader<'stmt> + 'stmt> {
+    fn get_schema(&mut self) -> Result<SchemaRef, ConnectorError> {
+        self.as_mut().get_schema()
+    }
+}
+
+impl<'conn, A: Append<'conn>> DynAppend for A {
+    fn append_dyn(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        self.append(batch)
+    }
+
+    fn finish_dyn(self: Box<Self>) -> Result<AppendSummary, ConnectorError> {
+        (*self).finish()
+    }
+}
+
+impl<'conn> Append<'conn> for Box<dyn DynAppend + 'conn> {
+    fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        self.as_mut().append_dyn(batch)
+    }
+
+    fn finish(self) -> Result<AppendSummary, ConnectorError> {
+        self.finish_dyn()
+    }
+}
@@ -1,7 +1,10 @@
 use std::string::FromUtf8Error;
 
+use itertools::Itertools;
 use thiserror::Error;
 
+use crate::migration::{incompatibility_message, Incompatibility};
+
 /// Errors that can be raised from this library.
 #[derive(Error, Debug)]
 pub enum ConnectorError {
@@ -14,28 +17,44 @@ pub enum ConnectorError {
     #[error("Schema of the result cannot be inferred or converted to Arrow schema")]
     CannotConvertSchema,
 
-    #[error("Result data does not match the schema")]
+    #[error("Result data does not match the schema: {0}")]
     DataSchemaMismatch(String),
 
-    #[error("Schema is not compatible with Arrow data types")]
+    #[error("Schema is not compatible with Arrow data types: {message}")]
     IncompatibleSchema {
         table_name: String,
         message: String,
         hint: Option<String>,
     },
 
+    #[error("Table {table_name} already exists")]
+    TableExists { table_name: String },
+
     #[error("When converting values from database representation into into an Arrow types, it fell out of supported range.")]
     DataOutOfRange,
 
+    /// An error about a specific value, with its location.
+    #[error("{source} ({context})")]
+    WithContext {
+        context: ErrorContext,
+        source: Box<ConnectorError>,
+    },
+
     #[error("{connector_name} does not support {feature}")]
     NotSupported {
         connector_name: &'static str,
         feature: &'static str,
     },
 
+    #[error("Invalid connection URL: {0}")]
+    InvalidUrl(String),
+
     #[error(transparent)]
     UrlEncoding(#[from] FromUtf8Error),
 
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
+
     #[error(transparent)]
     Arrow(#[from] arrow::error::ArrowError),
 
@@ -60,6 +79,149 @@ pub enum ConnectorError {
     Tiberius(#[from] tiberius::error::Error),
 }
 
+impl ConnectorError {
+    /// Attaches the location of the offending value to this error.
+    /// When the error already has a context, its unknown parts are filled in.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            ConnectorError::WithContext {
+                context: current,
+                source,
+            } => ConnectorError::WithContext {
+                context: ErrorContext {
+                    batch: current.batch.or(context.batch),
+                    row: current.row.or(context.row),
+                    column: current.column.or(context.column),
+                    value: current.value.or(context.value),
+                },
+                source,
+            },
+            source => ConnectorError::WithContext {
+                context,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Location of the value that caused this error, if known.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ConnectorError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Classifies the error, regardless of the database it originated from.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ConnectorError::WithContext { source, .. } => source.kind(),
+            ConnectorError::TableExists { .. } => ErrorKind::TableExists,
+            #[cfg(feature = "src_sqlite")]
+            ConnectorError::SQLite(e) => crate::sqlite::error_kind(e),
+            #[cfg(feature = "src_duckdb")]
+            ConnectorError::DuckDB(e) => crate::duckdb::error_kind(e),
+            #[cfg(feature = "src_postgres")]
+            ConnectorError::Postgres(e) => crate::postgres::error_kind(e),
+            #[cfg(feature = "src_mysql")]
+            ConnectorError::MySQL(e) => crate::mysql::error_kind(e),
+            #[cfg(feature = "src_tiberius")]
+            ConnectorError::Tiberius(e) => crate::tiberius::error_kind(e),
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Five-character SQLSTATE code, reported by PostgreSQL and MySQL.
+    pub fn sql_state(&self) -> Option<&str> {
+        match self {
+            ConnectorError::WithContext { source, .. } => source.sql_state(),
+            #[cfg(feature = "src_postgres")]
+            ConnectorError::Postgres(crate::postgres::PostgresError::Postgres(e)) => {
+                e.code().map(|c| c.code())
+            }
+            #[cfg(feature = "src_mysql")]
+            ConnectorError::MySQL(mysql::Error::MySqlError(e)) => Some(&e.state),
+            _ => None,
+        }
+    }
+
+    /// Database-specific error code, reported by SQLite (extended result code),
+    /// MySQL and Microsoft SQL Server.
+    pub fn vendor_code(&self) -> Option<i64> {
+        match self {
+            ConnectorError::WithContext { source, .. } => source.vendor_code(),
+            #[cfg(feature = "src_sqlite")]
+            ConnectorError::SQLite(e) => e.sqlite_error().map(|e| e.extended_code as i64),
+            #[cfg(feature = "src_mysql")]
+            ConnectorError::MySQL(mysql::Error::MySqlError(e)) => Some(e.code as i64),
+            #[cfg(feature = "src_tiberius")]
+            ConnectorError::Tiberius(tiberius::error::Error::Server(e)) => Some(e.code() as i64),
+            _ => None,
+        }
+    }
+}
+
+/// Location of the value that caused an error, attached with [ConnectorError::WithContext].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Index of the appended batch, starting at 0.
+    pub batch: Option<usize>,
+
+    /// Index of the row within the batch.
+    pub row: Option<usize>,
+
+    /// Name of the column.
+    pub column: Option<String>,
+
+    /// The offending value, formatted for display.
+    pub value: Option<String>,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = [
+            self.batch.map(|b| format!("batch {b}")),
+            self.row.map(|r| format!("row {r}")),
+            self.column.as_ref().map(|c| format!("column {c}")),
+            self.value.as_ref().map(|v| format!("value {v}")),
+        ];
+        write!(f, "{}", parts.iter().flatten().join(", "))
+    }
+}
+
+/// Database-independent classification of errors, returned by [ConnectorError::kind].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A unique or primary key constraint was violated.
+    UniqueViolation,
+    /// A foreign key constraint was violated.
+    ForeignKeyViolation,
+    /// NULL was written into a column that does not allow it.
+    NotNullViolation,
+    /// Referenced table does not exist.
+    UndefinedTable,
+    /// Referenced column does not exist.
+    UndefinedColumn,
+    /// Created table already exists.
+    TableExists,
+    /// Query could not be parsed.
+    SyntaxError,
+    /// Transaction could not be serialized with concurrent transactions and can be retried.
+    SerializationFailure,
+    /// Transaction was aborted because of a deadlock and can be retried.
+    Deadlock,
+    /// Connection to the database was closed or broken.
+    ConnectionLost,
+    /// A statement or lock timeout has elapsed.
+    Timeout,
+    /// Database or table is locked by another connection and the operation can be retried.
+    Busy,
+    /// Query was cancelled, for example with [crate::cancel::CancelHandle].
+    Cancelled,
+    /// Any other error, including errors raised by this library.
+    Other,
+}
+
 #[derive(Error, Debug)]
 pub enum TableCreateError {
     #[error("Table already exists")]
@@ -77,3 +239,39 @@ pub enum TableDropError {
     #[error(transparent)]
     Connector(#[from] ConnectorError),
 }
+
+#[derive(Error, Debug)]
+pub enum ColumnEditError {
+    #[error("Table does not exist")]
+    TableNonexistent,
+
+    #[error("Column already exists")]
+    ColumnExists,
+
+    #[error("Column does not exist")]
+    ColumnNonexistent,
+
+    #[error(transparent)]
+    Connector(#[from] ConnectorError),
+}
+
+#[derive(Error, Debug)]
+pub enum TableEditError {
+    #[error("Table does not exist")]
+    TableNonexistent,
+
+    #[error("Table already exists")]
+    TableExists,
+
+    #[error(transparent)]
+    Connector(#[from] ConnectorError),
+}
+
+#[derive(Error, Debug)]
+pub enum AppendCheckError {
+    #[error("Schema cannot be appended: {}", incompatibility_message(.0))]
+    Incompatible(Vec<Incompatibility>),
+
+    #[error(transparent)]
+    Connector(#[from] ConnectorError),
+}
@@ -9,7 +9,14 @@
 //! - **Temporal and Container Types**: Correctly handles temporal and container types.
 //! - **Schema Introspection**: Query the database for schema of specific tables.
 //! - **Schema Migration**: Basic schema migration commands.
-//! - **Append**: Write [arrow::record_batch::RecordBatch] into database tables.
+//! - **Type Mapping**: Override the mapping between database and Arrow types per connection.
+//! - **Append**: Write [arrow::record_batch::RecordBatch] into database tables and check
+//!   compatibility of the schema beforehand with [api::Connector::check_append].
+//! - **Copy**: Stream query results into a table of another database with [copy()].
+//! - **Dynamic Connections**: Open a connection from a URL with [connect] and select the database
+//!   at runtime with [dynamic::DynConnector].
+//! - **Cancellation**: Cancel running queries from another thread and set statement timeouts,
+//!   with [cancel::CancelHandle].
 //!
 //! Example for SQLite:
 //! ```
@@ -55,8 +62,14 @@
 //! releases of arrow-rs, even without breaking changes.
 
 pub mod api;
+pub mod cancel;
+mod connect;
+mod copy;
+pub mod dynamic;
 mod errors;
+pub mod migration;
 mod params;
+pub mod tls;
 pub mod types;
 pub mod util;
 
@@ -72,6 +85,8 @@ pub mod sqlite;
 pub mod tiberius;
 
 pub use arrow;
+pub use connect::{connect, connect_with_tls};
+pub use copy::{copy, CopyOptions, CopyProgress};
 pub use errors::*;
 
 use arrow::record_batch::RecordBatch;
@@ -0,0 +1,370 @@
+//! Planning of schema migrations from the schema of an existing table to a desired schema
+//! and checking whether batches of a schema can be appended into an existing table.
+//!
+//! Plans are created with [SchemaEdit::plan_migration] and [Connector::check_append].
+
+use arrow::datatypes::{DataType, Field, Schema};
+
+use crate::api::{Connector, SchemaEdit, TypeMapper, METADATA_DEFAULT, METADATA_GENERATED};
+use crate::{ColumnEditError, ConnectorError};
+
+/// Steps that migrate a table from its current schema into the desired schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationPlan {
+    pub table_name: String,
+
+    pub steps: Vec<MigrationStep>,
+
+    /// Differences that cannot be migrated without loss of data.
+    /// A plan with incompatibilities cannot be applied.
+    pub incompatible: Vec<Incompatibility>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationStep {
+    ColumnAdd(Field),
+
+    ColumnDrop(String),
+
+    /// Change type of a column into a type that can represent all values of the current type.
+    /// Contains the field with the new data type and nullability of the existing column.
+    ColumnWiden(Field),
+
+    /// Allow nulls in a column that does not allow them.
+    /// Contains the field with the data type of the column after preceding steps.
+    ColumnDropNotNull(Field),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incompatibility {
+    /// Type of the column cannot be changed without loss of data.
+    /// When appending, `current` is the type of the table column and `desired` is the type
+    /// of the appended field.
+    TypeChange {
+        column_name: String,
+        current: DataType,
+        desired: DataType,
+    },
+
+    /// Column does not allow nulls, but the existing column or the appended field does.
+    /// When migrating, existing rows might contain nulls that cannot be stored into
+    /// the desired column.
+    Nullability { column_name: String },
+
+    /// Type cannot be stored in the data store.
+    UnsupportedType {
+        column_name: String,
+        data_type: DataType,
+    },
+
+    /// Appended field does not have a matching column in the table.
+    ColumnNonexistent { column_name: String },
+
+    /// Added column does not allow nulls and has no [METADATA_DEFAULT], so it cannot be added
+    /// to a table that contains rows. Reported regardless of the number of rows,
+    /// because plans do not depend on contents of the table.
+    ColumnAddNotNull { column_name: String },
+
+    /// Column of the table does not allow nulls, but it is missing from the appended schema.
+    /// Columns with [METADATA_DEFAULT] or [METADATA_GENERATED] set are not reported.
+    ColumnMissing { column_name: String },
+}
+
+impl std::fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Incompatibility::TypeChange {
+                column_name,
+                current,
+                desired,
+            } => write!(
+                f,
+                "column {column_name} cannot be changed from {current} to {desired}"
+            ),
+            Incompatibility::Nullability { column_name } => {
+                write!(f, "column {column_name} does not allow nulls")
+            }
+            Incompatibility::UnsupportedType {
+                column_name,
+                data_type,
+            } => write!(
+                f,
+                "column {column_name} has type {data_type}, which cannot be stored"
+            ),
+            Incompatibility::ColumnAddNotNull { column_name } => {
+                write!(
+                    f,
+                    "column {column_name} does not allow nulls and has no default, so it cannot be added"
+                )
+            }
+            Incompatibility::ColumnNonexistent { column_name } => {
+                write!(f, "column {column_name} does not exist")
+            }
+            Incompatibility::ColumnMissing { column_name } => {
+                write!(
+                    f,
+                    "column {column_name} does not allow nulls, but is missing"
+                )
+            }
+        }
+    }
+}
+
+/// Joins incompatibilities into a single message.
+pub(crate) fn incompatibility_message(incompatible: &[Incompatibility]) -> String {
+    incompatible
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl MigrationPlan {
+    /// Plan does not contain any steps or incompatibilities.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty() && self.incompatible.is_empty()
+    }
+
+    /// Renders the plan into DDL statements of the data store of the connection.
+    pub fn to_ddl<C: SchemaEdit>(&self, conn: &C) -> Result<Vec<String>, ConnectorError> {
+        self.check_compatible()?;
+
+        (self.steps.iter())
+            .map(|step| conn.migration_step_ddl(&self.table_name, step))
+            .collect()
+    }
+
+    /// Executes the plan, step by step.
+    pub fn apply<C: SchemaEdit>(&self, conn: &mut C) -> Result<(), ColumnEditError> {
+        self.check_compatible()?;
+
+        for step in &self.steps {
+            match step {
+                MigrationStep::ColumnAdd(field) => conn.column_add(&self.table_name, field)?,
+                MigrationStep::ColumnDrop(name) => conn.column_drop(&self.table_name, name)?,
+                MigrationStep::ColumnWiden(field) => {
+                    conn.column_alter_type(&self.table_name, field.name(), field.data_type())?
+                }
+                MigrationStep::ColumnDropNotNull(field) => {
+                    conn.column_drop_not_null(&self.table_name, field.name())?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_compatible(&self) -> Result<(), ConnectorError> {
+        if self.incompatible.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConnectorError::IncompatibleSchema {
+            table_name: self.table_name.clone(),
+            message: incompatibility_message(&self.incompatible),
+            hint: None,
+        })
+    }
+}
+
+/// Compares types and nullability of the schemas after they are coerced to the types
+/// supported by the data store of connector `C`.
+pub(crate) fn plan<C: Connector>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    current: &Schema,
+    desired: &Schema,
+) -> MigrationPlan {
+    let mut steps = Vec::new();
+    let mut incompatible = Vec::new();
+
+    for desired_field in desired.fields() {
+        let column_name = desired_field.name().clone();
+
+        let Some(desired_ty) = coerce_type::<C>(mapper, desired_field) else {
+            incompatible.push(Incompatibility::UnsupportedType {
+                column_name,
+                data_type: desired_field.data_type().clone(),
+            });
+            continue;
+        };
+
+        let Ok(current_field) = current.field_with_name(desired_field.name()) else {
+            // existing rows would not have a value for the new column
+            let has_default = desired_field.metadata().contains_key(METADATA_DEFAULT);
+            if !desired_field.is_nullable() && !has_default {
+                incompatible.push(Incompatibility::ColumnAddNotNull { column_name });
+                continue;
+            }
+            steps.push(MigrationStep::ColumnAdd(desired_field.as_ref().clone()));
+            continue;
+        };
+
+        if !desired_field.is_nullable() && current_field.is_nullable() {
+            incompatible.push(Incompatibility::Nullability {
+                column_name: column_name.clone(),
+            });
+        }
+
+        // schema returned by table_get might not be in the canonical form for this connector
+        let current_ty = coerce_type::<C>(mapper, current_field)
+            .unwrap_or_else(|| current_field.data_type().clone());
+        let mut migrated = current_field.clone();
+        if current_ty != desired_ty {
+            if is_widening(&current_ty, &desired_ty) {
+                migrated = migrated.with_data_type(desired_field.data_type().clone());
+                steps.push(MigrationStep::ColumnWiden(migrated.clone()));
+            } else {
+                incompatible.push(Incompatibility::TypeChange {
+                    column_name,
+                    current: current_ty,
+                    desired: desired_ty,
+                });
+            }
+        }
+
+        // allowing nulls is lossless
+        if desired_field.is_nullable() && !current_field.is_nullable() {
+            steps.push(MigrationStep::ColumnDropNotNull(
+                migrated.with_nullable(true),
+            ));
+        }
+    }
+
+    for current_field in current.fields() {
+        if desired.field_with_name(current_field.name()).is_err() {
+            steps.push(MigrationStep::ColumnDrop(current_field.name().clone()));
+        }
+    }
+
+    MigrationPlan {
+        table_name: table_name.to_string(),
+        steps,
+        incompatible,
+    }
+}
+
+/// Columns of an existing table that the fields of an appended schema are written into.
+///
+/// Created by [Connector::check_append].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppendPlan {
+    pub table_name: String,
+
+    /// Columns of the table that receive the appended fields, in the order of the fields.
+    pub columns: Vec<Field>,
+
+    /// Columns of the table that are not appended and will be set to their default values.
+    pub omitted: Vec<Field>,
+}
+
+impl AppendPlan {
+    /// Names of the columns that receive the appended fields, in the order of the fields.
+    pub fn column_names(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|f| f.name().as_str())
+    }
+}
+
+/// Matches the fields of an appended schema to the columns of a table by name and checks that
+/// the values can be stored without loss of data, after they are coerced to the types
+/// supported by the data store of connector `C`.
+pub(crate) fn plan_append<C: Connector>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    table: &Schema,
+    appended: &Schema,
+) -> Result<AppendPlan, Vec<Incompatibility>> {
+    let mut columns = Vec::with_capacity(appended.fields().len());
+    let mut incompatible = Vec::new();
+
+    for field in appended.fields() {
+        let column_name = field.name().clone();
+        let Ok(column) = table.field_with_name(field.name()) else {
+            incompatible.push(Incompatibility::ColumnNonexistent { column_name });
+            continue;
+        };
+        columns.push(column.clone());
+
+        let Some(appended_ty) = coerce_type::<C>(mapper, field) else {
+            incompatible.push(Incompatibility::UnsupportedType {
+                column_name,
+                data_type: field.data_type().clone(),
+            });
+            continue;
+        };
+
+        if field.is_nullable() && !column.is_nullable() {
+            incompatible.push(Incompatibility::Nullability {
+                column_name: column_name.clone(),
+            });
+        }
+
+        let column_ty =
+            coerce_type::<C>(mapper, column).unwrap_or_else(|| column.data_type().clone());
+        if column_ty != appended_ty && !is_widening(&appended_ty, &column_ty) {
+            incompatible.push(Incompatibility::TypeChange {
+                column_name,
+                current: column_ty,
+                desired: appended_ty,
+            });
+        }
+    }
+
+    let mut omitted = Vec::new();
+    for column in table.fields() {
+        if appended.field_with_name(column.name()).is_ok() {
+            continue;
+        }
+        let has_default = column.metadata().contains_key(METADATA_DEFAULT)
+            || column.metadata().contains_key(METADATA_GENERATED);
+        if !column.is_nullable() && !has_default {
+            incompatible.push(Incompatibility::ColumnMissing {
+                column_name: column.name().clone(),
+            });
+        }
+        omitted.push(column.as_ref().clone());
+    }
+
+    if !incompatible.is_empty() {
+        return Err(incompatible);
+    }
+    Ok(AppendPlan {
+        table_name: table_name.to_string(),
+        columns,
+        omitted,
+    })
+}
+
+/// The type that a column of type `ty` will have, when stored into the data store.
+fn coerce_type<C: Connector>(mapper: Option<&dyn TypeMapper>, field: &Field) -> Option<DataType> {
+    let db_ty = crate::util::arrow_into_db::<C>(mapper, field.name(), field.data_type())?;
+    Some(
+        crate::util::db_into_arrow::<C>(mapper, field.name(), &db_ty)
+            .unwrap_or_else(|| field.data_type().clone()),
+    )
+}
+
+/// Values of type `from` can be losslessly converted into type `to`.
+fn is_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    match (from, to) {
+        (Null, _) => true,
+        (Int8, Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (Int16, Int32 | Int64 | Float32 | Float64) => true,
+        (Int32, Int64 | Float64) => true,
+        (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (UInt16, UInt32 | UInt64 | Int32 | Int64 | Float32 | Float64) => true,
+        (UInt32, UInt64 | Int64 | Float64) => true,
+        (Float16, Float32 | Float64) => true,
+        (Float32, Float64) => true,
+        (Utf8, LargeUtf8) => true,
+        (Binary, LargeBinary) => true,
+        (Decimal128(p1, s1), Decimal128(p2, s2) | Decimal256(p2, s2)) => {
+            s1 <= s2 && (*p1 as i16 - *s1 as i16) <= (*p2 as i16 - *s2 as i16)
+        }
+        (Decimal256(p1, s1), Decimal256(p2, s2)) => {
+            s1 <= s2 && (*p1 as i16 - *s1 as i16) <= (*p2 as i16 - *s2 as i16)
+        }
+        _ => false,
+    }
+}
@@ -1,64 +1,173 @@
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
-use itertools::{zip_eq, Itertools};
+use itertools::Itertools;
 use mysql::prelude::Queryable;
 use mysql::Value;
 
-use crate::api::Append;
+use crate::api::{Append, AppendMode, AppendOptions, AppendSummary};
 use crate::types::{FixedSizeBinaryType, NullType};
+use crate::util::append::{
+    write_batch, AppendStats, AppendTarget, Savepoint, WriteRows, SAVEPOINT_NAME,
+};
+use crate::util::encode::encode_batch;
 use crate::util::escape::escaped_ident_bt;
-use crate::util::transport::{self, Consume, ConsumeTy};
-use crate::util::ArrayCellRef;
+use crate::util::partition::staging_table_names;
+use crate::util::transport::{Consume, ConsumeTy};
 use crate::{impl_consume_unsupported, ConnectorError};
 
+use super::MySQLConnection;
+
+/// MySQL commits implicitly before DDL statements, so tables created or replaced on the first
+/// batch are not rolled back when the append is not finished.
+/// With [AppendMode::Replace], the table is swapped with the staging table by a single
+/// RENAME TABLE statement, after the rows are committed.
 pub struct MySQLAppender<'conn, C: Queryable> {
-    table: String,
-    client: &'conn mut C,
+    conn: &'conn mut MySQLConnection<C>,
+    target: AppendTarget,
+    stats: AppendStats,
+    committed: bool,
 }
 
 impl<'conn, C: Queryable> MySQLAppender<'conn, C> {
-    pub fn new(client: &'conn mut C, table_name: &str) -> Result<Self, ConnectorError> {
-        client.query_drop("START TRANSACTION;")?;
+    pub fn new(
+        conn: &'conn mut MySQLConnection<C>,
+        table_name: &str,
+        options: AppendOptions,
+    ) -> Result<Self, ConnectorError> {
+        conn.queryable.query_drop("START TRANSACTION;")?;
         Ok(Self {
-            table: table_name.to_owned(),
-            client,
+            conn,
+            stats: AppendStats::new(&options),
+            target: AppendTarget::new(table_name, options),
+            committed: false,
         })
     }
+
+    fn insert(&mut self, query: &str, params: Vec<Value>) -> Result<(), ConnectorError> {
+        let res = self.conn.queryable.exec_iter(query, params)?;
+        let rows = res.affected_rows();
+        let first_key = res.last_insert_id();
+        drop(res);
+
+        self.stats.rows(rows);
+        if self.target.options().generated_keys.is_some() {
+            // auto-increment values of a multi-row INSERT are allocated consecutively
+            let first_key = first_key.unwrap_or_default() as i64;
+            for key in first_key..first_key + rows as i64 {
+                self.stats.generated_key(key);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'conn, C: Queryable> Append<'conn> for MySQLAppender<'conn, C> {
     fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        let first = !self.target.is_prepared();
+        self.target.prepare(self.conn, &batch.schema())?;
+        if first && self.target.options().mode != AppendMode::Append {
+            // DDL has committed the transaction
+            self.conn.queryable.query_drop("START TRANSACTION;")?;
+        }
+
+        write_batch(self, &batch, self.target.options().on_error)
+    }
+
+    fn finish(mut self) -> Result<AppendSummary, ConnectorError> {
+        self.conn.queryable.query_drop("COMMIT;")?;
+        self.committed = true;
+
+        // DDL cannot be a part of the transaction, but RENAME TABLE is atomic
+        if self.target.options().mode == AppendMode::Replace && self.target.is_prepared() {
+            let staging = self.target.prepared_name();
+            replace_table(self.conn, staging, self.target.table_name())?;
+        } else {
+            self.target.finish(self.conn)?;
+        }
+        self.stats.finish()
+    }
+}
+
+impl<C: Queryable> WriteRows for MySQLAppender<'_, C> {
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError> {
         // TODO: 30 is a guess, we need benchmarking to find the optimum value
         const BATCH_SIZE: usize = 30;
 
         let last_batch_size = batch.num_rows() % BATCH_SIZE;
+        let mut values = encode_batch::<Value>(batch)?;
+        let table = self.target.prepared_name().to_string();
+        let columns = self.target.options().column_names(&batch.schema());
+        let columns = columns.as_deref();
 
-        let batch_query = insert_query(&self.table, batch.num_columns(), BATCH_SIZE);
-        for batch_number in 0..(batch.num_rows() / BATCH_SIZE) {
-            let rows_range = (batch_number * BATCH_SIZE)..((batch_number + 1) * BATCH_SIZE);
-
-            let params: Vec<Value> = collect_args(&batch, rows_range);
-            self.client.exec_iter(&batch_query, params)?;
+        let batch_query = insert_query(&table, columns, batch.num_columns(), BATCH_SIZE);
+        for _ in 0..(batch.num_rows() / BATCH_SIZE) {
+            let params = values
+                .by_ref()
+                .take(BATCH_SIZE * batch.num_columns())
+                .collect_vec();
+            self.insert(&batch_query, params)?;
         }
 
         if last_batch_size > 0 {
-            let rows_range = (batch.num_rows() - last_batch_size)..batch.num_rows();
-
-            let last_query = insert_query(&self.table, batch.num_columns(), last_batch_size);
-            let params: Vec<Value> = collect_args(&batch, rows_range);
-            self.client.exec_iter(&last_query, params)?;
+            let last_query = insert_query(&table, columns, batch.num_columns(), last_batch_size);
+            let params = values.collect_vec();
+            self.insert(&last_query, params)?;
         }
 
         Ok(())
     }
 
-    fn finish(self) -> Result<(), ConnectorError> {
-        self.client.query_drop("COMMIT;")?;
-        Ok(())
+    fn savepoint(&mut self, command: Savepoint) -> Result<(), ConnectorError> {
+        let query = match command {
+            Savepoint::Create => format!("SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Release => format!("RELEASE SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Rollback => format!("ROLLBACK TO SAVEPOINT {SAVEPOINT_NAME}"),
+        };
+        Ok(self.conn.queryable.query_drop(query)?)
     }
+
+    fn stats(&mut self) -> &mut AppendStats {
+        &mut self.stats
+    }
+}
+
+impl<C: Queryable> Drop for MySQLAppender<'_, C> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = self.conn.queryable.query_drop("ROLLBACK;") {
+                log::warn!("cannot roll back unfinished append: {e}");
+            }
+        }
+    }
+}
+
+/// Swaps the staging table into place of the table, with a single RENAME TABLE statement,
+/// and drops the replaced table.
+fn replace_table<C: Queryable>(
+    conn: &mut MySQLConnection<C>,
+    staging: &str,
+    table_name: &str,
+) -> Result<(), ConnectorError> {
+    let replaced = staging_table_names(1).remove(0);
+    let (staging, table_name) = (escaped_ident_bt(staging), escaped_ident_bt(table_name));
+
+    let swap = format!("RENAME TABLE {table_name} TO {replaced}, {staging} TO {table_name};");
+    match conn.queryable.query_drop(swap) {
+        Ok(()) => {
+            conn.queryable
+                .query_drop(format!("DROP TABLE {replaced};"))?;
+        }
+        // table does not exist yet
+        Err(mysql::Error::MySqlError(e)) if e.code == 1146 => {
+            let rename = format!("RENAME TABLE {staging} TO {table_name};");
+            conn.queryable.query_drop(rename)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
 }
 
-fn insert_query(table_name: &str, cols: usize, rows: usize) -> String {
+fn insert_query(table_name: &str, columns: Option<&[String]>, cols: usize, rows: usize) -> String {
     let values = (0..rows)
         .map(|_| {
             let row = (0..cols).map(|_| "?").join(",");
@@ -66,33 +175,15 @@ fn insert_query(table_name: &str, cols: usize, rows: usize) -> String {
         })
         .join(",");
 
+    let columns = columns
+        .map(|c| format!(" ({})", c.iter().map(|c| escaped_ident_bt(c)).join(",")))
+        .unwrap_or_default();
     format!(
-        "INSERT INTO {} VALUES {values}",
+        "INSERT INTO {}{columns} VALUES {values}",
         escaped_ident_bt(table_name)
     )
 }
 
-fn collect_args(batch: &RecordBatch, rows_range: std::ops::Range<usize>) -> Vec<Value> {
-    let mut res = Vec::with_capacity(rows_range.len() * batch.num_columns());
-
-    let schema = batch.schema();
-    let mut row = zip_eq(batch.columns(), schema.fields())
-        .map(|(array, field)| ArrayCellRef {
-            array,
-            field,
-            row_number: 0,
-        })
-        .collect_vec();
-
-    for row_number in rows_range {
-        for cell in &mut row {
-            cell.row_number = row_number;
-            transport::transport(cell.field, cell as &_, &mut res).unwrap();
-        }
-    }
-    res
-}
-
 impl Consume for Vec<Value> {}
 
 macro_rules! impl_consume_ty {
@@ -3,19 +3,48 @@ mod query;
 mod schema;
 mod types;
 
+use std::sync::Arc;
+
 use arrow::datatypes::*;
 use mysql::prelude::*;
 
-use crate::api::Connector;
-use crate::ConnectorError;
+use crate::api::{AppendOptions, Connector, TypeMapper};
+use crate::cancel::CancelHandle;
+use crate::{ConnectorError, ErrorKind};
 
 pub struct MySQLConnection<Q: Queryable> {
     queryable: Q,
+    type_mapper: Option<Arc<dyn TypeMapper>>,
+
+    /// Options of the side connection that cancels queries and id of this connection.
+    cancel: Option<(mysql::Opts, u32)>,
 }
 
 impl<Q: Queryable> MySQLConnection<Q> {
     pub fn new(conn: Q) -> Self {
-        MySQLConnection { queryable: conn }
+        MySQLConnection {
+            queryable: conn,
+            type_mapper: None,
+            cancel: None,
+        }
+    }
+
+    /// Enables [Connector::cancel_handle], which cancels queries with `KILL QUERY`,
+    /// issued over a new connection opened with `opts`.
+    pub fn with_cancel_opts(
+        mut self,
+        opts: impl Into<mysql::Opts>,
+    ) -> Result<Self, ConnectorError> {
+        let connection_id: Option<u32> = self.queryable.query_first("SELECT CONNECTION_ID()")?;
+        let connection_id = connection_id.ok_or(ConnectorError::NoResultSets)?;
+        self.cancel = Some((opts.into(), connection_id));
+        Ok(self)
+    }
+
+    /// Overrides the built-in type mapping for this connection.
+    pub fn with_type_mapper(mut self, mapper: impl TypeMapper + 'static) -> Self {
+        self.type_mapper = Some(Arc::new(mapper));
+        self
     }
 
     pub fn unwrap(self) -> Q {
@@ -27,6 +56,29 @@ impl<Q: Queryable> MySQLConnection<Q> {
     }
 }
 
+pub(crate) fn error_kind(err: &mysql::Error) -> ErrorKind {
+    match err {
+        mysql::Error::MySqlError(e) => match e.code {
+            1062 | 1586 => ErrorKind::UniqueViolation,
+            1216 | 1217 | 1451 | 1452 => ErrorKind::ForeignKeyViolation,
+            1048 | 1364 => ErrorKind::NotNullViolation,
+            1146 => ErrorKind::UndefinedTable,
+            1054 => ErrorKind::UndefinedColumn,
+            1050 => ErrorKind::TableExists,
+            1064 | 1149 => ErrorKind::SyntaxError,
+            1213 => ErrorKind::Deadlock,
+            1205 | 3024 => ErrorKind::Timeout,
+            1317 => ErrorKind::Cancelled,
+            1053 | 2006 | 2013 => ErrorKind::ConnectionLost,
+            _ => ErrorKind::Other,
+        },
+        mysql::Error::DriverError(mysql::DriverError::ConnectTimeout)
+        | mysql::Error::DriverError(mysql::DriverError::Timeout) => ErrorKind::Timeout,
+        mysql::Error::IoError(_) | mysql::Error::CodecError(_) => ErrorKind::ConnectionLost,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl<Q: Queryable> Connector for MySQLConnection<Q> {
     type Stmt<'conn>
         = query::MySQLStatement<'conn, Q>
@@ -43,11 +95,16 @@ impl<Q: Queryable> Connector for MySQLConnection<Q> {
         Ok(query::MySQLStatement {
             queryable: &mut self.queryable,
             stmt,
+            type_mapper: self.type_mapper.as_deref(),
         })
     }
 
-    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError> {
-        append::MySQLAppender::new(&mut self.queryable, table_name)
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError> {
+        append::MySQLAppender::new(self, table_name, options.clone())
     }
 
     fn type_db_into_arrow(ty: &str) -> Option<DataType> {
@@ -152,4 +209,26 @@ impl<Q: Queryable> Connector for MySQLConnection<Q> {
             .to_string(),
         )
     }
+
+    fn quote_ident(ident: &str) -> String {
+        crate::util::escape::escaped_ident_bt(ident).to_string()
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        self.type_mapper.as_deref()
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        let Some((opts, connection_id)) = self.cancel.clone() else {
+            return Err(ConnectorError::NotSupported {
+                connector_name: "MySQL",
+                feature: "query cancellation without `with_cancel_opts`",
+            });
+        };
+        Ok(CancelHandle::new(move || {
+            let mut conn = mysql::Conn::new(opts.clone())?;
+            conn.query_drop(format!("KILL QUERY {connection_id}"))?;
+            Ok(())
+        }))
+    }
 }
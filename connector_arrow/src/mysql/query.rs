@@ -2,21 +2,22 @@ use arrow::{datatypes::*, record_batch::RecordBatch};
 use mysql::prelude::*;
 use pac_cell::PacCell;
 
-use crate::api::{ResultReader, Statement};
+use crate::api::{ResultReader, Statement, TypeMapper};
 use crate::impl_produce_unsupported;
 use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
 use crate::util::transport::ProduceTy;
-use crate::util::{self, transport::Produce};
+use crate::util::{self, transport::Produce, CastReader};
 use crate::ConnectorError;
 
 pub struct MySQLStatement<'conn, Q: Queryable> {
     pub(super) stmt: mysql::Statement,
     pub(super) queryable: &'conn mut Q,
+    pub(super) type_mapper: Option<&'conn dyn TypeMapper>,
 }
 
 impl<'conn, C: Queryable> Statement<'conn> for MySQLStatement<'conn, C> {
     type Reader<'stmt>
-        = MySQLQueryResult<'stmt>
+        = CastReader<MySQLQueryResult<'stmt>>
     where
         Self: 'stmt;
 
@@ -25,14 +26,29 @@ impl<'conn, C: Queryable> Statement<'conn> for MySQLStatement<'conn, C> {
         _args: (&RecordBatch, usize),
     ) -> Result<Self::Reader<'_>, ConnectorError> {
         let query_result = self.queryable.exec_iter(&self.stmt, ())?;
+        let mapper = self.type_mapper;
+        let mut mapped_schema = None;
 
         // PacCell is needed so we can return query_result and result_set that mutably borrows query result.
         let pac = PacCell::try_new(query_result, |qr| -> Result<_, ConnectorError> {
             let result_set = qr.iter().ok_or(ConnectorError::NoResultSets)?;
-            let schema = super::types::get_result_schema(&result_set)?;
-            Ok(MySQLResultReader { result_set, schema })
+            let (schema, mapped) = super::types::get_result_schema(&result_set, mapper)?;
+            mapped_schema = mapped;
+            Ok(MySQLResultReader {
+                result_set,
+                schema,
+                rows_read: 0,
+            })
         })?;
-        Ok(MySQLQueryResult(pac))
+        Ok(CastReader::new(MySQLQueryResult(pac), mapped_schema))
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        self.start_batch(args)?.cast_into(expected)
     }
 }
 
@@ -55,7 +71,10 @@ impl Iterator for MySQLQueryResult<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.with_mut(|reader| {
             let schema = reader.schema.clone();
-            util::next_batch_from_rows(&schema, reader, 1024).transpose()
+            let mut rows_read = reader.rows_read;
+            let batch = util::next_batch_from_rows(&schema, reader, 1024, &mut rows_read);
+            reader.rows_read = rows_read;
+            batch.transpose()
         })
     }
 }
@@ -63,6 +82,7 @@ impl Iterator for MySQLQueryResult<'_> {
 struct MySQLResultReader<'stmt> {
     result_set: mysql::ResultSet<'stmt, 'stmt, 'stmt, 'stmt, mysql::Binary>,
     schema: SchemaRef,
+    rows_read: usize,
 }
 
 impl<'s> util::RowsReader<'s> for MySQLResultReader<'s> {
@@ -188,10 +208,10 @@ impl<'r> ProduceTy<'r, Utf8Type> for MySQLCellRef<'r> {
             mysql::Value::Bytes(_) => Ok(Some(
                 String::from_value_opt(res).map_err(|x| ConnectorError::MySQL(x.into()))?,
             )),
-            mysql::Value::Int(_) => todo!(),
-            mysql::Value::UInt(_) => todo!(),
-            mysql::Value::Float(_) => todo!(),
-            mysql::Value::Double(_) => todo!(),
+            mysql::Value::Int(v) => Ok(Some(v.to_string())),
+            mysql::Value::UInt(v) => Ok(Some(v.to_string())),
+            mysql::Value::Float(v) => Ok(Some(v.to_string())),
+            mysql::Value::Double(v) => Ok(Some(v.to_string())),
             mysql::Value::Date(year, month, day, hour, minutes, seconds, micro_seconds) => {
                 // TODO: converting to timestamp
                 // let date_time = chrono::NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32).unwrap().and_hms_opt(hour as u32, minutes as u32, seconds as u32).unwrap();
@@ -204,7 +224,13 @@ impl<'r> ProduceTy<'r, Utf8Type> for MySQLCellRef<'r> {
                     "{year:04}-{month:02}-{day:02}T{hour:02}:{minutes:02}:{seconds:02}.{micro_seconds:06}"
                 )))
             }
-            mysql::Value::Time(_, _, _, _, _, _) => todo!(),
+            mysql::Value::Time(is_negative, days, hours, minutes, seconds, micro_seconds) => {
+                let sign = if is_negative { "-" } else { "" };
+                let hours = u32::from(hours) + days * 24;
+                Ok(Some(format!(
+                    "{sign}{hours:02}:{minutes:02}:{seconds:02}.{micro_seconds:06}"
+                )))
+            }
         }
     }
 }
@@ -1,14 +1,18 @@
 use std::sync::Arc;
 
-use arrow::datatypes::{DataType, Schema};
-use itertools::Itertools;
-use mysql::prelude::Queryable;
+use arrow::datatypes::{DataType, Field, Schema};
+use mysql::prelude::{FromValue, Queryable};
 
 use crate::{
-    api::{Connector, SchemaEdit, SchemaGet},
+    api::{Relation, RelationKind, SchemaEdit, SchemaGet, TableOptions, TypeMapper},
+    migration::MigrationStep,
     mysql::MySQLConnection,
-    util::escape::escaped_ident_bt,
-    ConnectorError, TableCreateError, TableDropError,
+    util::ddl::{
+        column_comment, column_default, table_constraints, with_column_comment,
+        with_column_default, Generated,
+    },
+    util::escape::{escaped_ident_bt, escaped_literal},
+    ColumnEditError, ConnectorError, TableCreateError, TableDropError, TableEditError,
 };
 
 impl<C: Queryable> SchemaGet for super::MySQLConnection<C> {
@@ -18,63 +22,145 @@ impl<C: Queryable> SchemaGet for super::MySQLConnection<C> {
 
         let table_names = result
             .into_iter()
-            .map(|r_row| r_row.map(|row| row.get::<String, _>(0).unwrap()))
+            .map(|r_row| row_get::<String>(&r_row?, 0))
             .collect::<Result<Vec<String>, _>>()?;
 
         Ok(table_names)
     }
 
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        let mut results = self.queryable.exec_iter("SHOW FULL TABLES;", ())?;
+        let result = results.iter().ok_or(crate::ConnectorError::NoResultSets)?;
+
+        // temporary tables are not listed by SHOW TABLES
+        let relations = result
+            .into_iter()
+            .map(|r_row| {
+                let row = r_row?;
+                let name = row_get::<String>(&row, 0)?;
+                let ty = row_get::<String>(&row, 1)?;
+
+                let kind = match ty.as_str() {
+                    "VIEW" | "SYSTEM VIEW" => RelationKind::View,
+                    _ => RelationKind::Table,
+                };
+                Ok(Relation { name, kind })
+            })
+            .collect::<Result<Vec<_>, ConnectorError>>()?;
+
+        Ok(relations)
+    }
+
     fn table_get(
         &mut self,
         name: &str,
     ) -> Result<arrow::datatypes::SchemaRef, crate::ConnectorError> {
-        let mut results = self
-            .queryable
-            .exec_iter(format!("DESCRIBE {};", escaped_ident_bt(name)), ())?;
+        let mut results = self.queryable.exec_iter(
+            format!("SHOW FULL COLUMNS FROM {};", escaped_ident_bt(name)),
+            (),
+        )?;
         let result = results.iter().ok_or(crate::ConnectorError::NoResultSets)?;
+        let mapper = self.type_mapper.as_deref();
 
         let fields = result
             .into_iter()
             .map(|r_row| {
-                r_row.map(|row| {
-                    let name = row.get::<String, _>(0).unwrap();
-                    let ty = row.get::<String, _>(1).unwrap();
-                    let nullable = row.get::<String, _>(2).unwrap() == "YES";
+                let row = r_row?;
+                let name = row_get::<String>(&row, 0)?;
+                let ty = row_get::<String>(&row, 1)?;
+                let nullable = row_get::<String>(&row, 3)? == "YES";
+                let default = row_get::<Option<String>>(&row, 5)?;
+                let extra = row_get::<String>(&row, 6)?.to_lowercase();
+                let comment = row_get::<Option<String>>(&row, 8)?;
 
-                    super::types::create_field(name, &ty, nullable)
-                })
+                let field = super::types::create_field(mapper, name, &ty, nullable);
+                let (default, generated) = column_default_of(default, &extra);
+                let field = with_column_default(field, default, generated);
+                Ok(with_column_comment(field, comment))
             })
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect::<Result<Vec<_>, ConnectorError>>()?;
 
         Ok(Arc::new(Schema::new(fields)))
     }
 }
 
+/// Reads a value of a row returned by a metadata query, such as SHOW TABLES.
+fn row_get<T: FromValue>(row: &mysql::Row, index: usize) -> Result<T, ConnectorError> {
+    match row.get_opt::<T, _>(index) {
+        Some(res) => Ok(res.map_err(mysql::Error::from)?),
+        None => Err(ConnectorError::DataSchemaMismatch(format!(
+            "metadata query returned no column {index}"
+        ))),
+    }
+}
+
+/// Reads columns `(Field, Type, Null)` of DESCRIBE.
+fn describe_table<C: Queryable>(
+    queryable: &mut C,
+    table_name: &str,
+) -> Result<Vec<(String, String, String)>, ColumnEditError> {
+    let rows: Vec<mysql::Row> = queryable
+        .query(format!("DESCRIBE {};", escaped_ident_bt(table_name)))
+        .map_err(column_edit_error)?;
+    let columns = rows
+        .iter()
+        .map(|row| Ok((row_get(row, 0)?, row_get(row, 1)?, row_get(row, 2)?)))
+        .collect::<Result<Vec<_>, ConnectorError>>()?;
+    Ok(columns)
+}
+
+/// Converts `Default` and `Extra` columns of SHOW COLUMNS into a default SQL expression
+/// and the kind of generated column.
+fn column_default_of(default: Option<String>, extra: &str) -> (Option<String>, Option<Generated>) {
+    if extra.contains("auto_increment") {
+        return (None, Some(Generated::Identity));
+    }
+    if (extra.contains("generated") && !extra.contains("default_generated"))
+        || extra.contains("virtual")
+        || extra.contains("persistent")
+    {
+        return (None, Some(Generated::Computed));
+    }
+
+    // literals are reported without quotes, expressions are reported as they were declared
+    let default = default.map(|d| {
+        let is_expression = extra.contains("default_generated")
+            || d.parse::<f64>().is_ok()
+            || d.to_lowercase().starts_with("current_timestamp");
+        if is_expression {
+            d
+        } else {
+            escaped_literal(&d).to_string()
+        }
+    });
+    (default, None)
+}
+
 impl<C: Queryable> SchemaEdit for super::MySQLConnection<C> {
-    fn table_create(
+    fn table_create_with(
         &mut self,
         name: &str,
         schema: arrow::datatypes::SchemaRef,
+        options: &TableOptions,
     ) -> Result<(), TableCreateError> {
         let column_defs = schema
             .fields()
             .iter()
-            .map(|field| {
-                let ty = MySQLConnection::<mysql::Conn>::type_arrow_into_db(field.data_type())
-                    .unwrap_or_else(|| {
-                        unimplemented!("cannot store arrow type {} in MySQL", field.data_type());
-                    });
-
-                let is_nullable =
-                    field.is_nullable() || matches!(field.data_type(), DataType::Null);
-                let not_null = if is_nullable { "" } else { " NOT NULL" };
-
-                let name = escaped_ident_bt(field.name());
-                format!("{name} {ty}{not_null}",)
-            })
+            .map(|field| column_def(self.type_mapper.as_deref(), name, field, options))
+            .collect::<Result<Vec<_>, _>>()?
             .join(",");
+        let constraints = table_constraints(options, escaped_ident_bt);
 
-        let ddl = format!("CREATE TABLE {} ({column_defs});", escaped_ident_bt(name));
+        let temporary = if options.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if options.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let ddl = format!(
+            "CREATE{temporary} TABLE{if_not_exists} {} ({column_defs}{constraints});",
+            escaped_ident_bt(name)
+        );
 
         let res = self.queryable.query_drop(&ddl);
         match res {
@@ -86,6 +172,23 @@ impl<C: Queryable> SchemaEdit for super::MySQLConnection<C> {
         }
     }
 
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        // CREATE TABLE ... LIKE would also copy indexes and AUTO_INCREMENT
+        let ddl = format!(
+            "CREATE TABLE {} AS SELECT * FROM {} WHERE 1 = 0;",
+            escaped_ident_bt(name),
+            escaped_ident_bt(like)
+        );
+        let res = self.queryable.query_drop(&ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(mysql::Error::MySqlError(e)) if e.code == 1050 => {
+                Err(TableCreateError::TableExists)
+            }
+            Err(e) => Err(TableCreateError::Connector(ConnectorError::MySQL(e))),
+        }
+    }
+
     fn table_drop(&mut self, name: &str) -> Result<(), TableDropError> {
         let res = self
             .queryable
@@ -98,4 +201,185 @@ impl<C: Queryable> SchemaEdit for super::MySQLConnection<C> {
             Err(e) => Err(TableDropError::Connector(ConnectorError::MySQL(e))),
         }
     }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        let ddl = format!(
+            "RENAME TABLE {} TO {};",
+            escaped_ident_bt(old_name),
+            escaped_ident_bt(new_name)
+        );
+        self.queryable.query_drop(ddl).map_err(table_edit_error)
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        let ddl = format!("TRUNCATE TABLE {};", escaped_ident_bt(name));
+        self.queryable.query_drop(ddl).map_err(table_edit_error)
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_add(self.type_mapper.as_deref(), table_name, field)?;
+        self.queryable.query_drop(ddl).map_err(column_edit_error)
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_drop(table_name, column_name);
+        self.queryable.query_drop(ddl).map_err(column_edit_error)
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {};",
+            escaped_ident_bt(table_name),
+            escaped_ident_bt(old_name),
+            escaped_ident_bt(new_name)
+        );
+        self.queryable.query_drop(ddl).map_err(column_edit_error)
+    }
+
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        // MODIFY COLUMN replaces the whole column definition, so we need to retain nullability
+        let columns = describe_table(&mut self.queryable, table_name)?;
+        let (_, _, null) = columns
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(column_name))
+            .ok_or(ColumnEditError::ColumnNonexistent)?;
+
+        let field = Field::new(column_name, data_type.clone(), null == "YES");
+        let ddl = ddl_column_modify(self.type_mapper.as_deref(), table_name, &field)?;
+        self.queryable.query_drop(ddl).map_err(column_edit_error)
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        // MODIFY COLUMN replaces the whole column definition, so we need to retain the type
+        let columns = describe_table(&mut self.queryable, table_name)?;
+        let (name, ty, _) = columns
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(column_name))
+            .ok_or(ColumnEditError::ColumnNonexistent)?;
+
+        let ddl = format!(
+            "ALTER TABLE {} MODIFY COLUMN {} {ty} NULL;",
+            escaped_ident_bt(table_name),
+            escaped_ident_bt(name)
+        );
+        self.queryable.query_drop(ddl).map_err(column_edit_error)
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        let mapper = self.type_mapper.as_deref();
+        Ok(match step {
+            MigrationStep::ColumnAdd(field) => ddl_column_add(mapper, table_name, field)?,
+            MigrationStep::ColumnDrop(name) => ddl_column_drop(table_name, name),
+            MigrationStep::ColumnWiden(field) | MigrationStep::ColumnDropNotNull(field) => {
+                ddl_column_modify(mapper, table_name, field)?
+            }
+        })
+    }
+}
+
+fn ddl_column_add(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    Ok(format!(
+        "ALTER TABLE {} ADD COLUMN {};",
+        escaped_ident_bt(table_name),
+        column_def(mapper, table_name, field, &TableOptions::default())?
+    ))
+}
+
+fn ddl_column_drop(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP COLUMN {};",
+        escaped_ident_bt(table_name),
+        escaped_ident_bt(column_name)
+    )
+}
+
+fn ddl_column_modify(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    Ok(format!(
+        "ALTER TABLE {} MODIFY COLUMN {};",
+        escaped_ident_bt(table_name),
+        column_def(mapper, table_name, field, &TableOptions::default())?
+    ))
+}
+
+fn db_type_of(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    let data_type = field.data_type();
+    crate::util::arrow_into_db::<MySQLConnection<mysql::Conn>>(mapper, field.name(), data_type)
+        .ok_or_else(|| {
+            crate::util::unsupported_column_type("MySQL", table_name, field.name(), data_type)
+        })
+}
+
+fn column_def(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+    options: &TableOptions,
+) -> Result<String, ConnectorError> {
+    let ty = db_type_of(mapper, table_name, field)?;
+
+    let is_nullable = field.is_nullable() || matches!(field.data_type(), DataType::Null);
+    let not_null = if is_nullable { "" } else { " NOT NULL" };
+
+    // expressions must be enclosed in parenthesis
+    let default = column_default(field, options)
+        .map(|d| format!(" DEFAULT ({d})"))
+        .unwrap_or_default();
+
+    // backslash is an escape character in MySQL string literals
+    let comment = column_comment(field, options)
+        .map(|c| format!(" COMMENT {}", escaped_literal(&c.replace('\\', "\\\\"))))
+        .unwrap_or_default();
+
+    let name = escaped_ident_bt(field.name());
+    Ok(format!("{name} {ty}{not_null}{default}{comment}"))
+}
+
+fn table_edit_error(e: mysql::Error) -> TableEditError {
+    match e {
+        mysql::Error::MySqlError(e) if e.code == 1146 => TableEditError::TableNonexistent,
+        mysql::Error::MySqlError(e) if e.code == 1050 => TableEditError::TableExists,
+        e => TableEditError::Connector(ConnectorError::MySQL(e)),
+    }
+}
+
+fn column_edit_error(e: mysql::Error) -> ColumnEditError {
+    match e {
+        mysql::Error::MySqlError(e) if e.code == 1146 => ColumnEditError::TableNonexistent,
+        mysql::Error::MySqlError(e) if e.code == 1060 => ColumnEditError::ColumnExists,
+        // 1091: can't drop, 1054: unknown column
+        mysql::Error::MySqlError(e) if e.code == 1091 || e.code == 1054 => {
+            ColumnEditError::ColumnNonexistent
+        }
+        e => ColumnEditError::Connector(ConnectorError::MySQL(e)),
+    }
 }
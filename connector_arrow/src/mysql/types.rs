@@ -1,16 +1,21 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow::datatypes::*;
 use mysql::consts::{ColumnFlags, ColumnType};
 use mysql::prelude::Protocol;
 
-use crate::api::Connector;
+use crate::api::TypeMapper;
 use crate::ConnectorError;
 
+/// Returns the schema that the rows are decoded into and, if the mapper changes it,
+/// the schema that the result should be converted into.
 pub fn get_result_schema<'a, P: Protocol>(
     result: &mysql::ResultSet<'a, 'a, 'a, 'a, P>,
-) -> Result<SchemaRef, ConnectorError> {
+    mapper: Option<&dyn TypeMapper>,
+) -> Result<(SchemaRef, Option<SchemaRef>), ConnectorError> {
     let mut fields = Vec::new();
+    let mut db_types = Vec::new();
     for column in result.columns().as_ref() {
         let is_unsigned = !(column.flags() & ColumnFlags::UNSIGNED_FLAG).is_empty();
         let is_not_null = !(column.flags() & ColumnFlags::NOT_NULL_FLAG).is_empty();
@@ -19,18 +24,34 @@ pub fn get_result_schema<'a, P: Protocol>(
 
         let db_ty = get_name_of_column_type(&column.column_type(), is_unsigned, is_binary);
         fields.push(create_field(
+            None,
             column.name_str().to_string(),
             db_ty,
             !is_not_null,
         ));
+        db_types.push(db_ty);
     }
 
-    Ok(Arc::new(Schema::new(fields)))
+    let schema = Schema::new(fields);
+    let mapped = crate::util::map_schema(mapper, &schema, db_types);
+    Ok((Arc::new(schema), mapped))
 }
 
-pub fn create_field(name: String, db_ty: &str, nullable: bool) -> Field {
-    let data_type = super::MySQLConnection::<mysql::Conn>::type_db_into_arrow(db_ty);
-    let data_type = data_type.unwrap_or_else(|| todo!("db type: {db_ty}"));
+pub fn create_field(
+    mapper: Option<&dyn TypeMapper>,
+    name: String,
+    db_ty: &str,
+    nullable: bool,
+) -> Field {
+    let data_type =
+        crate::util::db_into_arrow::<super::MySQLConnection<mysql::Conn>>(mapper, &name, db_ty);
+
+    // if we cannot map to an arrow type, map into a binary
+    let Some(data_type) = data_type else {
+        let metadata =
+            HashMap::from([(crate::api::METADATA_DB_TYPE.to_string(), db_ty.to_string())]);
+        return Field::new(name, DataType::Binary, nullable).with_metadata(metadata);
+    };
 
     Field::new(name, data_type, nullable)
 }
@@ -1,110 +1,555 @@
+use std::convert::TryFrom;
+use std::io::Write;
 use std::sync::Arc;
 
+use arrow::array::{Array, ArrayRef, AsArray, StringArray};
+use arrow::compute::CastOptions;
 use arrow::datatypes::*;
+use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
-use bytes::BytesMut;
+use bytes::{BufMut, BytesMut};
 use itertools::{zip_eq, Itertools};
-use postgres::binary_copy::BinaryCopyInWriter;
-use postgres::types::{to_sql_checked, IsNull, ToSql};
-use postgres::{Client, CopyInWriter};
+use postgres::error::SqlState;
+use postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+use postgres::Client;
 use postgres_protocol::types as postgres_proto;
 
-use crate::api::Append;
-use crate::types::{FixedSizeBinaryType, NullType};
+use crate::api::{Append, AppendOptions, AppendSummary};
+use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
+use crate::util::append::{
+    write_batch, AppendStats, AppendTarget, Savepoint, WriteRows, SAVEPOINT_NAME,
+};
+use crate::util::encode::encode_column;
 use crate::util::escape::escaped_ident;
 use crate::util::transport::{Consume, ConsumeTy};
 use crate::util::ArrayCellRef;
-use crate::{impl_consume_unsupported, ConnectorError};
+use crate::{impl_consume_unsupported, ConnectorError, ErrorContext};
 
-use super::PostgresError;
+use super::text::{self, TextColumn};
+use super::{CopyFormat, PostgresConnection, PostgresError};
 
+/// Appends within a transaction, which is committed by [Append::finish] and rolled back when
+/// the appender is dropped unfinished. When the connection is already in a transaction,
+/// the append is wrapped into a savepoint instead, leaving the outer transaction open.
 pub struct PostgresAppender<'c> {
-    writer: Writer<'c>,
+    conn: &'c mut PostgresConnection,
+    target: AppendTarget,
+    /// Prepared on the first batch.
+    columns: Option<TargetColumns>,
+    stats: AppendStats,
+    /// Append is nested in a transaction of the caller.
+    nested: bool,
+    committed: bool,
+}
+
+/// Name of the savepoint of an append that is nested in a transaction.
+const APPEND_SAVEPOINT_NAME: &str = "connector_arrow_append";
+
+/// Columns of the table that receive the appended fields.
+struct TargetColumns {
+    /// Escaped table name, followed by the list of the columns.
+    into: String,
+    column_types: Vec<Type>,
+    /// Names of the columns that receive the fields of the batch, in order.
+    table_columns: Vec<String>,
+    /// Columns as listed in `into`, None for all columns of the table.
+    names: Option<Vec<String>>,
 }
 
 impl<'conn> PostgresAppender<'conn> {
-    pub fn new(client: &'conn mut Client, table_name: &str) -> Result<Self, ConnectorError> {
-        let query = format!("COPY BINARY {} FROM stdin", escaped_ident(table_name));
-        let writer = client.copy_in(&query).map_err(PostgresError::Postgres)?;
-        let writer = Writer::Uninitialized(writer);
-        Ok(Self { writer })
+    pub fn new(
+        conn: &'conn mut PostgresConnection,
+        table_name: &str,
+        options: AppendOptions,
+    ) -> Result<Self, ConnectorError> {
+        // SAVEPOINT fails outside of a transaction block, without any side effects
+        let savepoint = format!("SAVEPOINT {APPEND_SAVEPOINT_NAME}");
+        let nested = match conn.client.batch_execute(&savepoint) {
+            Ok(()) => true,
+            Err(e) if e.code() == Some(&SqlState::NO_ACTIVE_SQL_TRANSACTION) => {
+                (conn.client)
+                    .batch_execute("BEGIN")
+                    .map_err(PostgresError::Postgres)?;
+                false
+            }
+            Err(e) => return Err(PostgresError::Postgres(e).into()),
+        };
+        Ok(PostgresAppender {
+            conn,
+            stats: AppendStats::new(&options),
+            target: AppendTarget::new(table_name, options),
+            columns: None,
+            nested,
+            committed: false,
+        })
+    }
+}
+
+impl TargetColumns {
+    fn prepare(
+        client: &mut Client,
+        table_name: &str,
+        names: Option<Vec<String>>,
+    ) -> Result<Self, ConnectorError> {
+        let table_name = escaped_ident(table_name);
+        let column_list = names
+            .as_ref()
+            .map(|c| c.iter().map(|c| escaped_ident(c)).join(","));
+
+        // types of the table columns determine the binary representation of some values
+        let query = format!(
+            "SELECT {} FROM {table_name} LIMIT 0",
+            column_list.as_deref().unwrap_or("*")
+        );
+        let stmt = client.prepare(&query).map_err(PostgresError::Postgres)?;
+        let column_types = stmt.columns().iter().map(|c| c.type_().clone()).collect();
+        let table_columns = stmt
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+
+        let column_list = column_list.map(|c| format!(" ({c})")).unwrap_or_default();
+        Ok(TargetColumns {
+            into: format!("{table_name}{column_list}"),
+            column_types,
+            table_columns,
+            names,
+        })
     }
 }
 
-enum Writer<'c> {
-    Uninitialized(CopyInWriter<'c>),
-    Invalid,
-    Initialized { writer: BinaryCopyInWriter<'c> },
+/// Signature, flags and header extension length of the binary COPY format.
+const COPY_HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+
+/// Field count of -1 marks the end of binary COPY data.
+const COPY_TRAILER: &[u8] = &(-1i16).to_be_bytes();
+
+impl<'conn> Append<'conn> for PostgresAppender<'conn> {
+    fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        let names = self.target.options().column_names(&batch.schema());
+        match &self.columns {
+            Some(columns) if columns.names == names => {}
+            Some(_) => {
+                return Err(ConnectorError::DataSchemaMismatch(
+                    "fields of the batch differ from fields of the first batch".to_string(),
+                ))
+            }
+            None => {
+                let table_name = self.target.prepare(self.conn, &batch.schema())?;
+                let columns = TargetColumns::prepare(&mut self.conn.client, table_name, names)?;
+                self.columns = Some(columns);
+            }
+        };
+        write_batch(self, &batch, self.target.options().on_error)
+    }
+
+    fn finish(mut self) -> Result<AppendSummary, ConnectorError> {
+        self.target.finish(self.conn)?;
+        let commit = if self.nested {
+            format!("RELEASE SAVEPOINT {APPEND_SAVEPOINT_NAME}")
+        } else {
+            "COMMIT".to_string()
+        };
+        (self.conn.client)
+            .batch_execute(&commit)
+            .map_err(PostgresError::Postgres)?;
+        self.committed = true;
+        self.stats.finish()
+    }
 }
 
-impl<'c> Writer<'c> {
-    fn as_binary(
-        &mut self,
-        schema: SchemaRef,
-    ) -> Result<&mut BinaryCopyInWriter<'c>, ConnectorError> {
-        if let Writer::Uninitialized(_) = self {
-            // replace plain writer with a new binary one
-            let Writer::Uninitialized(w) = std::mem::replace(self, Writer::Invalid) else {
-                unreachable!();
+impl WriteRows for PostgresAppender<'_> {
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError> {
+        let target = self.columns.as_ref().expect("target columns are prepared");
+
+        let Some(returning) = &self.target.options().generated_keys else {
+            let format = self.conn.copy_format;
+            let adapted = adapt_batch(batch.clone(), &target.column_types, format)?;
+            let schema = adapted.schema();
+            let data = match format {
+                CopyFormat::Binary => encode_binary_rows(&encode_columns(&adapted)?)?,
+                CopyFormat::Text | CopyFormat::Csv => {
+                    let mut columns = Vec::with_capacity(adapted.num_columns());
+                    for (array, field) in zip_eq(adapted.columns(), schema.fields()) {
+                        let mut column = TextColumn::with_capacity(format, adapted.num_rows());
+                        encode_column(field, array, 0..adapted.num_rows(), &mut column)?;
+                        columns.push(column);
+                    }
+                    text::encode_rows(&columns, adapted.num_rows())
+                }
             };
 
-            // types don't really matter
-            // they are used only for client-side checking of match between the
-            // declared type and passed value.
-            // Because our ToSql::accepts returns true
-            let types = vec![postgres::types::Type::VOID; schema.fields().len()];
+            let rows = copy_in(&mut self.conn.client, &target.into, format, &data)
+                .map_err(|e| copy_error(e, target, &schema))?;
+            self.stats.rows(rows);
+            return Ok(());
+        };
 
-            *self = Writer::Initialized {
-                writer: BinaryCopyInWriter::new(w, &types),
+        // COPY cannot return values, so INSERT is used instead
+        let adapted = adapt_batch(batch.clone(), &target.column_types, CopyFormat::Binary)?;
+        let columns = encode_columns(&adapted)?;
+
+        // Postgres allows at most 65535 parameters per statement
+        let rows_per_query = (65535 / columns.len().max(1)).clamp(1, 1000);
+
+        for chunk in &(0..adapted.num_rows()).chunks(rows_per_query) {
+            let chunk = chunk.collect_vec();
+            let query = insert_query(&target.into, columns.len(), chunk.len(), returning);
+
+            let params = (chunk.iter())
+                .flat_map(|row| columns.iter().map(move |c| c.field(*row)))
+                .collect_vec();
+            let params = params
+                .iter()
+                .map(|p| p as &(dyn ToSql + Sync))
+                .collect_vec();
+
+            let keys = (self.conn.client)
+                .query(&query, &params)
+                .map_err(PostgresError::Postgres)?;
+            for key in keys {
+                self.stats.generated_key(key.get(0));
+                self.stats.rows(1);
             }
         }
+        Ok(())
+    }
 
-        // return binary writer
-        let Writer::Initialized { writer } = self else {
-            unreachable!();
+    fn savepoint(&mut self, command: Savepoint) -> Result<(), ConnectorError> {
+        let query = match command {
+            Savepoint::Create => format!("SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Release => format!("RELEASE SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Rollback => format!(
+                "ROLLBACK TO SAVEPOINT {SAVEPOINT_NAME}; RELEASE SAVEPOINT {SAVEPOINT_NAME}"
+            ),
         };
-        Ok(writer)
+        Ok(self
+            .conn
+            .client
+            .batch_execute(&query)
+            .map_err(PostgresError::Postgres)?)
     }
 
-    fn finish(mut self) -> Result<u64, ConnectorError> {
-        let schema = Arc::new(Schema::new(vec![] as Vec<Field>));
-        self.as_binary(schema)?;
-        match self {
-            Writer::Initialized { writer: w, .. } => {
-                Ok(w.finish().map_err(PostgresError::Postgres)?)
+    fn stats(&mut self) -> &mut AppendStats {
+        &mut self.stats
+    }
+}
+
+impl Drop for PostgresAppender<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let rollback = if self.nested {
+                format!(
+                    "ROLLBACK TO SAVEPOINT {APPEND_SAVEPOINT_NAME}; \
+                    RELEASE SAVEPOINT {APPEND_SAVEPOINT_NAME}"
+                )
+            } else {
+                "ROLLBACK".to_string()
+            };
+            if let Err(e) = self.conn.client.batch_execute(&rollback) {
+                log::warn!("cannot roll back unfinished append: {e}");
             }
-            Writer::Uninitialized(_) | Writer::Invalid => unreachable!(),
         }
     }
 }
 
-impl<'conn> Append<'conn> for PostgresAppender<'conn> {
-    fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
-        let writer = self.writer.as_binary(batch.schema())?;
-
-        let schema = batch.schema();
-        let mut row = zip_eq(batch.columns(), schema.fields())
-            .map(|(array, field)| ArrayCellRef {
-                array,
-                field,
-                row_number: 0,
-            })
-            .collect_vec();
-
-        for row_number in 0..batch.num_rows() {
-            for cell in &mut row {
-                cell.row_number = row_number;
-            }
+/// Encodes all columns of the batch into binary representation.
+fn encode_columns(batch: &RecordBatch) -> Result<Vec<CopyColumn>, ConnectorError> {
+    let schema = batch.schema();
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (array, field) in zip_eq(batch.columns(), schema.fields()) {
+        let mut column = CopyColumn::with_capacity(batch.num_rows());
+        encode_column(field, array, 0..batch.num_rows(), &mut column)?;
+        columns.push(column);
+    }
+    Ok(columns)
+}
 
-            writer.write_raw(&row).map_err(PostgresError::Postgres)?;
+/// Interleaves encoded columns into tuples of binary COPY data.
+fn encode_binary_rows(columns: &[CopyColumn]) -> Result<Vec<u8>, ConnectorError> {
+    let field_count = i16::try_from(columns.len())
+        .map_err(|_| ConnectorError::DataSchemaMismatch("too many columns for COPY".to_string()))?;
+    let rows = columns.first().map_or(0, |c| c.ends.len());
+
+    let mut buf = Vec::with_capacity(
+        COPY_HEADER.len()
+            + columns.iter().map(|c| c.data.len()).sum::<usize>()
+            + rows * 2
+            + COPY_TRAILER.len(),
+    );
+    buf.extend_from_slice(COPY_HEADER);
+    let mut starts = vec![0; columns.len()];
+    for row in 0..rows {
+        buf.extend_from_slice(&field_count.to_be_bytes());
+        for (column, start) in columns.iter().zip(&mut starts) {
+            let end = column.ends[row];
+            buf.extend_from_slice(&column.data[*start..end]);
+            *start = end;
         }
-        Ok(())
     }
+    buf.extend_from_slice(COPY_TRAILER);
+    Ok(buf)
+}
 
-    fn finish(self) -> Result<(), ConnectorError> {
-        self.writer.finish()?;
-        Ok(())
+/// Writes encoded rows with COPY. Returns the number of copied rows.
+fn copy_in(
+    client: &mut Client,
+    into: &str,
+    format: CopyFormat,
+    data: &[u8],
+) -> Result<u64, ConnectorError> {
+    let format = match format {
+        CopyFormat::Binary => "binary",
+        CopyFormat::Text => "text",
+        CopyFormat::Csv => "csv",
+    };
+    let query = format!("COPY {into} FROM stdin (FORMAT {format})");
+
+    let mut writer = client.copy_in(&query).map_err(PostgresError::Postgres)?;
+    writer.write_all(data).map_err(PostgresError::IO)?;
+    Ok(writer.finish().map_err(PostgresError::Postgres)?)
+}
+
+/// Attaches the row and the column that COPY reports in the context of an error.
+fn copy_error(err: ConnectorError, target: &TargetColumns, schema: &Schema) -> ConnectorError {
+    let ConnectorError::Postgres(PostgresError::Postgres(e)) = &err else {
+        return err;
+    };
+    let Some(db_error) = e.as_db_error() else {
+        return err;
+    };
+
+    // context looks like: COPY t, line 2, column b
+    let location = (db_error.where_().unwrap_or_default().lines())
+        .find_map(|l| l.strip_prefix("COPY "))
+        .unwrap_or_default();
+    let mut row = None;
+    let mut column = db_error.column().map(str::to_string);
+    for part in location.split(", ") {
+        if let Some(line) = part.strip_prefix("line ") {
+            let line = line.split(':').next().unwrap_or_default();
+            row = line.parse::<usize>().ok().and_then(|l| l.checked_sub(1));
+        } else if let Some(name) = part.strip_prefix("column ") {
+            column = Some(name.split(':').next().unwrap_or_default().to_string());
+        }
     }
+
+    // report the field of the batch that was written into the column
+    let field = column.and_then(|c| target.table_columns.iter().position(|t| *t == c));
+    let field = field
+        .and_then(|i| schema.fields().get(i))
+        .map(|f| f.name().clone());
+    if row.is_none() && field.is_none() {
+        return err;
+    }
+    err.with_context(ErrorContext {
+        row,
+        column: field,
+        ..Default::default()
+    })
+}
+
+fn insert_query(into: &str, cols: usize, rows: usize, returning: &str) -> String {
+    let mut param = 0;
+    let values = (0..rows)
+        .map(|_| {
+            let row = (0..cols)
+                .map(|_| {
+                    param += 1;
+                    format!("${param}")
+                })
+                .join(",");
+            format!("({row})")
+        })
+        .join(",");
+    format!(
+        "INSERT INTO {into} VALUES {values} RETURNING {}::int8",
+        escaped_ident(returning)
+    )
+}
+
+/// Values of a column, encoded as fields of binary COPY tuples:
+/// length of the value, followed by its binary representation.
+struct CopyColumn {
+    data: BytesMut,
+    /// End of each value in `data`.
+    ends: Vec<usize>,
+}
+
+impl CopyColumn {
+    fn with_capacity(rows: usize) -> Self {
+        CopyColumn {
+            data: BytesMut::with_capacity(rows * 8),
+            ends: Vec::with_capacity(rows),
+        }
+    }
+}
+
+impl CopyColumn {
+    /// Binary representation of the value in `row`, None for NULL.
+    fn field(&self, row: usize) -> EncodedField<'_> {
+        let start = if row == 0 { 0 } else { self.ends[row - 1] };
+        let field = &self.data[start..self.ends[row]];
+        let (len, value) = field.split_at(4);
+        let is_null = len == (-1i32).to_be_bytes();
+        EncodedField((!is_null).then_some(value))
+    }
+}
+
+/// Value that was already encoded for the type of the column. Used as a query parameter.
+#[derive(Debug)]
+struct EncodedField<'a>(Option<&'a [u8]>);
+
+impl ToSql for EncodedField<'_> {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        match self.0 {
+            Some(value) => {
+                out.extend_from_slice(value);
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+impl Consume for CopyColumn {}
+
+impl<T: ArrowType> ConsumeTy<T> for CopyColumn
+where
+    BytesMut: ConsumeTy<T>,
+{
+    fn consume(&mut self, ty: &DataType, value: T::Native) {
+        let start = self.data.len();
+        self.data.put_i32(0);
+        ConsumeTy::<T>::consume(&mut self.data, ty, value);
+
+        let len = (self.data.len() - start - 4) as i32;
+        self.data[start..start + 4].copy_from_slice(&len.to_be_bytes());
+        self.ends.push(self.data.len());
+    }
+
+    fn consume_null(&mut self, _ty: &DataType) {
+        self.data.put_i32(-1);
+        self.ends.push(self.data.len());
+    }
+}
+
+/// Number of microseconds between 1970-01-01 and 2000-01-01, which is the PostgreSQL epoch.
+const UNIX_TO_PG_EPOCH_MICROS: i64 = 946_684_800_000_000;
+
+/// Number of days between 1970-01-01 and 2000-01-01, which is the PostgreSQL epoch.
+const UNIX_TO_PG_EPOCH_DAYS: i32 = 10_957;
+
+/// Converts the columns into the representation that COPY expects for the column
+/// types of the table. Arrays that are encoded as-is are not changed.
+fn adapt_batch(
+    batch: RecordBatch,
+    column_types: &[Type],
+    format: CopyFormat,
+) -> Result<RecordBatch, ConnectorError> {
+    if batch.num_columns() != column_types.len() {
+        // COPY will report the mismatch
+        return Ok(batch);
+    }
+
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(column_types.len());
+    let mut columns = Vec::with_capacity(column_types.len());
+    for ((array, field), db_ty) in zip_eq(zip_eq(batch.columns(), schema.fields()), column_types) {
+        let array = adapt_array(array, db_ty, format).map_err(|e| {
+            ConnectorError::DataSchemaMismatch(format!("column {}: {e}", field.name()))
+        })?;
+
+        fields.push(
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(array.data_type().clone()),
+        );
+        columns.push(array);
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+fn adapt_array(array: &ArrayRef, db_ty: &Type, format: CopyFormat) -> Result<ArrayRef, ArrowError> {
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+
+    Ok(match (array.data_type(), db_ty) {
+        (DataType::Timestamp(_, tz), &Type::TIMESTAMP | &Type::TIMESTAMPTZ)
+            if format != CopyFormat::Binary =>
+        {
+            let ty = DataType::Timestamp(TimeUnit::Microsecond, tz.clone());
+            let micros = arrow::compute::cast_with_options(array, &ty, &options)?;
+            let micros = micros.as_primitive::<TimestampMicrosecondType>();
+            let offset = if *db_ty == Type::TIMESTAMPTZ {
+                "+00"
+            } else {
+                ""
+            };
+            let texts = micros.iter().map(|v| {
+                v.map(|v| {
+                    let ts = chrono::DateTime::from_timestamp_micros(v)
+                        .ok_or_else(|| ArrowError::ComputeError("timestamp out of range".into()))?;
+                    Ok(format!("{}{offset}", ts.format("%Y-%m-%d %H:%M:%S%.6f")))
+                })
+                .transpose()
+            });
+            Arc::new(texts.collect::<Result<StringArray, ArrowError>>()?)
+        }
+        (DataType::Date32 | DataType::Date64, &Type::DATE) if format != CopyFormat::Binary => {
+            arrow::compute::cast_with_options(array, &DataType::Utf8, &options)?
+        }
+        (DataType::Timestamp(_, tz), &Type::TIMESTAMP | &Type::TIMESTAMPTZ) => {
+            // keep the timezone, so values are not converted into local time
+            let ty = DataType::Timestamp(TimeUnit::Microsecond, tz.clone());
+            let micros = arrow::compute::cast_with_options(array, &ty, &options)?;
+            let micros = micros.as_primitive::<TimestampMicrosecondType>();
+            Arc::new(micros.try_unary::<_, Int64Type, _>(|v| {
+                v.checked_sub(UNIX_TO_PG_EPOCH_MICROS)
+                    .ok_or_else(|| ArrowError::ComputeError("timestamp out of range".into()))
+            })?)
+        }
+        (DataType::Date32 | DataType::Date64, &Type::DATE) => {
+            let days = arrow::compute::cast_with_options(array, &DataType::Date32, &options)?;
+            let days = days.as_primitive::<Date32Type>();
+            Arc::new(days.try_unary::<_, Int32Type, _>(|v| {
+                v.checked_sub(UNIX_TO_PG_EPOCH_DAYS)
+                    .ok_or_else(|| ArrowError::ComputeError("date out of range".into()))
+            })?)
+        }
+        (ty, _) if ty.is_numeric() => {
+            let target = match *db_ty {
+                Type::INT2 => DataType::Int16,
+                Type::INT4 => DataType::Int32,
+                Type::INT8 => DataType::Int64,
+                Type::FLOAT4 => DataType::Float32,
+                Type::FLOAT8 => DataType::Float64,
+                _ => return Ok(array.clone()),
+            };
+            if *ty == target {
+                return Ok(array.clone());
+            }
+            arrow::compute::cast_with_options(array, &target, &options)?
+        }
+        _ => array.clone(),
+    })
 }
 
 impl ToSql for ArrayCellRef<'_> {
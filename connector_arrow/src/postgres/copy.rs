@@ -0,0 +1,306 @@
+use std::convert::TryFrom;
+use std::io::Read;
+use std::ops::Range;
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use postgres::types::Type;
+use postgres::CopyOutReader;
+
+use crate::api::ResultReader;
+use crate::util::escape::escaped_ident;
+use crate::util::{CastReader, CellReader, RowsReader};
+use crate::ConnectorError;
+
+use super::query::BinaryCell;
+use super::text::{self, TextCell};
+use super::{types, CopyFormat, CopySource, PostgresConnection, PostgresError};
+
+/// Reads the output of `COPY ... TO STDOUT`.
+pub struct PostgresCopyReader<'conn> {
+    schema: SchemaRef,
+    rows: CopyRows<'conn>,
+    rows_read: usize,
+}
+
+impl<'conn> PostgresCopyReader<'conn> {
+    pub(super) fn new(
+        conn: &'conn mut PostgresConnection,
+        source: CopySource<'_>,
+    ) -> Result<CastReader<Self>, ConnectorError> {
+        let (query, source) = match source {
+            CopySource::Table { schema, name } => {
+                let table_name = match schema {
+                    Some(schema) => format!("{}.{}", escaped_ident(schema), escaped_ident(name)),
+                    None => escaped_ident(name).to_string(),
+                };
+                (format!("SELECT * FROM {table_name}"), table_name)
+            }
+            CopySource::Query(query) => {
+                // the query is wrapped in parentheses, where a semicolon is a syntax error
+                let query = query.trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+                (query.to_string(), format!("({query})"))
+            }
+        };
+
+        // COPY does not describe its output, so the types are taken from the query
+        let stmt = (conn.client.prepare(&query)).map_err(PostgresError::Postgres)?;
+        let schema = types::pg_stmt_to_arrow(&stmt)?;
+        let db_types = stmt.columns().iter().map(|c| c.type_().name());
+        let mapped_schema = crate::util::map_schema(conn.type_mapper.as_deref(), &schema, db_types);
+        let column_types = stmt.columns().iter().map(|c| c.type_().clone()).collect();
+
+        let format = match conn.copy_format {
+            CopyFormat::Binary => "binary",
+            CopyFormat::Text => "text",
+            CopyFormat::Csv => "csv",
+        };
+        let copy_query = format!("COPY {source} TO STDOUT (FORMAT {format})");
+        log::debug!("copy out: {copy_query}");
+        let mut reader = (conn.client.copy_out(&copy_query)).map_err(PostgresError::Postgres)?;
+
+        let rows = match conn.copy_format {
+            CopyFormat::Binary => {
+                read_header(&mut reader)?;
+                CopyRows::Binary(BinaryRows {
+                    reader,
+                    column_types,
+                    data: Vec::new(),
+                    fields: Vec::new(),
+                    finished: false,
+                })
+            }
+            format => CopyRows::Text(TextRows {
+                reader,
+                format,
+                column_types,
+                line: Vec::new(),
+                fields: Vec::new(),
+                finished: false,
+            }),
+        };
+        Ok(CastReader::new(
+            PostgresCopyReader {
+                schema,
+                rows,
+                rows_read: 0,
+            },
+            mapped_schema,
+        ))
+    }
+}
+
+impl<'conn> ResultReader<'conn> for PostgresCopyReader<'conn> {
+    fn get_schema(&mut self) -> Result<SchemaRef, ConnectorError> {
+        Ok(self.schema.clone())
+    }
+}
+
+impl Iterator for PostgresCopyReader<'_> {
+    type Item = Result<RecordBatch, ConnectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (schema, rows_read) = (&self.schema, &mut self.rows_read);
+        match &mut self.rows {
+            CopyRows::Text(rows) => {
+                crate::util::next_batch_from_rows(schema, rows, 1024, rows_read)
+            }
+            CopyRows::Binary(rows) => {
+                crate::util::next_batch_from_rows(schema, rows, 1024, rows_read)
+            }
+        }
+        .transpose()
+    }
+}
+
+enum CopyRows<'conn> {
+    Text(TextRows<'conn>),
+    Binary(BinaryRows<'conn>),
+}
+
+/// Signature at the start of binary COPY data.
+const COPY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Reads the header of binary COPY data, up to the first tuple.
+fn read_header<R: Read>(reader: &mut R) -> Result<(), ConnectorError> {
+    let mut header = [0; 19];
+    reader.read_exact(&mut header).map_err(PostgresError::IO)?;
+    if &header[..11] != COPY_SIGNATURE {
+        return Err(malformed("invalid signature"));
+    }
+
+    // flags are followed by the length of the header extension, which is skipped
+    let extension_len = u32::from_be_bytes([header[15], header[16], header[17], header[18]]);
+    let mut extension = reader.take(u64::from(extension_len));
+    std::io::copy(&mut extension, &mut std::io::sink()).map_err(PostgresError::IO)?;
+    Ok(())
+}
+
+fn malformed(message: &str) -> ConnectorError {
+    ConnectorError::DataSchemaMismatch(format!("malformed COPY data: {message}"))
+}
+
+/// Tuples of binary COPY data.
+struct BinaryRows<'conn> {
+    reader: CopyOutReader<'conn>,
+    column_types: Vec<Type>,
+    /// Values of the current tuple.
+    data: Vec<u8>,
+    /// Range of each field in `data`, None for NULL.
+    fields: Vec<Option<Range<usize>>>,
+    finished: bool,
+}
+
+impl<'conn> RowsReader<'conn> for BinaryRows<'conn> {
+    type CellReader<'row>
+        = BinaryCellReader<'row>
+    where
+        Self: 'row;
+
+    fn next_row(&mut self) -> Result<Option<Self::CellReader<'_>>, ConnectorError> {
+        // the stream must not be read after its end
+        if self.finished {
+            return Ok(None);
+        }
+
+        let mut field_count = [0; 2];
+        (self.reader.read_exact(&mut field_count)).map_err(PostgresError::IO)?;
+        let field_count = i16::from_be_bytes(field_count);
+        if field_count == -1 {
+            // the trailer is the last message of the stream
+            let mut rest = Vec::new();
+            (self.reader.read_to_end(&mut rest)).map_err(PostgresError::IO)?;
+            self.finished = true;
+            return Ok(None);
+        }
+        if field_count as usize != self.column_types.len() {
+            return Err(ConnectorError::DataSchemaMismatch(format!(
+                "COPY returned {} fields, expected {}",
+                field_count,
+                self.column_types.len()
+            )));
+        }
+
+        self.data.clear();
+        self.fields.clear();
+        for _ in 0..field_count {
+            let mut len = [0; 4];
+            (self.reader.read_exact(&mut len)).map_err(PostgresError::IO)?;
+            let Ok(len) = usize::try_from(i32::from_be_bytes(len)) else {
+                // length of -1 marks NULL
+                self.fields.push(None);
+                continue;
+            };
+
+            let start = self.data.len();
+            self.data.resize(start + len, 0);
+            (self.reader.read_exact(&mut self.data[start..])).map_err(PostgresError::IO)?;
+            self.fields.push(Some(start..start + len));
+        }
+
+        Ok(Some(BinaryCellReader {
+            data: &self.data,
+            fields: &self.fields,
+            column_types: &self.column_types,
+            next_col: 0,
+        }))
+    }
+}
+
+struct BinaryCellReader<'row> {
+    data: &'row [u8],
+    fields: &'row [Option<Range<usize>>],
+    column_types: &'row [Type],
+    next_col: usize,
+}
+
+impl<'row> CellReader<'row> for BinaryCellReader<'row> {
+    type CellRef<'cell>
+        = BinaryCell<'cell>
+    where
+        Self: 'cell;
+
+    fn next_cell(&mut self) -> Option<Self::CellRef<'_>> {
+        let col = self.next_col;
+        self.next_col += 1;
+        let field = self.fields.get(col)?;
+        Some(BinaryCell {
+            value: field.clone().map(|range| &self.data[range]),
+            ty: &self.column_types[col],
+        })
+    }
+}
+
+/// Rows of text or CSV COPY data.
+struct TextRows<'conn> {
+    reader: CopyOutReader<'conn>,
+    format: CopyFormat,
+    column_types: Vec<Type>,
+    /// Buffers of the current row.
+    line: Vec<u8>,
+    fields: Vec<Option<String>>,
+    finished: bool,
+}
+
+impl<'conn> RowsReader<'conn> for TextRows<'conn> {
+    type CellReader<'row>
+        = TextCellReader<'row>
+    where
+        Self: 'row;
+
+    fn next_row(&mut self) -> Result<Option<Self::CellReader<'_>>, ConnectorError> {
+        // the stream must not be read after its end
+        if self.finished {
+            return Ok(None);
+        }
+        let has_row = text::read_row(
+            &mut self.reader,
+            self.format,
+            &mut self.line,
+            &mut self.fields,
+        )?;
+        if !has_row {
+            self.finished = true;
+            return Ok(None);
+        }
+        if self.column_types.is_empty() {
+            // rows without columns are empty lines
+            self.fields.clear();
+        }
+        if self.fields.len() != self.column_types.len() {
+            return Err(ConnectorError::DataSchemaMismatch(format!(
+                "COPY returned {} fields, expected {}",
+                self.fields.len(),
+                self.column_types.len()
+            )));
+        }
+
+        Ok(Some(TextCellReader {
+            fields: &self.fields,
+            column_types: &self.column_types,
+            next_col: 0,
+        }))
+    }
+}
+
+struct TextCellReader<'row> {
+    fields: &'row [Option<String>],
+    column_types: &'row [Type],
+    next_col: usize,
+}
+
+impl<'row> CellReader<'row> for TextCellReader<'row> {
+    type CellRef<'cell>
+        = TextCell<'cell>
+    where
+        Self: 'cell;
+
+    fn next_cell(&mut self) -> Option<Self::CellRef<'_>> {
+        let col = self.next_col;
+        self.next_col += 1;
+        Some(TextCell {
+            value: self.fields.get(col)?.as_deref(),
+            ty: &self.column_types[col],
+        })
+    }
+}
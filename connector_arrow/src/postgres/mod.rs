@@ -13,17 +13,23 @@
 //! ````
 
 mod append;
+mod copy;
 mod decimal;
 mod query;
 mod schema;
+mod text;
 mod types;
 
+use std::sync::Arc;
+
 use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
 use postgres::Client;
 use thiserror::Error;
 
-use crate::api::Connector;
-use crate::errors::ConnectorError;
+use crate::api::{AppendOptions, Connector, TypeMapper};
+use crate::cancel::CancelHandle;
+use crate::errors::{ConnectorError, ErrorKind};
+use crate::util::CastReader;
 
 /// Connection to PostgreSQL that implements [Connection], [crate::api::SchemaGet] and [crate::api::SchemaEdit].
 ///
@@ -32,11 +38,52 @@ use crate::errors::ConnectorError;
 /// - [ProtocolSimple]
 pub struct PostgresConnection {
     client: Client,
+    type_mapper: Option<Arc<dyn TypeMapper>>,
+    copy_format: CopyFormat,
+    #[cfg(feature = "tls-rustls")]
+    tls: Option<tokio_postgres_rustls::MakeRustlsConnect>,
 }
 
 impl PostgresConnection {
     pub fn new(client: Client) -> Self {
-        PostgresConnection { client }
+        PostgresConnection {
+            client,
+            type_mapper: None,
+            copy_format: CopyFormat::default(),
+            #[cfg(feature = "tls-rustls")]
+            tls: None,
+        }
+    }
+
+    /// Sets the TLS connector that the client was connected with.
+    ///
+    /// Cancel requests open a new connection to the server, which uses this connector.
+    /// Without it, they are sent unencrypted.
+    #[cfg(feature = "tls-rustls")]
+    pub fn with_tls(mut self, tls: tokio_postgres_rustls::MakeRustlsConnect) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Overrides the built-in type mapping for this connection.
+    pub fn with_type_mapper(mut self, mapper: impl TypeMapper + 'static) -> Self {
+        self.type_mapper = Some(Arc::new(mapper));
+        self
+    }
+
+    /// Sets the format of COPY, used by appenders and [PostgresConnection::copy_out].
+    pub fn with_copy_format(mut self, format: CopyFormat) -> Self {
+        self.copy_format = format;
+        self
+    }
+
+    /// Reads a table or the result of a query with `COPY ... TO STDOUT`,
+    /// which streams rows faster than [Connector::query].
+    pub fn copy_out(
+        &mut self,
+        source: CopySource<'_>,
+    ) -> Result<CastReader<copy::PostgresCopyReader<'_>>, ConnectorError> {
+        copy::PostgresCopyReader::new(self, source)
     }
 
     pub fn unwrap(self) -> Client {
@@ -48,11 +95,33 @@ impl PostgresConnection {
     }
 }
 
-// /// Protocol - Binary based bulk load
-// pub struct BinaryProtocol;
+/// Format of the data exchanged with COPY.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Binary representation of values, which is the fastest to encode and decode.
+    #[default]
+    Binary,
+
+    /// Tab-separated values in their text representation.
+    /// Supported by databases that implement the PostgreSQL protocol, but not binary COPY.
+    Text,
 
-// /// Protocol - CSV based bulk load
-// pub struct CSVProtocol;
+    /// Comma-separated values in their text representation.
+    Csv,
+}
+
+/// Source of the rows read by [PostgresConnection::copy_out].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopySource<'a> {
+    /// A table, which is found on the search path when the schema is not given.
+    Table {
+        schema: Option<&'a str>,
+        name: &'a str,
+    },
+
+    /// A single `SELECT`, `VALUES` or similar query. Trailing semicolons are ignored.
+    Query(&'a str),
+}
 
 #[derive(Error, Debug)]
 pub enum PostgresError {
@@ -69,6 +138,48 @@ pub enum PostgresError {
     IO(#[from] std::io::Error),
 }
 
+pub(crate) fn error_kind(err: &PostgresError) -> ErrorKind {
+    use postgres::error::SqlState;
+
+    let PostgresError::Postgres(err) = err else {
+        return ErrorKind::Other;
+    };
+    let io_error = std::error::Error::source(err).is_some_and(|e| e.is::<std::io::Error>());
+    if err.is_closed() || io_error {
+        return ErrorKind::ConnectionLost;
+    }
+    let Some(code) = err.code() else {
+        return ErrorKind::Other;
+    };
+    match code {
+        &SqlState::UNIQUE_VIOLATION => ErrorKind::UniqueViolation,
+        &SqlState::FOREIGN_KEY_VIOLATION => ErrorKind::ForeignKeyViolation,
+        &SqlState::NOT_NULL_VIOLATION => ErrorKind::NotNullViolation,
+        &SqlState::UNDEFINED_TABLE => ErrorKind::UndefinedTable,
+        &SqlState::UNDEFINED_COLUMN => ErrorKind::UndefinedColumn,
+        &SqlState::DUPLICATE_TABLE => ErrorKind::TableExists,
+        &SqlState::SYNTAX_ERROR => ErrorKind::SyntaxError,
+        &SqlState::T_R_SERIALIZATION_FAILURE => ErrorKind::SerializationFailure,
+        &SqlState::T_R_DEADLOCK_DETECTED => ErrorKind::Deadlock,
+        &SqlState::LOCK_NOT_AVAILABLE | &SqlState::IDLE_IN_TRANSACTION_SESSION_TIMEOUT => {
+            ErrorKind::Timeout
+        }
+        // statement_timeout is reported as a cancellation
+        &SqlState::QUERY_CANCELED => {
+            let message = err.as_db_error().map(|e| e.message()).unwrap_or_default();
+            if message.contains("timeout") {
+                ErrorKind::Timeout
+            } else {
+                ErrorKind::Cancelled
+            }
+        }
+        &SqlState::ADMIN_SHUTDOWN | &SqlState::CRASH_SHUTDOWN => ErrorKind::ConnectionLost,
+        // class 08: connection exception
+        c if c.code().starts_with("08") => ErrorKind::ConnectionLost,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl Connector for PostgresConnection {
     type Stmt<'conn>
         = query::PostgresStatement<'conn>
@@ -88,11 +199,16 @@ impl Connector for PostgresConnection {
         Ok(query::PostgresStatement {
             client: &mut self.client,
             stmt,
+            type_mapper: self.type_mapper.as_deref(),
         })
     }
 
-    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError> {
-        append::PostgresAppender::new(&mut self.client, table_name)
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError> {
+        append::PostgresAppender::new(self, table_name, options.clone())
     }
 
     fn type_db_into_arrow(ty: &str) -> Option<DataType> {
@@ -188,4 +304,31 @@ impl Connector for PostgresConnection {
             .into(),
         )
     }
+
+    fn quote_ident(ident: &str) -> String {
+        crate::util::escape::escaped_ident(ident).to_string()
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        self.type_mapper.as_deref()
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        let token = self.client.cancel_token();
+        #[cfg(feature = "tls-rustls")]
+        let tls = self.tls.clone();
+        Ok(CancelHandle::new(move || {
+            #[cfg(feature = "tls-rustls")]
+            if let Some(tls) = &tls {
+                token
+                    .cancel_query(tls.clone())
+                    .map_err(PostgresError::Postgres)?;
+                return Ok(());
+            }
+            token
+                .cancel_query(postgres::NoTls)
+                .map_err(PostgresError::Postgres)?;
+            Ok(())
+        }))
+    }
 }
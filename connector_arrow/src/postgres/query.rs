@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
 
@@ -5,10 +7,10 @@ use postgres::fallible_iterator::FallibleIterator;
 use postgres::types::{FromSql, Type};
 use postgres::{Client, Row, RowIter};
 
-use crate::api::{ResultReader, Statement};
+use crate::api::{Connector, ResultReader, Statement, TypeMapper};
 use crate::types::{ArrowType, FixedSizeBinaryType};
 use crate::util::CellReader;
-use crate::util::{transport, ArrayCellRef};
+use crate::util::{check_field_count, transport, ArrayCellRef, CastReader};
 use crate::{errors::ConnectorError, util::RowsReader};
 
 use super::{types, PostgresError};
@@ -16,11 +18,12 @@ use super::{types, PostgresError};
 pub struct PostgresStatement<'conn> {
     pub(super) client: &'conn mut Client,
     pub(super) stmt: postgres::Statement,
+    pub(super) type_mapper: Option<&'conn dyn TypeMapper>,
 }
 
 impl<'conn> Statement<'conn> for PostgresStatement<'conn> {
     type Reader<'stmt>
-        = PostgresBatchStream<'stmt>
+        = CastReader<PostgresBatchStream<'stmt>>
     where
         Self: 'stmt;
 
@@ -28,26 +31,89 @@ impl<'conn> Statement<'conn> for PostgresStatement<'conn> {
         &mut self,
         args: (&RecordBatch, usize),
     ) -> Result<Self::Reader<'_>, ConnectorError> {
-        let stmt = &self.stmt;
-        let schema = types::pg_stmt_to_arrow(stmt)?;
+        let schema = types::pg_stmt_to_arrow(&self.stmt)?;
+        let db_types = self.stmt.columns().iter().map(|c| c.type_().name());
+        let mapped_schema = crate::util::map_schema(self.type_mapper, &schema, db_types);
+
+        self.start_with_schemas(args, schema, mapped_schema)
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        let schema = types::pg_stmt_to_arrow(&self.stmt)?;
+        check_field_count(schema.fields().len(), &expected)?;
+
+        // decode directly into the expected types where possible
+        let columns = self.stmt.columns();
+        let fields: Vec<_> = (schema.fields().iter().zip(expected.fields()).zip(columns))
+            .map(|((field, expected), column)| {
+                if produces_directly(column.type_(), expected.data_type()) {
+                    Field::new(field.name(), expected.data_type().clone(), true)
+                } else {
+                    field.as_ref().clone()
+                }
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        self.start_with_schemas(args, schema, Some(expected))
+    }
+}
 
+impl PostgresStatement<'_> {
+    /// Rows are decoded into `schema` and then cast into `cast_schema`.
+    fn start_with_schemas(
+        &mut self,
+        args: (&RecordBatch, usize),
+        schema: SchemaRef,
+        cast_schema: Option<SchemaRef>,
+    ) -> Result<CastReader<PostgresBatchStream<'_>>, ConnectorError> {
         let arg_row = ArrayCellRef::vec_from_batch(args.0, args.1);
 
         // query
         let rows = self
             .client
-            .query_raw::<_, _, _>(stmt, &arg_row)
+            .query_raw::<_, _, _>(&self.stmt, &arg_row)
             .map_err(PostgresError::from)?;
 
         // create the row reader
         let row_reader = PostgresRowStream::new(rows);
-        Ok(PostgresBatchStream { schema, row_reader })
+        let reader = PostgresBatchStream {
+            schema,
+            row_reader,
+            rows_read: 0,
+        };
+        Ok(CastReader::new(reader, cast_schema))
+    }
+}
+
+/// Values of the database type can be produced into the arrow type without a cast.
+fn produces_directly(db_ty: &Type, ty: &DataType) -> bool {
+    match ty {
+        DataType::Utf8 => matches!(
+            *db_ty,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::NUMERIC
+        ),
+        DataType::LargeUtf8 => {
+            matches!(
+                *db_ty,
+                Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME
+            )
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            matches!(*db_ty, Type::TIMESTAMP | Type::TIMESTAMPTZ)
+        }
+        _ => super::PostgresConnection::type_db_into_arrow(db_ty.name()).as_ref() == Some(ty),
     }
 }
 
 pub struct PostgresBatchStream<'a> {
     schema: SchemaRef,
     row_reader: PostgresRowStream<'a>,
+    rows_read: usize,
 }
 
 impl<'a> ResultReader<'a> for PostgresBatchStream<'a> {
@@ -60,7 +126,9 @@ impl Iterator for PostgresBatchStream<'_> {
     type Item = Result<RecordBatch, ConnectorError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        crate::util::next_batch_from_rows(&self.schema, &mut self.row_reader, 1024).transpose()
+        let rows_read = &mut self.rows_read;
+        crate::util::next_batch_from_rows(&self.schema, &mut self.row_reader, 1024, rows_read)
+            .transpose()
     }
 }
 
@@ -112,6 +180,16 @@ type CellRef<'a> = (&'a Row, usize);
 
 impl<'c> transport::Produce<'c> for CellRef<'c> {}
 
+/// A field of binary COPY data, with the type of its column.
+/// Decoded with the same [FromSql] implementations as the fields of a [Row].
+#[derive(Debug)]
+pub(super) struct BinaryCell<'a> {
+    pub(super) value: Option<&'a [u8]>,
+    pub(super) ty: &'a Type,
+}
+
+impl<'c> transport::Produce<'c> for BinaryCell<'c> {}
+
 macro_rules! impl_produce {
     ($t: ty, $native: ty, $conversion_fn: expr) => {
         impl<'c> transport::ProduceTy<'c, $t> for CellRef<'c> {
@@ -125,9 +203,27 @@ macro_rules! impl_produce {
                 value.map($conversion_fn).transpose()
             }
         }
+
+        impl<'c> transport::ProduceTy<'c, $t> for BinaryCell<'c> {
+            fn produce(self) -> Result<<$t as ArrowType>::Native, ConnectorError> {
+                let value = <$native as FromSql>::from_sql_nullable(self.ty, self.value)
+                    .map_err(|e| decode_error(self.ty, e))?;
+                $conversion_fn(value)
+            }
+
+            fn produce_opt(self) -> Result<Option<<$t as ArrowType>::Native>, ConnectorError> {
+                let value = <Option<$native> as FromSql>::from_sql_nullable(self.ty, self.value)
+                    .map_err(|e| decode_error(self.ty, e))?;
+                value.map($conversion_fn).transpose()
+            }
+        }
     };
 }
 
+fn decode_error(ty: &Type, err: Box<dyn std::error::Error + Sync + Send>) -> ConnectorError {
+    ConnectorError::DataSchemaMismatch(format!("cannot decode {ty}: {err}"))
+}
+
 impl_produce!(BooleanType, bool, Result::Ok);
 impl_produce!(Int8Type, i8, Result::Ok);
 impl_produce!(Int16Type, i16, Result::Ok);
@@ -179,6 +275,33 @@ crate::impl_produce_unsupported!(
     )
 );
 
+crate::impl_produce_unsupported!(
+    BinaryCell<'r>,
+    (
+        UInt8Type,
+        UInt16Type,
+        UInt32Type,
+        UInt64Type,
+        Float16Type,
+        TimestampSecondType,
+        TimestampMillisecondType,
+        TimestampNanosecondType,
+        Date64Type,
+        Time32SecondType,
+        Time32MillisecondType,
+        Time64NanosecondType,
+        IntervalYearMonthType,
+        IntervalDayTimeType,
+        DurationSecondType,
+        DurationMillisecondType,
+        DurationMicrosecondType,
+        DurationNanosecondType,
+        FixedSizeBinaryType,
+        Decimal128Type,
+        Decimal256Type,
+    )
+);
+
 struct StrOrNum(String);
 
 impl StrOrNum {
@@ -1,14 +1,21 @@
 use std::sync::Arc;
 
-use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use itertools::Itertools;
 use postgres::error::SqlState;
 use postgres::types::Type;
 
-use crate::api::{Connector, SchemaEdit, SchemaGet};
+use crate::api::{
+    Connector, Relation, RelationKind, SchemaEdit, SchemaGet, TableOptions, TypeMapper,
+};
+use crate::migration::MigrationStep;
 use crate::postgres::PostgresConnection;
-use crate::util::escape::escaped_ident;
-use crate::{ConnectorError, TableCreateError, TableDropError};
+use crate::util::ddl::{
+    column_comment, column_default, table_constraints, with_column_comment, with_column_default,
+    Generated,
+};
+use crate::util::escape::{escaped_ident, escaped_literal};
+use crate::{ColumnEditError, ConnectorError, TableCreateError, TableDropError, TableEditError};
 
 use super::PostgresError;
 
@@ -26,34 +33,89 @@ impl SchemaGet for super::PostgresConnection {
         Ok(table_names)
     }
 
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        let query = "
+            SELECT relname, relkind, relpersistence
+            FROM pg_class
+            JOIN pg_namespace ON (relnamespace = pg_namespace.oid)
+            WHERE
+                (nspname = current_schema OR pg_namespace.oid = pg_my_temp_schema()) AND
+                relkind IN ('r', 'p', 'v', 'm', 'f')
+            ORDER BY relname
+        ";
+        let rows = self.client.query(query, &[]).map_err(PostgresError::from)?;
+
+        let relations = rows
+            .into_iter()
+            .map(|row| {
+                let name: String = row.get(0);
+                let relkind: i8 = row.get(1);
+                let relpersistence: i8 = row.get(2);
+
+                let kind = match (relkind as u8, relpersistence as u8) {
+                    (_, b't') => RelationKind::Temporary,
+                    (b'p', _) => RelationKind::Partitioned,
+                    (b'v', _) => RelationKind::View,
+                    (b'm', _) => RelationKind::MaterializedView,
+                    (b'f', _) => RelationKind::Foreign,
+                    _ => RelationKind::Table,
+                };
+                Relation { name, kind }
+            })
+            .collect_vec();
+        Ok(relations)
+    }
+
     fn table_get(
         &mut self,
         table_name: &str,
     ) -> Result<arrow::datatypes::SchemaRef, ConnectorError> {
+        // temporary relations shadow the relations in current schema
         let query = "
-            SELECT attname, atttypid, attnotnull
+            SELECT
+                attname, atttypid, attnotnull, pg_get_expr(adbin, adrelid),
+                attidentity <> '', attgenerated <> '', col_description(attrelid, attnum)
             FROM pg_attribute
-            JOIN pg_class ON (attrelid = pg_class.oid)
-            JOIN pg_namespace ON (relnamespace = pg_namespace.oid)
-            WHERE nspname = current_schema AND relname = $1 AND attnum > 0 AND atttypid > 0
+            LEFT JOIN pg_attrdef ON (adrelid = attrelid AND adnum = attnum)
+            WHERE attnum > 0 AND atttypid > 0 AND attrelid = (
+                SELECT pg_class.oid
+                FROM pg_class
+                JOIN pg_namespace ON (relnamespace = pg_namespace.oid)
+                WHERE
+                    (nspname = current_schema OR pg_namespace.oid = pg_my_temp_schema()) AND
+                    relname = $1
+                ORDER BY pg_namespace.oid = pg_my_temp_schema() DESC
+                LIMIT 1
+            )
             ORDER BY attnum;
         ";
         let res = self.client.query(query, &[&table_name.to_string()]);
         let rows = res.map_err(PostgresError::Postgres)?;
 
+        let mapper = self.type_mapper.as_deref();
         let fields: Vec<_> = rows
             .into_iter()
             .map(|row| -> Result<_, ConnectorError> {
                 let name: String = row.get(0);
                 let typid: u32 = row.get(1);
                 let not_null: bool = row.get(2);
+                let default: Option<String> = row.get(3);
+                let generated = match (row.get(4), row.get(5)) {
+                    (true, _) => Some(Generated::Identity),
+                    (_, true) => Some(Generated::Computed),
+                    _ => None,
+                };
+                let comment: Option<String> = row.get(6);
 
                 let ty = Type::from_oid(typid).ok_or(ConnectorError::NotSupported {
                     connector_name: "connector_arrow::postgres table_get",
                     feature: "custom types",
                 })?;
 
-                Ok(super::types::pg_field_to_arrow(name, &ty, !not_null))
+                let field = super::types::pg_field_to_arrow(name, &ty, !not_null);
+                let field = with_column_default(field, default, generated);
+                let field = with_column_comment(field, comment);
+                Ok(crate::util::map_field(mapper, field, ty.name()))
             })
             .try_collect()?;
 
@@ -62,29 +124,56 @@ impl SchemaGet for super::PostgresConnection {
 }
 
 impl SchemaEdit for super::PostgresConnection {
-    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError> {
         let column_defs = schema
             .fields()
             .iter()
-            .map(|field| {
-                let ty = PostgresConnection::type_arrow_into_db(field.data_type()).unwrap_or_else(
-                    || {
-                        unimplemented!("cannot store type {} in PostgreSQL", field.data_type());
-                    },
-                );
-
-                let is_nullable =
-                    field.is_nullable() || matches!(field.data_type(), DataType::Null);
-                let not_null = if is_nullable { "" } else { " NOT NULL" };
-
-                let name = escaped_ident(field.name());
-                format!("{name} {ty}{not_null}",)
-            })
+            .map(|field| column_def(self.type_mapper(), name, field, options))
+            .collect::<Result<Vec<_>, _>>()?
             .join(",");
+        let constraints = table_constraints(options, escaped_ident);
 
-        let ddl = format!("CREATE TABLE {} ({column_defs});", escaped_ident(name));
+        let temporary = if options.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if options.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let mut ddl = format!(
+            "CREATE{temporary} TABLE{if_not_exists} {} ({column_defs}{constraints});",
+            escaped_ident(name)
+        );
+        for field in schema.fields() {
+            if let Some(comment) = column_comment(field, options) {
+                ddl += &comment_on_column(name, field.name(), comment);
+            }
+        }
 
-        let res = self.client.execute(&ddl, &[]);
+        // multiple statements of a simple query are executed in an implicit transaction
+        let res = self.client.batch_execute(&ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.code(), Some(&SqlState::DUPLICATE_TABLE)) => {
+                Err(TableCreateError::TableExists)
+            }
+            Err(e) => Err(TableCreateError::Connector(ConnectorError::Postgres(
+                PostgresError::Postgres(e),
+            ))),
+        }
+    }
+
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        let ddl = format!(
+            "CREATE TABLE {} (LIKE {})",
+            escaped_ident(name),
+            escaped_ident(like)
+        );
+        let res = self.client.batch_execute(&ddl);
         match res {
             Ok(_) => Ok(()),
             Err(e) if matches!(e.code(), Some(&SqlState::DUPLICATE_TABLE)) => {
@@ -105,7 +194,7 @@ impl SchemaEdit for super::PostgresConnection {
             Err(e)
                 if matches!(e.code(), Some(&SqlState::UNDEFINED_TABLE)) ||
                     // GlareDB will return such errors
-                    e.as_db_error().map_or(false, |e| {
+                    e.as_db_error().is_some_and(|e| {
                         e.message().starts_with("Error during planning: Table ")
                             && e.message().ends_with(" does not exist")
                     }) =>
@@ -120,4 +209,200 @@ impl SchemaEdit for super::PostgresConnection {
             ))),
         }
     }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME TO {}",
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        self.table_edit(&ddl)
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        let ddl = format!("TRUNCATE TABLE {}", escaped_ident(name));
+        self.table_edit(&ddl)
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        self.column_edit(&ddl_column_add(self.type_mapper(), table_name, field)?)
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        self.column_edit(&ddl_column_drop(table_name, column_name))
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            escaped_ident(table_name),
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        self.column_edit(&ddl)
+    }
+
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_alter_type(self.type_mapper(), table_name, column_name, data_type)?;
+        self.column_edit(&ddl)
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        self.column_edit(&ddl_column_drop_not_null(table_name, column_name))
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        let mapper = self.type_mapper();
+        Ok(match step {
+            MigrationStep::ColumnAdd(field) => ddl_column_add(mapper, table_name, field)?,
+            MigrationStep::ColumnDrop(name) => ddl_column_drop(table_name, name),
+            MigrationStep::ColumnWiden(field) => {
+                ddl_column_alter_type(mapper, table_name, field.name(), field.data_type())?
+            }
+            MigrationStep::ColumnDropNotNull(field) => {
+                ddl_column_drop_not_null(table_name, field.name())
+            }
+        })
+    }
+}
+
+impl PostgresConnection {
+    fn table_edit(&mut self, ddl: &str) -> Result<(), TableEditError> {
+        let res = self.client.batch_execute(ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.code(), Some(&SqlState::UNDEFINED_TABLE)) => {
+                Err(TableEditError::TableNonexistent)
+            }
+            Err(e) if matches!(e.code(), Some(&SqlState::DUPLICATE_TABLE)) => {
+                Err(TableEditError::TableExists)
+            }
+            Err(e) => Err(TableEditError::Connector(ConnectorError::Postgres(
+                PostgresError::Postgres(e),
+            ))),
+        }
+    }
+
+    fn column_edit(&mut self, ddl: &str) -> Result<(), ColumnEditError> {
+        let res = self.client.batch_execute(ddl);
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.code(), Some(&SqlState::UNDEFINED_TABLE)) => {
+                Err(ColumnEditError::TableNonexistent)
+            }
+            Err(e) if matches!(e.code(), Some(&SqlState::DUPLICATE_COLUMN)) => {
+                Err(ColumnEditError::ColumnExists)
+            }
+            Err(e) if matches!(e.code(), Some(&SqlState::UNDEFINED_COLUMN)) => {
+                Err(ColumnEditError::ColumnNonexistent)
+            }
+            Err(e) => Err(ColumnEditError::Connector(ConnectorError::Postgres(
+                PostgresError::Postgres(e),
+            ))),
+        }
+    }
+}
+
+fn db_type_of(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    column_name: &str,
+    data_type: &DataType,
+) -> Result<String, ConnectorError> {
+    crate::util::arrow_into_db::<PostgresConnection>(mapper, column_name, data_type).ok_or_else(
+        || crate::util::unsupported_column_type("PostgreSQL", table_name, column_name, data_type),
+    )
+}
+
+fn column_def(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+    options: &TableOptions,
+) -> Result<String, ConnectorError> {
+    let ty = db_type_of(mapper, table_name, field.name(), field.data_type())?;
+
+    let is_nullable = field.is_nullable() || matches!(field.data_type(), DataType::Null);
+    let not_null = if is_nullable { "" } else { " NOT NULL" };
+
+    let default = column_default(field, options)
+        .map(|d| format!(" DEFAULT {d}"))
+        .unwrap_or_default();
+
+    let name = escaped_ident(field.name());
+    Ok(format!("{name} {ty}{not_null}{default}"))
+}
+
+fn ddl_column_add(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    let options = TableOptions::default();
+    let mut ddl = format!(
+        "ALTER TABLE {} ADD COLUMN {};",
+        escaped_ident(table_name),
+        column_def(mapper, table_name, field, &options)?
+    );
+    if let Some(comment) = column_comment(field, &options) {
+        ddl += &comment_on_column(table_name, field.name(), comment);
+    }
+    Ok(ddl)
+}
+
+fn ddl_column_drop(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP COLUMN {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn ddl_column_alter_type(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    column_name: &str,
+    data_type: &DataType,
+) -> Result<String, ConnectorError> {
+    let ty = db_type_of(mapper, table_name, column_name, data_type)?;
+    let column_name = escaped_ident(column_name);
+    Ok(format!(
+        "ALTER TABLE {} ALTER COLUMN {column_name} TYPE {ty} USING {column_name}::{ty};",
+        escaped_ident(table_name),
+    ))
+}
+
+fn ddl_column_drop_not_null(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL;",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn comment_on_column(table_name: &str, column_name: &str, comment: &str) -> String {
+    format!(
+        "COMMENT ON COLUMN {}.{} IS {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name),
+        escaped_literal(comment)
+    )
 }
@@ -0,0 +1,583 @@
+//! Text and CSV formats of COPY.
+//!
+//! Values are written and read in their text representation, as produced by the output
+//! functions of PostgreSQL types with the default `DateStyle` (ISO) and `IntervalStyle`.
+
+use std::convert::TryFrom;
+use std::io::BufRead;
+
+use arrow::datatypes::*;
+use postgres::types::Type;
+
+use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
+use crate::util::transport::{Consume, ConsumeTy, Produce, ProduceTy};
+use crate::{impl_consume_unsupported, impl_produce_unsupported, ConnectorError};
+
+use super::{CopyFormat, PostgresError};
+
+/// Values of a column, encoded as fields of text or CSV COPY rows.
+pub(super) struct TextColumn {
+    format: CopyFormat,
+    data: String,
+    /// End of each value in `data`.
+    ends: Vec<usize>,
+}
+
+impl TextColumn {
+    pub(super) fn with_capacity(format: CopyFormat, rows: usize) -> Self {
+        TextColumn {
+            format,
+            data: String::with_capacity(rows * 8),
+            ends: Vec::with_capacity(rows),
+        }
+    }
+
+    fn push_value(&mut self, value: &str) {
+        match self.format {
+            CopyFormat::Csv => {
+                // always quoted, so empty strings are not read as NULL
+                self.data.push('"');
+                for c in value.chars() {
+                    if c == '"' {
+                        self.data.push('"');
+                    }
+                    self.data.push(c);
+                }
+                self.data.push('"');
+            }
+            _ => {
+                for c in value.chars() {
+                    match c {
+                        '\\' => self.data.push_str("\\\\"),
+                        '\n' => self.data.push_str("\\n"),
+                        '\r' => self.data.push_str("\\r"),
+                        '\t' => self.data.push_str("\\t"),
+                        c => self.data.push(c),
+                    }
+                }
+            }
+        }
+        self.ends.push(self.data.len());
+    }
+
+    fn push_null(&mut self) {
+        if self.format != CopyFormat::Csv {
+            self.data.push_str("\\N");
+        }
+        self.ends.push(self.data.len());
+    }
+}
+
+/// Interleaves encoded columns into rows of text or CSV COPY data.
+pub(super) fn encode_rows(columns: &[TextColumn], rows: usize) -> Vec<u8> {
+    let Some(first) = columns.first() else {
+        return "\n".repeat(rows).into_bytes();
+    };
+    let delimiter = if first.format == CopyFormat::Csv {
+        ','
+    } else {
+        '\t'
+    };
+
+    let mut buf = String::with_capacity(columns.iter().map(|c| c.data.len() + rows).sum());
+    let mut starts = vec![0; columns.len()];
+    for row in 0..rows {
+        for (index, (column, start)) in columns.iter().zip(&mut starts).enumerate() {
+            if index > 0 {
+                buf.push(delimiter);
+            }
+            let end = column.ends[row];
+            buf.push_str(&column.data[*start..end]);
+            *start = end;
+        }
+        buf.push('\n');
+    }
+    buf.into_bytes()
+}
+
+impl Consume for TextColumn {}
+
+macro_rules! impl_consume_ty {
+    ($ArrTy: ty) => {
+        impl_consume_ty!($ArrTy, |value: <$ArrTy as ArrowType>::Native, _ty| value
+            .to_string());
+    };
+
+    ($ArrTy: ty, $to_text: expr) => {
+        impl ConsumeTy<$ArrTy> for TextColumn {
+            fn consume(&mut self, ty: &DataType, value: <$ArrTy as ArrowType>::Native) {
+                let text = ($to_text)(value, ty);
+                self.push_value(&text);
+            }
+
+            fn consume_null(&mut self, _ty: &DataType) {
+                self.push_null();
+            }
+        }
+    };
+}
+
+impl ConsumeTy<NullType> for TextColumn {
+    fn consume(&mut self, _ty: &DataType, _: ()) {
+        self.push_null();
+    }
+
+    fn consume_null(&mut self, _ty: &DataType) {
+        self.push_null();
+    }
+}
+
+impl_consume_ty!(BooleanType, |value: bool, _ty| if value {
+    "t"
+} else {
+    "f"
+});
+impl_consume_ty!(Int8Type);
+impl_consume_ty!(Int16Type);
+impl_consume_ty!(Int32Type);
+impl_consume_ty!(Int64Type);
+impl_consume_ty!(UInt8Type);
+impl_consume_ty!(UInt16Type);
+impl_consume_ty!(UInt32Type);
+impl_consume_ty!(UInt64Type);
+impl_consume_ty!(Float16Type, |value: <Float16Type as ArrowType>::Native,
+                               _ty| float_text(
+    value.to_f64(),
+    value.to_string()
+));
+impl_consume_ty!(Float32Type, |value: f32, _ty| float_text(
+    f64::from(value),
+    value.to_string()
+));
+impl_consume_ty!(Float64Type, |value: f64, _ty| float_text(
+    value,
+    value.to_string()
+));
+impl_consume_ty!(TimestampSecondType);
+impl_consume_ty!(TimestampMillisecondType);
+impl_consume_ty!(TimestampMicrosecondType);
+impl_consume_ty!(TimestampNanosecondType);
+impl_consume_ty!(Date32Type);
+impl_consume_ty!(Date64Type);
+impl_consume_ty!(Time32SecondType);
+impl_consume_ty!(Time32MillisecondType);
+impl_consume_ty!(Time64MicrosecondType);
+impl_consume_ty!(Time64NanosecondType);
+impl_consume_ty!(DurationSecondType);
+impl_consume_ty!(DurationMillisecondType);
+impl_consume_ty!(DurationMicrosecondType);
+impl_consume_ty!(DurationNanosecondType);
+impl_consume_ty!(BinaryType, |value: Vec<u8>, _ty| bytea_text(&value));
+impl_consume_ty!(LargeBinaryType, |value: Vec<u8>, _ty| bytea_text(&value));
+impl_consume_ty!(FixedSizeBinaryType, |value: Vec<u8>, _ty| bytea_text(
+    &value
+));
+impl_consume_ty!(Utf8Type, |value: String, _ty| value);
+impl_consume_ty!(LargeUtf8Type, |value: String, _ty| value);
+impl_consume_ty!(Decimal128Type, |value: i128, ty: &DataType| {
+    let DataType::Decimal128(p, s) = ty else {
+        unreachable!()
+    };
+    Decimal128Type::format_decimal(value, *p, *s)
+});
+impl_consume_ty!(Decimal256Type, |value: i256, ty: &DataType| {
+    let DataType::Decimal256(p, s) = ty else {
+        unreachable!()
+    };
+    Decimal256Type::format_decimal(value, *p, *s)
+});
+
+impl_consume_unsupported!(
+    TextColumn,
+    (
+        IntervalYearMonthType,
+        IntervalDayTimeType,
+        IntervalMonthDayNanoType,
+    )
+);
+
+/// Text of a float, with the spelling of special values that PostgreSQL uses.
+fn float_text(value: f64, text: String) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        text
+    }
+}
+
+fn bytea_text(value: &[u8]) -> String {
+    format!("\\x{}", hex::encode(value))
+}
+
+/// Reads the next row of text or CSV COPY data into `fields`.
+/// Returns false at the end of data.
+pub(super) fn read_row<R: BufRead>(
+    reader: &mut R,
+    format: CopyFormat,
+    line: &mut Vec<u8>,
+    fields: &mut Vec<Option<String>>,
+) -> Result<bool, ConnectorError> {
+    line.clear();
+    fields.clear();
+    if reader.read_until(b'\n', line).map_err(PostgresError::IO)? == 0 {
+        return Ok(false);
+    }
+
+    if format == CopyFormat::Csv {
+        // quoted values can contain newlines
+        while quotes_open(line) {
+            if reader.read_until(b'\n', line).map_err(PostgresError::IO)? == 0 {
+                return Err(malformed("unterminated quoted value"));
+            }
+        }
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+
+    match format {
+        CopyFormat::Csv => split_csv(line, fields)?,
+        _ => split_text(line, fields)?,
+    }
+    Ok(true)
+}
+
+fn quotes_open(line: &[u8]) -> bool {
+    line.iter().filter(|b| **b == b'"').count() % 2 == 1
+}
+
+/// Splits a row of the text format into fields and resolves escape sequences.
+fn split_text(line: &[u8], fields: &mut Vec<Option<String>>) -> Result<(), ConnectorError> {
+    for field in line.split(|b| *b == b'\t') {
+        if field == b"\\N" {
+            fields.push(None);
+            continue;
+        }
+
+        let mut value = Vec::with_capacity(field.len());
+        let mut bytes = field.iter().copied().peekable();
+        while let Some(b) = bytes.next() {
+            if b != b'\\' {
+                value.push(b);
+                continue;
+            }
+            let Some(escaped) = bytes.next() else {
+                return Err(malformed("trailing backslash"));
+            };
+            value.push(match escaped {
+                b'b' => 0x08,
+                b'f' => 0x0c,
+                b'n' => b'\n',
+                b'r' => b'\r',
+                b't' => b'\t',
+                b'v' => 0x0b,
+                b'0'..=b'7' => {
+                    let mut code = u32::from(escaped - b'0');
+                    for _ in 0..2 {
+                        match bytes.peek() {
+                            Some(d @ b'0'..=b'7') => code = code * 8 + u32::from(d - b'0'),
+                            _ => break,
+                        }
+                        bytes.next();
+                    }
+                    code as u8
+                }
+                b'x' => {
+                    let mut code = 0;
+                    for _ in 0..2 {
+                        match bytes.peek().and_then(|d| (*d as char).to_digit(16)) {
+                            Some(d) => code = code * 16 + d,
+                            None => break,
+                        }
+                        bytes.next();
+                    }
+                    code as u8
+                }
+                other => other,
+            });
+        }
+        fields.push(Some(into_string(value)?));
+    }
+    Ok(())
+}
+
+/// Splits a row of the CSV format into fields.
+/// Unquoted empty fields are NULL, quoted empty fields are empty strings.
+// The csv crate does not tell quoted fields from unquoted ones, so it cannot be used here.
+fn split_csv(line: &[u8], fields: &mut Vec<Option<String>>) -> Result<(), ConnectorError> {
+    let mut value = Vec::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut bytes = line.iter().copied().peekable();
+    loop {
+        match bytes.next() {
+            Some(b'"') if in_quotes => {
+                if bytes.peek() == Some(&b'"') {
+                    value.push(b'"');
+                    bytes.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+            Some(b'"') => {
+                quoted = true;
+                in_quotes = true;
+            }
+            Some(b',') if !in_quotes => {
+                let field = std::mem::take(&mut value);
+                fields.push(csv_field(field, quoted)?);
+                quoted = false;
+            }
+            Some(b) => value.push(b),
+            None => {
+                fields.push(csv_field(value, quoted)?);
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn csv_field(value: Vec<u8>, quoted: bool) -> Result<Option<String>, ConnectorError> {
+    if quoted || !value.is_empty() {
+        Ok(Some(into_string(value)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn into_string(value: Vec<u8>) -> Result<String, ConnectorError> {
+    String::from_utf8(value).map_err(|_| malformed("value is not valid UTF-8"))
+}
+
+fn malformed(message: &str) -> ConnectorError {
+    ConnectorError::DataSchemaMismatch(format!("malformed COPY data: {message}"))
+}
+
+/// A field of text or CSV COPY data, with the type of its column.
+#[derive(Debug)]
+pub(super) struct TextCell<'a> {
+    pub(super) value: Option<&'a str>,
+    pub(super) ty: &'a Type,
+}
+
+impl<'a> TextCell<'a> {
+    fn parse<T>(
+        self,
+        parse: impl FnOnce(&'a str, &Type) -> Option<T>,
+    ) -> Result<Option<T>, ConnectorError> {
+        let Some(value) = self.value else {
+            return Ok(None);
+        };
+        match parse(value, self.ty) {
+            Some(v) => Ok(Some(v)),
+            None => Err(ConnectorError::DataSchemaMismatch(format!(
+                "cannot read {value:?} as {}",
+                self.ty
+            ))),
+        }
+    }
+}
+
+impl<'r> Produce<'r> for TextCell<'r> {}
+
+macro_rules! impl_produce {
+    ($t: ty, $parse: expr) => {
+        impl<'r> ProduceTy<'r, $t> for TextCell<'r> {
+            fn produce(self) -> Result<<$t as ArrowType>::Native, ConnectorError> {
+                self.parse($parse)?.ok_or_else(|| {
+                    ConnectorError::DataSchemaMismatch("unexpected NULL".to_string())
+                })
+            }
+
+            fn produce_opt(self) -> Result<Option<<$t as ArrowType>::Native>, ConnectorError> {
+                self.parse($parse)
+            }
+        }
+    };
+}
+
+impl_produce!(BooleanType, |v: &str, _ty: &Type| match v {
+    "t" => Some(true),
+    "f" => Some(false),
+    _ => None,
+});
+impl_produce!(Int8Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(Int16Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(Int32Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(Int64Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(Float32Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(Float64Type, |v: &str, _ty: &Type| v.parse().ok());
+impl_produce!(BinaryType, parse_binary);
+impl_produce!(LargeBinaryType, parse_binary);
+impl_produce!(Utf8Type, |v: &str, _ty: &Type| Some(v.to_string()));
+impl_produce!(LargeUtf8Type, |v: &str, _ty: &Type| Some(v.to_string()));
+impl_produce!(TimestampMicrosecondType, parse_timestamp);
+impl_produce!(Time64MicrosecondType, |v: &str, _ty: &Type| parse_time(v));
+impl_produce!(Date32Type, parse_date);
+impl_produce!(IntervalMonthDayNanoType, parse_interval);
+
+impl_produce_unsupported!(
+    TextCell<'r>,
+    (
+        UInt8Type,
+        UInt16Type,
+        UInt32Type,
+        UInt64Type,
+        Float16Type,
+        TimestampSecondType,
+        TimestampMillisecondType,
+        TimestampNanosecondType,
+        Date64Type,
+        Time32SecondType,
+        Time32MillisecondType,
+        Time64NanosecondType,
+        IntervalYearMonthType,
+        IntervalDayTimeType,
+        DurationSecondType,
+        DurationMillisecondType,
+        DurationMicrosecondType,
+        DurationNanosecondType,
+        FixedSizeBinaryType,
+        Decimal128Type,
+        Decimal256Type,
+    )
+);
+
+fn parse_binary(value: &str, ty: &Type) -> Option<Vec<u8>> {
+    match *ty {
+        Type::BYTEA => hex::decode(value.strip_prefix("\\x")?).ok(),
+        Type::BIT | Type::VARBIT => {
+            // bits are packed into bytes, most significant bit first
+            let mut bytes = vec![0u8; value.len().div_ceil(8)];
+            for (i, bit) in value.bytes().enumerate() {
+                match bit {
+                    b'1' => bytes[i / 8] |= 0x80 >> (i % 8),
+                    b'0' => {}
+                    _ => return None,
+                }
+            }
+            Some(bytes)
+        }
+        // types that have no arrow equivalent are read as their text representation
+        _ => Some(value.as_bytes().to_vec()),
+    }
+}
+
+// Dates are parsed without chrono, which cannot represent the full range of PostgreSQL years.
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Parses timestamps such as `2024-02-23 15:18:36.5`, followed by the UTC offset `+01`
+/// for `timestamptz` and by `BC` for years before the common era.
+fn parse_timestamp(value: &str, ty: &Type) -> Option<i64> {
+    let (value, bc) = strip_era(value);
+    let (date, time) = value.split_once(' ')?;
+    let (time, offset) = if *ty == Type::TIMESTAMPTZ {
+        let split = time.find(['+', '-'])?;
+        (&time[..split], parse_offset(&time[split..])?)
+    } else {
+        (time, 0)
+    };
+
+    let days = parse_days(date, bc)?;
+    let micros = days.checked_mul(MICROS_PER_DAY)?;
+    micros
+        .checked_add(parse_time(time)?)?
+        .checked_sub(offset * 1_000_000)
+}
+
+fn parse_date(value: &str, _ty: &Type) -> Option<i32> {
+    let (value, bc) = strip_era(value);
+    i32::try_from(parse_days(value, bc)?).ok()
+}
+
+/// Years before the common era are suffixed with `BC`.
+fn strip_era(value: &str) -> (&str, bool) {
+    match value.strip_suffix(" BC") {
+        Some(value) => (value, true),
+        None => (value, false),
+    }
+}
+
+/// Parses `YYYY-MM-DD` into days since 1970-01-01.
+fn parse_days(value: &str, bc: bool) -> Option<i64> {
+    let mut parts = value.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // year 1 BC is year 0
+    let year = if bc { 1 - year } else { year };
+
+    // days from civil, http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Parses `HH:MM:SS.ffffff` into microseconds. Hours are not limited to a day.
+fn parse_time(value: &str) -> Option<i64> {
+    let mut parts = value.splitn(3, ':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let (seconds, fraction) = match parts.next() {
+        Some(seconds) => seconds.split_once('.').unwrap_or((seconds, "")),
+        None => ("0", ""),
+    };
+    let seconds: i64 = seconds.parse().ok()?;
+    let fraction = format!("{fraction:0<6}");
+    let micros: i64 = fraction.get(..6)?.parse().ok()?;
+
+    let seconds = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60)?
+        .checked_add(seconds)?;
+    seconds.checked_mul(1_000_000)?.checked_add(micros)
+}
+
+/// Parses UTC offset such as `+01`, `-03:30` or `+00:19:32` into seconds.
+fn parse_offset(value: &str) -> Option<i64> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+')?),
+    };
+    let mut seconds = 0;
+    for (part, unit) in value.split(':').zip([3600, 60, 1]) {
+        seconds += part.parse::<i64>().ok()? * unit;
+    }
+    Some(if negative { -seconds } else { seconds })
+}
+
+/// Parses intervals in the `postgres` style, such as `1 year 2 mons -3 days 04:05:06.7`.
+fn parse_interval(value: &str, _ty: &Type) -> Option<IntervalMonthDayNano> {
+    let mut interval = IntervalMonthDayNano::new(0, 0, 0);
+    let mut tokens = value.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token.contains(':') {
+            let (negative, time) = match token.strip_prefix('-') {
+                Some(time) => (true, time),
+                None => (false, token.trim_start_matches('+')),
+            };
+            let nanos = parse_time(time)?.checked_mul(1000)?;
+            interval.nanoseconds = if negative { -nanos } else { nanos };
+            continue;
+        }
+
+        let amount: i32 = token.parse().ok()?;
+        match tokens.next()? {
+            "year" | "years" => interval.months += amount.checked_mul(12)?,
+            "mon" | "mons" => interval.months += amount,
+            "day" | "days" => interval.days += amount,
+            _ => return None,
+        }
+    }
+    Some(interval)
+}
@@ -1,66 +1,148 @@
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
-use itertools::zip_eq;
 use itertools::Itertools;
+use rusqlite::params_from_iter;
 use rusqlite::types::Value;
-use rusqlite::{params_from_iter, Transaction};
 
+use crate::api::{Append, AppendOptions, AppendSummary};
 use crate::impl_consume_unsupported;
 use crate::types::{FixedSizeBinaryType, NullType};
+use crate::util::append::{
+    write_batch, AppendStats, AppendTarget, Savepoint, WriteRows, SAVEPOINT_NAME,
+};
+use crate::util::encode::encode_batch;
 use crate::util::escape::escaped_ident;
-use crate::util::transport;
 use crate::util::transport::{Consume, ConsumeTy};
-use crate::util::ArrayCellRef;
-use crate::{api::Append, ConnectorError};
+use crate::ConnectorError;
+
+use super::SQLiteConnection;
 
 pub struct SQLiteAppender<'conn> {
-    table: String,
-    transaction: Transaction<'conn>,
+    conn: &'conn mut SQLiteConnection,
+    target: AppendTarget,
+    stats: AppendStats,
+    committed: bool,
 }
 
 impl<'conn> SQLiteAppender<'conn> {
-    pub fn new(table: String, transaction: Transaction<'conn>) -> Result<Self, ConnectorError> {
-        Ok(Self { table, transaction })
+    pub fn new(
+        conn: &'conn mut SQLiteConnection,
+        table_name: &str,
+        options: AppendOptions,
+    ) -> Result<Self, ConnectorError> {
+        conn.inner.execute_batch("BEGIN")?;
+        Ok(Self {
+            conn,
+            stats: AppendStats::new(&options),
+            target: AppendTarget::new(table_name, options),
+            committed: false,
+        })
+    }
+
+    fn insert(
+        &mut self,
+        query: &str,
+        params: impl Iterator<Item = Value>,
+    ) -> Result<(), ConnectorError> {
+        let params = params_from_iter(params);
+        if self.target.options().generated_keys.is_none() {
+            let rows = self.conn.inner.execute(query, params)?;
+            self.stats.rows(rows as u64);
+            return Ok(());
+        }
+
+        let mut stmt = self.conn.inner.prepare(query)?;
+        let mut rows = stmt.query(params)?;
+        while let Some(row) = rows.next()? {
+            self.stats.generated_key(row.get(0)?);
+            self.stats.rows(1);
+        }
+        Ok(())
     }
 }
 
 impl<'conn> Append<'conn> for SQLiteAppender<'conn> {
-    fn append(
-        &mut self,
-        batch: arrow::record_batch::RecordBatch,
-    ) -> Result<(), crate::ConnectorError> {
+    fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        self.target.prepare(self.conn, &batch.schema())?;
+        write_batch(self, &batch, self.target.options().on_error)
+    }
+
+    fn finish(mut self) -> Result<AppendSummary, ConnectorError> {
+        self.target.finish(self.conn)?;
+        self.conn.inner.execute_batch("COMMIT")?;
+        self.committed = true;
+        self.stats.finish()
+    }
+}
+
+impl WriteRows for SQLiteAppender<'_> {
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError> {
         // TODO: 30 is a guess, we need benchmarking to find the optimum value
         const BATCH_SIZE: usize = 30;
 
         let last_batch_size = batch.num_rows() % BATCH_SIZE;
+        let mut values = encode_batch::<Value>(batch)?;
+        let table = self.target.prepared_name().to_string();
+        let options = self.target.options();
+        let columns = options.column_names(&batch.schema());
+        let returning = options.generated_keys.clone();
+        let query = |rows| {
+            let columns = columns.as_deref();
+            insert_query(
+                &table,
+                columns,
+                batch.num_columns(),
+                rows,
+                returning.as_deref(),
+            )
+        };
 
-        let batch_query = insert_query(&self.table, batch.num_columns(), BATCH_SIZE);
-        for batch_number in 0..(batch.num_rows() / BATCH_SIZE) {
-            let rows_range = (batch_number * BATCH_SIZE)..((batch_number + 1) * BATCH_SIZE);
-
-            let params: Vec<Value> = collect_args(&batch, rows_range);
-            self.transaction
-                .execute(&batch_query, params_from_iter(params))?;
+        let batch_query = query(BATCH_SIZE);
+        for _ in 0..(batch.num_rows() / BATCH_SIZE) {
+            let params = values.by_ref().take(BATCH_SIZE * batch.num_columns());
+            self.insert(&batch_query, params)?;
         }
 
         if last_batch_size > 0 {
-            let rows_range = (batch.num_rows() - last_batch_size)..batch.num_rows();
-
-            let last_query = insert_query(&self.table, batch.num_columns(), last_batch_size);
-            let params: Vec<Value> = collect_args(&batch, rows_range);
-            self.transaction
-                .execute(&last_query, params_from_iter(params))?;
+            self.insert(&query(last_batch_size), values)?;
         }
 
         Ok(())
     }
 
-    fn finish(self) -> Result<(), crate::ConnectorError> {
-        Ok(self.transaction.commit()?)
+    fn savepoint(&mut self, command: Savepoint) -> Result<(), ConnectorError> {
+        let query = match command {
+            Savepoint::Create => format!("SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Release => format!("RELEASE SAVEPOINT {SAVEPOINT_NAME}"),
+            Savepoint::Rollback => format!(
+                "ROLLBACK TO SAVEPOINT {SAVEPOINT_NAME}; RELEASE SAVEPOINT {SAVEPOINT_NAME}"
+            ),
+        };
+        Ok(self.conn.inner.execute_batch(&query)?)
+    }
+
+    fn stats(&mut self) -> &mut AppendStats {
+        &mut self.stats
+    }
+}
+
+impl Drop for SQLiteAppender<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = self.conn.inner.execute_batch("ROLLBACK") {
+                log::warn!("cannot roll back unfinished append: {e}");
+            }
+        }
     }
 }
 
-fn insert_query(table_name: &str, cols: usize, rows: usize) -> String {
+fn insert_query(
+    table_name: &str,
+    columns: Option<&[String]>,
+    cols: usize,
+    rows: usize,
+    returning: Option<&str>,
+) -> String {
     let values = (0..rows)
         .map(|_| {
             let row = (0..cols).map(|_| "?").join(",");
@@ -68,28 +150,16 @@ fn insert_query(table_name: &str, cols: usize, rows: usize) -> String {
         })
         .join(",");
 
-    format!("INSERT INTO {} VALUES {values}", escaped_ident(table_name))
-}
-
-fn collect_args(batch: &RecordBatch, rows_range: std::ops::Range<usize>) -> Vec<Value> {
-    let mut res = Vec::with_capacity(rows_range.len() * batch.num_columns());
-
-    let schema = batch.schema();
-    let mut row = zip_eq(batch.columns(), schema.fields())
-        .map(|(array, field)| ArrayCellRef {
-            array,
-            field,
-            row_number: 0,
-        })
-        .collect_vec();
-
-    for row_number in rows_range {
-        for cell in &mut row {
-            cell.row_number = row_number;
-            transport::transport(cell.field, cell as &_, &mut res).unwrap();
-        }
-    }
-    res
+    let columns = columns
+        .map(|c| format!(" ({})", c.iter().map(|c| escaped_ident(c)).join(",")))
+        .unwrap_or_default();
+    let returning = returning
+        .map(|c| format!(" RETURNING {}", escaped_ident(c)))
+        .unwrap_or_default();
+    format!(
+        "INSERT INTO {}{columns} VALUES {values}{returning}",
+        escaped_ident(table_name)
+    )
 }
 
 impl Consume for Vec<Value> {}
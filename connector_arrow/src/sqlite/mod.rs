@@ -10,17 +10,30 @@ pub use append::SQLiteAppender;
 #[doc(hidden)]
 pub use query::SQLiteStatement;
 
-use crate::api::Connector;
-use crate::errors::ConnectorError;
+use std::sync::Arc;
+
+use crate::api::{AppendOptions, Connector, TypeMapper};
+use crate::cancel::CancelHandle;
+use crate::errors::{ConnectorError, ErrorKind};
 use arrow::datatypes::DataType;
 
 pub struct SQLiteConnection {
     inner: rusqlite::Connection,
+    type_mapper: Option<Arc<dyn TypeMapper>>,
 }
 
 impl SQLiteConnection {
     pub fn new(inner: rusqlite::Connection) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            type_mapper: None,
+        }
+    }
+
+    /// Overrides the built-in type mapping for this connection.
+    pub fn with_type_mapper(mut self, mapper: impl TypeMapper + 'static) -> Self {
+        self.type_mapper = Some(Arc::new(mapper));
+        self
     }
 
     pub fn unwrap(self) -> rusqlite::Connection {
@@ -32,6 +45,34 @@ impl SQLiteConnection {
     }
 }
 
+pub(crate) fn error_kind(err: &rusqlite::Error) -> ErrorKind {
+    use rusqlite::ffi;
+
+    let rusqlite::Error::SqliteFailure(failure, message) = err else {
+        return ErrorKind::Other;
+    };
+
+    // primary codes cover all of their extended codes, such as SQLITE_BUSY_SNAPSHOT
+    match failure.code {
+        ffi::ErrorCode::OperationInterrupted => return ErrorKind::Cancelled,
+        ffi::ErrorCode::DatabaseBusy | ffi::ErrorCode::DatabaseLocked => return ErrorKind::Busy,
+        _ => {}
+    }
+
+    match failure.extended_code {
+        ffi::SQLITE_CONSTRAINT_UNIQUE | ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+            return ErrorKind::UniqueViolation
+        }
+        ffi::SQLITE_CONSTRAINT_FOREIGNKEY => return ErrorKind::ForeignKeyViolation,
+        ffi::SQLITE_CONSTRAINT_NOTNULL => return ErrorKind::NotNullViolation,
+        _ => {}
+    }
+
+    // SQLite reports these as generic SQLITE_ERROR
+    let message = message.as_deref().unwrap_or_default();
+    crate::util::error_message::error_kind_from_message(message)
+}
+
 impl Connector for SQLiteConnection {
     type Stmt<'conn>
         = SQLiteStatement<'conn>
@@ -43,15 +84,20 @@ impl Connector for SQLiteConnection {
     where
         Self: 'conn;
 
-    fn query(&mut self, query: &str) -> Result<SQLiteStatement, ConnectorError> {
+    fn query(&mut self, query: &str) -> Result<SQLiteStatement<'_>, ConnectorError> {
         let stmt = self.inner.prepare(query)?;
-        Ok(SQLiteStatement { stmt })
+        Ok(SQLiteStatement {
+            stmt,
+            type_mapper: self.type_mapper.as_deref(),
+        })
     }
 
-    fn append<'a>(&'a mut self, table: &str) -> Result<Self::Append<'a>, ConnectorError> {
-        let transaction = self.inner.transaction()?;
-
-        SQLiteAppender::new(table.to_string(), transaction)
+    fn append_with<'a>(
+        &'a mut self,
+        table: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError> {
+        SQLiteAppender::new(self, table, options.clone())
     }
 
     fn type_db_into_arrow(database_ty: &str) -> Option<DataType> {
@@ -110,4 +156,20 @@ impl Connector for SQLiteConnection {
         };
         Some(s.to_string())
     }
+
+    fn quote_ident(ident: &str) -> String {
+        crate::util::escape::escaped_ident(ident).to_string()
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        self.type_mapper.as_deref()
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        let handle = self.inner.get_interrupt_handle();
+        Ok(CancelHandle::new(move || {
+            handle.interrupt();
+            Ok(())
+        }))
+    }
 }
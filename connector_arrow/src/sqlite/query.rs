@@ -5,17 +5,19 @@ use arrow::datatypes::*;
 use itertools::{zip_eq, Itertools};
 use rusqlite::types::{Type, Value};
 
-use crate::api::{Connector, Statement};
+use crate::api::{Connector, Statement, TypeMapper};
 use crate::types::FixedSizeBinaryType;
+use crate::util::coerce::cast_batch;
 use crate::util::transport::{self, Produce, ProduceTy};
+use crate::util::{check_field_count, ArrayCellRef, ArrowReader};
 use crate::util::{collect_rows_to_arrow, CellReader, RowsReader};
-use crate::util::{ArrayCellRef, ArrowReader};
 use crate::ConnectorError;
 
 use super::SQLiteConnection;
 
 pub struct SQLiteStatement<'conn> {
     pub stmt: rusqlite::Statement<'conn>,
+    pub(super) type_mapper: Option<&'conn dyn TypeMapper>,
 }
 
 impl<'conn> Statement<'conn> for SQLiteStatement<'conn> {
@@ -28,6 +30,55 @@ impl<'conn> Statement<'conn> for SQLiteStatement<'conn> {
         &mut self,
         args: (&RecordBatch, usize),
     ) -> Result<Self::Reader<'_>, ConnectorError> {
+        let rows = self.query_rows(args)?;
+        let schema = infer_schema(&self.stmt, &rows, self.stmt.column_count())?;
+        let batches = rows_to_arrow(schema.clone(), rows)?;
+
+        // declared types are not available, so mapping uses the storage classes of the values
+        let db_types = (schema.fields().iter())
+            .map(|f| SQLiteConnection::type_arrow_into_db(f.data_type()).unwrap_or_default())
+            .collect_vec();
+        let db_types = db_types.iter().map(String::as_str);
+        if let Some(mapped) = crate::util::map_schema(self.type_mapper, &schema, db_types) {
+            let batches = batches
+                .iter()
+                .map(|b| cast_batch(b, &mapped))
+                .collect::<Result<_, _>>()?;
+            return Ok(ArrowReader::new(mapped, batches));
+        }
+
+        Ok(ArrowReader::new(schema, batches))
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        check_field_count(self.stmt.column_count(), &expected)?;
+        let rows = self.query_rows(args)?;
+
+        // values of a column can have different storage classes, so we cannot use
+        // the inferred schema, but decode into a type that the expected type can be cast from
+        let fields = zip_eq(self.stmt.column_names(), expected.fields())
+            .map(|(name, field)| Field::new(name, decode_type(field.data_type()), true))
+            .collect_vec();
+        let schema = Arc::new(Schema::new(fields));
+
+        let batches = rows_to_arrow(schema, rows)?
+            .iter()
+            .map(|b| cast_batch(b, &expected))
+            .collect::<Result<_, _>>()?;
+        Ok(ArrowReader::new(expected, batches))
+    }
+}
+
+impl SQLiteStatement<'_> {
+    /// Executes the statement and reads all of the rows into a buffer.
+    fn query_rows(
+        &mut self,
+        args: (&RecordBatch, usize),
+    ) -> Result<Vec<Vec<Value>>, ConnectorError> {
         let column_count = self.stmt.column_count();
 
         // args
@@ -39,33 +90,43 @@ impl<'conn> Statement<'conn> for SQLiteStatement<'conn> {
         let args = args.iter().map(|x| x as &dyn rusqlite::ToSql).collect_vec();
 
         // query
-        let rows: Vec<Vec<Value>> = {
-            let mut rows_iter = self.stmt.query(args.as_slice())?;
-
-            // read all of the rows into a buffer
-            let mut rows = Vec::with_capacity(1024);
-            while let Some(row_ref) = rows_iter.next()? {
-                let mut row = Vec::with_capacity(column_count);
-                for col_index in 0..column_count {
-                    let value = row_ref.get::<_, Value>(col_index).unwrap();
-                    row.push(value);
-                }
-                rows.push(row);
-            }
-            rows
-        };
+        let mut rows_iter = self.stmt.query(args.as_slice())?;
 
-        // infer schema
-        let schema = infer_schema(&self.stmt, &rows, column_count)?;
+        let mut rows = Vec::with_capacity(1024);
+        while let Some(row_ref) = rows_iter.next()? {
+            let mut row = Vec::with_capacity(column_count);
+            for col_index in 0..column_count {
+                let value = row_ref.get::<_, Value>(col_index).unwrap();
+                row.push(value);
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
 
-        // iterate over rows and convert into arrow
-        let row_count = rows.len();
-        let mut rows = SQLiteRowsReader {
-            rows: rows.into_iter(),
-        };
-        let batches = collect_rows_to_arrow(schema.clone(), &mut rows, row_count)?;
+fn rows_to_arrow(
+    schema: SchemaRef,
+    rows: Vec<Vec<Value>>,
+) -> Result<Vec<RecordBatch>, ConnectorError> {
+    let row_count = rows.len();
+    let mut rows = SQLiteRowsReader {
+        rows: rows.into_iter(),
+    };
+    collect_rows_to_arrow(schema, &mut rows, row_count)
+}
 
-        Ok(ArrowReader::new(schema, batches))
+/// Type that values are decoded into, before they are cast into the expected type.
+fn decode_type(expected: &DataType) -> DataType {
+    match expected {
+        DataType::Null => DataType::Null,
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => DataType::Float64,
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => DataType::Utf8,
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => DataType::Binary,
+        _ => DataType::Int64,
     }
 }
 
@@ -145,58 +206,83 @@ impl CellReader<'_> for SQLiteCellReader {
 
 impl Produce<'_> for Value {}
 
+// values of a column can have different storage classes, so we convert between them
+
 impl ProduceTy<'_, Int64Type> for Value {
     fn produce(self) -> Result<i64, ConnectorError> {
-        unimplemented!()
+        ProduceTy::<Int64Type>::produce_opt(self)?
+            .ok_or_else(|| ConnectorError::DataSchemaMismatch("unexpected NULL".into()))
     }
     fn produce_opt(self) -> Result<Option<i64>, ConnectorError> {
         Ok(match self {
             Self::Null => None,
             Self::Integer(v) => Some(v),
-            _ => panic!("SQLite schema not inferred correctly"),
+            Self::Real(v) if v.fract() == 0.0 && v >= i64::MIN as f64 && v < i64::MAX as f64 => {
+                Some(v as i64)
+            }
+            Self::Text(v) => Some(
+                v.trim()
+                    .parse()
+                    .map_err(|_| cannot_convert(&v, "integer"))?,
+            ),
+            v => return Err(cannot_convert(&v, "integer")),
         })
     }
 }
 
 impl ProduceTy<'_, Float64Type> for Value {
     fn produce(self) -> Result<f64, ConnectorError> {
-        unimplemented!()
+        ProduceTy::<Float64Type>::produce_opt(self)?
+            .ok_or_else(|| ConnectorError::DataSchemaMismatch("unexpected NULL".into()))
     }
     fn produce_opt(self) -> Result<Option<f64>, ConnectorError> {
         Ok(match self {
             Self::Null => None,
+            Self::Integer(v) => Some(v as f64),
             Self::Real(v) => Some(v),
-            _ => panic!("SQLite schema not inferred correctly"),
+            Self::Text(v) => Some(v.trim().parse().map_err(|_| cannot_convert(&v, "real"))?),
+            v => return Err(cannot_convert(&v, "real")),
         })
     }
 }
 
 impl ProduceTy<'_, Utf8Type> for Value {
     fn produce(self) -> Result<String, ConnectorError> {
-        unimplemented!()
+        ProduceTy::<Utf8Type>::produce_opt(self)?
+            .ok_or_else(|| ConnectorError::DataSchemaMismatch("unexpected NULL".into()))
     }
     fn produce_opt(self) -> Result<Option<String>, ConnectorError> {
         Ok(match self {
             Self::Null => None,
+            Self::Integer(v) => Some(v.to_string()),
+            Self::Real(v) => Some(v.to_string()),
             Self::Text(v) => Some(v),
-            _ => panic!("SQLite schema not inferred correctly"),
+            Self::Blob(v) => {
+                Some(String::from_utf8(v).map_err(|e| cannot_convert(&e.as_bytes(), "text"))?)
+            }
         })
     }
 }
 
 impl ProduceTy<'_, BinaryType> for Value {
     fn produce(self) -> Result<Vec<u8>, ConnectorError> {
-        unimplemented!()
+        ProduceTy::<BinaryType>::produce_opt(self)?
+            .ok_or_else(|| ConnectorError::DataSchemaMismatch("unexpected NULL".into()))
     }
     fn produce_opt(self) -> Result<Option<Vec<u8>>, ConnectorError> {
         Ok(match self {
             Self::Null => None,
+            Self::Text(v) => Some(v.into_bytes()),
             Self::Blob(v) => Some(v),
-            _ => panic!("SQLite schema not inferred correctly"),
+            v => return Err(cannot_convert(&v, "blob")),
         })
     }
 }
 
+fn cannot_convert(value: &dyn std::fmt::Debug, into: &str) -> ConnectorError {
+    ConnectorError::DataSchemaMismatch(format!("cannot convert {value:?} into {into}"))
+}
+
 crate::impl_produce_unsupported!(
     Value,
     (
@@ -1,9 +1,19 @@
-use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use itertools::Itertools;
+use rusqlite::OptionalExtension;
 use std::sync::Arc;
 
-use crate::api::{Connector, SchemaEdit, SchemaGet};
-use crate::errors::{ConnectorError, TableCreateError, TableDropError};
+use crate::api::{
+    Connector, Relation, RelationKind, SchemaEdit, SchemaGet, TableOptions, TypeMapper,
+};
+use crate::errors::{
+    ColumnEditError, ConnectorError, ErrorKind, TableCreateError, TableDropError, TableEditError,
+};
+use crate::migration::MigrationStep;
+use crate::util::ddl::{
+    column_comment, column_default, table_constraints, with_column_comment, with_column_default,
+    Generated,
+};
 use crate::util::escape::escaped_ident;
 
 use super::types;
@@ -23,6 +33,32 @@ impl SchemaGet for SQLiteConnection {
         Ok(table_names)
     }
 
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        let query = "
+            SELECT name, type, FALSE FROM sqlite_master WHERE type IN ('table', 'view')
+            UNION ALL
+            SELECT name, type, TRUE FROM sqlite_temp_master WHERE type IN ('table', 'view')
+            ORDER BY name;
+        ";
+        let mut statement = self.inner.prepare(query)?;
+        let mut res = statement.query(())?;
+
+        let mut relations = Vec::new();
+        while let Some(row) = res.next()? {
+            let name: String = row.get(0)?;
+            let ty: String = row.get(1)?;
+            let is_temporary: bool = row.get(2)?;
+
+            let kind = match (ty.as_str(), is_temporary) {
+                ("view", _) => RelationKind::View,
+                (_, true) => RelationKind::Temporary,
+                _ => RelationKind::Table,
+            };
+            relations.push(Relation { name, kind });
+        }
+        Ok(relations)
+    }
+
     fn table_get(
         &mut self,
         table_name: &str,
@@ -32,14 +68,45 @@ impl SchemaGet for SQLiteConnection {
         let mut columns_res = statement.query(())?;
         // contains columns: cid, name, type, notnull, dflt_value, pk
 
-        let mut fields = Vec::new();
+        let mut columns = Vec::new();
         while let Some(row) = columns_res.next()? {
             let name: String = row.get(1)?;
             let ty: String = row.get(2)?;
             let not_null: bool = row.get(3)?;
+            let default: Option<String> = row.get(4)?;
+            let pk: i64 = row.get(5)?;
+            columns.push((name, ty, not_null, default, pk));
+        }
+
+        // temporary tables shadow the tables of the main database
+        let query_sql = "
+            SELECT sql FROM sqlite_temp_master WHERE type = 'table' AND name = ?1
+            UNION ALL
+            SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1;
+        ";
+        let mut statement = self.inner.prepare(query_sql)?;
+        let sql: Option<String> = statement
+            .query_row([table_name], |row| row.get(0))
+            .optional()?;
+        let mut comments = sql.as_deref().map(column_comments).unwrap_or_default();
+        comments.resize(columns.len(), None);
+
+        // a sole INTEGER PRIMARY KEY column is an alias of the rowid
+        let pk_count = columns.iter().filter(|c| c.4 > 0).count();
+        let is_rowid = |ty: &str, pk: i64| pk > 0 && pk_count == 1 && ty == "INTEGER";
 
-            let ty = types::decl_ty_to_arrow(&ty, &name, table_name)?;
-            fields.push(Field::new(name, ty, !not_null));
+        let mut fields = Vec::with_capacity(columns.len());
+        for ((name, ty, not_null, default, pk), comment) in columns.into_iter().zip(comments) {
+            let generated = is_rowid(&ty.to_uppercase(), pk).then_some(Generated::Identity);
+
+            let mapped = (self.type_mapper.as_deref()).and_then(|m| m.db_into_arrow(&name, &ty));
+            let ty = match mapped {
+                Some(ty) => ty,
+                None => types::decl_ty_to_arrow(&ty, &name, table_name)?,
+            };
+            let field = Field::new(name, ty, !not_null);
+            let field = with_column_default(field, default, generated);
+            fields.push(with_column_comment(field, comment));
         }
 
         Ok(Arc::new(Schema::new(fields)))
@@ -47,39 +114,264 @@ impl SchemaGet for SQLiteConnection {
 }
 
 impl SchemaEdit for SQLiteConnection {
-    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
-        table_create(self, name, schema)
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError> {
+        table_create(self, name, schema, options)
+    }
+
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        // CREATE TABLE ... AS SELECT would replace declared types with their affinities
+        let query_columns = format!("PRAGMA table_info({});", escaped_ident(like));
+        let mut statement = self
+            .inner
+            .prepare(&query_columns)
+            .map_err(ConnectorError::from)?;
+        let columns = statement
+            .query_map((), |row| {
+                let name: String = row.get(1)?;
+                let ty: String = row.get(2)?;
+                let not_null: bool = row.get(3)?;
+                let not_null = if not_null { " NOT NULL" } else { "" };
+                Ok(format!("{} {ty}{not_null}", escaped_ident(&name)))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(ConnectorError::from)?;
+        drop(statement);
+        if columns.is_empty() {
+            return Err(TableCreateError::Connector(
+                ConnectorError::IncompatibleSchema {
+                    table_name: like.to_string(),
+                    message: "table does not exist".into(),
+                    hint: None,
+                },
+            ));
+        }
+
+        let ddl = format!(
+            "CREATE TABLE {} ({});",
+            escaped_ident(name),
+            columns.join(",")
+        );
+        let res = self.inner.execute(&ddl, ());
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) if super::error_kind(&e) == ErrorKind::TableExists => {
+                Err(TableCreateError::TableExists)
+            }
+            Err(e) => Err(TableCreateError::Connector(ConnectorError::SQLite(e))),
+        }
     }
 
     fn table_drop(&mut self, name: &str) -> Result<(), TableDropError> {
         table_drop(self, name)
     }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME TO {};",
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        table_edit(self, &ddl)
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        // there is no TRUNCATE, but DELETE without WHERE is optimized in the same way
+        let ddl = format!("DELETE FROM {};", escaped_ident(name));
+        table_edit(self, &ddl)
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_add(self.type_mapper(), table_name, field);
+        column_edit(self, &ddl)
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        column_edit(self, &ddl_column_drop(table_name, column_name))
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        let ddl = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {};",
+            escaped_ident(table_name),
+            escaped_ident(old_name),
+            escaped_ident(new_name)
+        );
+        column_edit(self, &ddl)
+    }
+
+    fn column_alter_type(
+        &mut self,
+        _table_name: &str,
+        _column_name: &str,
+        _data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        Err(ColumnEditError::Connector(alter_type_not_supported()))
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        _table_name: &str,
+        _column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        Err(ColumnEditError::Connector(alter_nullability_not_supported()))
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        match step {
+            MigrationStep::ColumnAdd(field) => {
+                Ok(ddl_column_add(self.type_mapper(), table_name, field))
+            }
+            MigrationStep::ColumnDrop(name) => Ok(ddl_column_drop(table_name, name)),
+            MigrationStep::ColumnWiden(_) => Err(alter_type_not_supported()),
+            MigrationStep::ColumnDropNotNull(_) => Err(alter_nullability_not_supported()),
+        }
+    }
+}
+
+fn ddl_column_add(mapper: Option<&dyn TypeMapper>, table_name: &str, field: &Field) -> String {
+    format!(
+        "ALTER TABLE {} ADD COLUMN {};",
+        escaped_ident(table_name),
+        column_def(mapper, field, &TableOptions::default())
+    )
+}
+
+fn ddl_column_drop(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP COLUMN {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn alter_type_not_supported() -> ConnectorError {
+    // would require re-creating the table
+    ConnectorError::NotSupported {
+        connector_name: "SQLite",
+        feature: "altering column type",
+    }
+}
+
+fn alter_nullability_not_supported() -> ConnectorError {
+    // would require re-creating the table
+    ConnectorError::NotSupported {
+        connector_name: "SQLite",
+        feature: "altering column nullability",
+    }
+}
+
+fn column_def(mapper: Option<&dyn TypeMapper>, field: &Field, options: &TableOptions) -> String {
+    let ty =
+        crate::util::arrow_into_db::<SQLiteConnection>(mapper, field.name(), field.data_type())
+            .unwrap_or_default();
+
+    let not_null = if field.is_nullable() { "" } else { " NOT NULL" };
+
+    // non-literal expressions must be enclosed in parenthesis
+    let default = column_default(field, options)
+        .map(|d| format!(" DEFAULT ({d})"))
+        .unwrap_or_default();
+
+    // SQLite has no column comments, but it does keep the original CREATE TABLE
+    // statement (including SQL comments) in sqlite_schema
+    let comment = column_comment(field, options)
+        .map(|c| format!(" /* {} */", c.replace("*/", "* /")))
+        .unwrap_or_default();
+
+    let name = escaped_ident(field.name());
+    format!("{name} {ty}{not_null}{default}{comment}")
+}
+
+/// Reads comments of columns from the CREATE TABLE statement, in order of column definitions.
+///
+/// Comment of a column is the last `/* ... */` comment within its definition, as rendered by
+/// [column_def]. Table constraints that follow the columns produce additional items.
+fn column_comments(sql: &str) -> Vec<Option<String>> {
+    let Some(start) = sql.find('(') else {
+        return Vec::new();
+    };
+    let bytes = sql.as_bytes();
+    let mut comments = vec![None];
+    let mut depth = 0;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            // skip quoted identifiers and literals, doubled quotes are two quoted parts
+            q @ (b'\'' | b'"' | b'`' | b'[') => {
+                let end = if q == b'[' { b']' } else { q };
+                i += 1;
+                while i < bytes.len() && bytes[i] != end {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = sql[i + 2..].find("*/").map_or(sql.len(), |e| i + 2 + e);
+                if depth == 0 {
+                    let comment = sql[i + 2..end].trim().to_string();
+                    *comments.last_mut().unwrap() = Some(comment);
+                }
+                i = end + 1;
+            }
+            b'(' => depth += 1,
+            b')' if depth == 0 => break,
+            b')' => depth -= 1,
+            b',' if depth == 0 => comments.push(None),
+            _ => {}
+        }
+        i += 1;
+    }
+    comments
 }
 
 pub(crate) fn table_create(
     conn: &mut SQLiteConnection,
     name: &str,
     schema: SchemaRef,
+    options: &TableOptions,
 ) -> Result<(), TableCreateError> {
     let column_defs = schema
         .fields()
         .iter()
-        .map(|field| {
-            let ty = SQLiteConnection::type_arrow_into_db(field.data_type()).unwrap_or_default();
-
-            let not_null = if field.is_nullable() { "" } else { " NOT NULL" };
-
-            let name = escaped_ident(field.name());
-            format!("{name} {ty}{not_null}")
-        })
+        .map(|field| column_def(conn.type_mapper(), field, options))
         .join(",");
+    let constraints = table_constraints(options, escaped_ident);
 
-    let ddl = format!("CREATE TABLE {} ({column_defs});", escaped_ident(name));
+    let temporary = if options.temporary { " TEMPORARY" } else { "" };
+    let if_not_exists = if options.if_not_exists {
+        " IF NOT EXISTS"
+    } else {
+        ""
+    };
+    let ddl = format!(
+        "CREATE{temporary} TABLE{if_not_exists} {} ({column_defs}{constraints});",
+        escaped_ident(name)
+    );
 
     let res = conn.inner.execute(&ddl, ());
     match res {
         Ok(_) => Ok(()),
-        Err(e) if e.to_string().ends_with("already exists") => Err(TableCreateError::TableExists),
+        Err(e) if super::error_kind(&e) == ErrorKind::TableExists => {
+            Err(TableCreateError::TableExists)
+        }
         Err(e) => Err(TableCreateError::Connector(ConnectorError::SQLite(e))),
     }
 }
@@ -90,9 +382,40 @@ pub(crate) fn table_drop(conn: &mut SQLiteConnection, name: &str) -> Result<(),
     let res = conn.inner.execute(&ddl, ());
     match res {
         Ok(_) => Ok(()),
-        Err(e) if e.to_string().starts_with("no such table") => {
+        Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
             Err(TableDropError::TableNonexistent)
         }
         Err(e) => Err(TableDropError::Connector(ConnectorError::SQLite(e))),
     }
 }
+
+fn table_edit(conn: &mut SQLiteConnection, ddl: &str) -> Result<(), TableEditError> {
+    let res = conn.inner.execute(ddl, ());
+    match res {
+        Ok(_) => Ok(()),
+        Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
+            Err(TableEditError::TableNonexistent)
+        }
+        Err(e) if super::error_kind(&e) == ErrorKind::TableExists => {
+            Err(TableEditError::TableExists)
+        }
+        Err(e) => Err(TableEditError::Connector(ConnectorError::SQLite(e))),
+    }
+}
+
+fn column_edit(conn: &mut SQLiteConnection, ddl: &str) -> Result<(), ColumnEditError> {
+    let res = conn.inner.execute(ddl, ());
+    match res {
+        Ok(_) => Ok(()),
+        Err(e) if super::error_kind(&e) == ErrorKind::UndefinedTable => {
+            Err(ColumnEditError::TableNonexistent)
+        }
+        Err(e) if e.to_string().starts_with("duplicate column name") => {
+            Err(ColumnEditError::ColumnExists)
+        }
+        Err(e) if super::error_kind(&e) == ErrorKind::UndefinedColumn => {
+            Err(ColumnEditError::ColumnNonexistent)
+        }
+        Err(e) => Err(ColumnEditError::Connector(ConnectorError::SQLite(e))),
+    }
+}
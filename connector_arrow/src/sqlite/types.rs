@@ -20,7 +20,8 @@ pub fn decl_ty_to_arrow(decl_ty: &str, col: &str, table: &str) -> Result<DataTyp
         return Ok(DataType::LargeUtf8);
     }
 
-    if ty.contains("BLOB") {
+    // columns without declared type (i.e. computed columns of views) have BLOB affinity
+    if ty.is_empty() || ty.contains("BLOB") {
         return Ok(DataType::LargeBinary);
     }
 
@@ -1,77 +1,190 @@
 use std::borrow::Cow;
-use std::sync::Arc;
 
 use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
 use futures::{AsyncRead, AsyncWrite};
-use itertools::{zip_eq, Itertools};
+use itertools::Itertools;
 use tiberius::numeric::Numeric;
-use tiberius::{BulkLoadRequest, Client, ColumnData, TokenRow};
-use tokio::runtime::Runtime;
+use tiberius::{ColumnData, IntoSql, Query, TokenRow};
 
-use crate::api::Append;
+use crate::api::{Append, AppendOptions, AppendSummary};
 use crate::types::{FixedSizeBinaryType, NullType};
+use crate::util::append::{
+    write_batch, AppendStats, AppendTarget, Savepoint, WriteRows, SAVEPOINT_NAME,
+};
+use crate::util::encode::encode_batch;
 use crate::util::escape::escaped_ident;
 use crate::util::transport::{Consume, ConsumeTy};
-use crate::util::ArrayCellRef;
 use crate::{impl_consume_unsupported, ConnectorError};
 
+use super::TiberiusConnection;
+
 pub struct TiberiusAppender<'c, S: AsyncRead + AsyncWrite + Unpin + Send> {
-    rt: Arc<Runtime>,
-    bulk_load: BulkLoadRequest<'c, S>,
+    conn: &'c mut TiberiusConnection<S>,
+    target: AppendTarget,
+    stats: AppendStats,
+    committed: bool,
 }
 
 impl<'conn, S: AsyncRead + AsyncWrite + Unpin + Send> TiberiusAppender<'conn, S> {
     pub fn new(
-        rt: Arc<Runtime>,
-        client: &'conn mut Client<S>,
+        conn: &'conn mut TiberiusConnection<S>,
         table_name: &str,
+        options: AppendOptions,
     ) -> Result<Self, ConnectorError> {
-        let table_name = escaped_ident(table_name).to_string();
-
-        // Tiberius requires table_name to be 'conn, but does not really use it as such.
-        // We convert our '_ into 'conn here.
-        let table_name: &'conn str = unsafe { std::mem::transmute::<_, _>(table_name.as_str()) };
-
-        let bulk_load = client.bulk_insert(table_name);
-        let bulk_load = rt.block_on(bulk_load)?;
-
-        Ok(Self { rt, bulk_load })
+        conn.rt
+            .block_on(conn.client.execute("BEGIN TRANSACTION", &[]))?;
+        Ok(Self {
+            conn,
+            stats: AppendStats::new(&options),
+            target: AppendTarget::new(table_name, options),
+            committed: false,
+        })
     }
 }
 
 impl<'conn, S: AsyncRead + AsyncWrite + Unpin + Send> Append<'conn> for TiberiusAppender<'conn, S> {
     fn append(&mut self, batch: RecordBatch) -> Result<(), ConnectorError> {
+        self.target.prepare(self.conn, &batch.schema())?;
+        write_batch(self, &batch, self.target.options().on_error)
+    }
+
+    fn finish(mut self) -> Result<AppendSummary, ConnectorError> {
+        self.target.finish(self.conn)?;
+        (self.conn.rt).block_on(self.conn.client.execute("COMMIT", &[]))?;
+        self.committed = true;
+        self.stats.finish()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> WriteRows for TiberiusAppender<'_, S> {
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError> {
         let schema = batch.schema();
-        let mut row_ref = zip_eq(batch.columns(), schema.fields())
-            .map(|(array, field)| ArrayCellRef {
-                array,
-                field,
-                row_number: 0,
-            })
-            .collect_vec();
-
-        for row_number in 0..batch.num_rows() {
-            let mut tb_row = TokenRow::with_capacity(row_ref.len());
-            let mut buffer = Vec::with_capacity(1);
-            for cell_ref in &mut row_ref {
-                cell_ref.row_number = row_number;
-
-                crate::util::transport::transport(cell_ref.field, &*cell_ref, &mut buffer)?;
-                tb_row.push(buffer.pop().unwrap());
+        let table_name = &self.target.prepared_name().to_string();
+        let mut values = encode_batch::<ColumnData<'static>>(batch)?;
+        let TiberiusConnection { rt, client, .. } = &mut *self.conn;
+
+        let options = self.target.options();
+        let columns = options.column_names(&schema);
+        if columns.is_none() && options.generated_keys.is_none() {
+            let table_name = escaped_ident(table_name).to_string();
+            let mut bulk_load = rt.block_on(client.bulk_insert(&table_name))?;
+            for _ in 0..batch.num_rows() {
+                let mut tb_row = TokenRow::with_capacity(batch.num_columns());
+                for value in values.by_ref().take(batch.num_columns()) {
+                    tb_row.push(value);
+                }
+
+                let f = bulk_load.send(tb_row);
+                rt.block_on(f)?;
+            }
+            let res = rt.block_on(bulk_load.finalize())?;
+            self.stats.rows(res.total());
+            return Ok(());
+        }
+
+        // Bulk load must contain all columns of the table and cannot return values,
+        // so INSERT statements are used instead.
+        let cols = batch.num_columns();
+        let returning = options.generated_keys.as_deref();
+
+        // SQL Server allows at most 2100 parameters and 1000 rows per statement
+        let rows_per_query = (2000 / cols.max(1)).clamp(1, 1000);
+
+        let mut remaining = batch.num_rows();
+        while remaining > 0 {
+            let rows = remaining.min(rows_per_query);
+            remaining -= rows;
+
+            let query = insert_query(table_name, columns.as_deref(), cols, rows, returning);
+            let mut query = Query::new(query);
+            for value in values.by_ref().take(rows * cols) {
+                query.bind(Param(value));
             }
 
-            let f = self.bulk_load.send(tb_row);
-            self.rt.block_on(f)?;
+            if returning.is_none() {
+                let res = rt.block_on(query.execute(client))?;
+                self.stats.rows(res.total());
+            } else {
+                let keys =
+                    rt.block_on(async { query.query(client).await?.into_first_result().await })?;
+                for key in keys {
+                    self.stats
+                        .generated_key(key.try_get(0)?.unwrap_or_default());
+                    self.stats.rows(1);
+                }
+            }
         }
         Ok(())
     }
 
-    fn finish(self) -> Result<(), ConnectorError> {
-        let res = self.bulk_load.finalize();
-        self.rt.block_on(res)?;
+    fn savepoint(&mut self, command: Savepoint) -> Result<(), ConnectorError> {
+        let query = match command {
+            Savepoint::Create => format!("SAVE TRANSACTION {SAVEPOINT_NAME}"),
+            // SQL Server savepoints cannot be released
+            Savepoint::Release => return Ok(()),
+            Savepoint::Rollback => format!("ROLLBACK TRANSACTION {SAVEPOINT_NAME}"),
+        };
+        let TiberiusConnection { rt, client, .. } = &mut *self.conn;
+        rt.block_on(client.execute(query, &[]))?;
         Ok(())
     }
+
+    fn stats(&mut self) -> &mut AppendStats {
+        &mut self.stats
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Drop for TiberiusAppender<'_, S> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let rollback = self.conn.client.execute("ROLLBACK", &[]);
+            if let Err(e) = self.conn.rt.block_on(rollback) {
+                log::warn!("cannot roll back unfinished append: {e}");
+            }
+        }
+    }
+}
+
+fn insert_query(
+    table_name: &str,
+    columns: Option<&[String]>,
+    cols: usize,
+    rows: usize,
+    returning: Option<&str>,
+) -> String {
+    let mut param = 0;
+    let values = (0..rows)
+        .map(|_| {
+            let row = (0..cols)
+                .map(|_| {
+                    param += 1;
+                    format!("@P{param}")
+                })
+                .join(",");
+            format!("({row})")
+        })
+        .join(",");
+
+    let columns = columns
+        .map(|c| format!(" ({})", c.iter().map(|c| escaped_ident(c)).join(",")))
+        .unwrap_or_default();
+    let output = returning
+        .map(|c| format!(" OUTPUT CAST(inserted.{} AS BIGINT)", escaped_ident(c)))
+        .unwrap_or_default();
+    format!(
+        "INSERT INTO {}{columns}{output} VALUES {values}",
+        escaped_ident(table_name),
+    )
+}
+
+/// Binds an encoded value as a query parameter.
+struct Param(ColumnData<'static>);
+
+impl<'a> IntoSql<'a> for Param {
+    fn into_sql(self) -> ColumnData<'a> {
+        self.0
+    }
 }
 
 impl Consume for Vec<ColumnData<'static>> {}
@@ -9,17 +9,29 @@ use itertools::Itertools;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
-use crate::api::Connector;
-use crate::ConnectorError;
+use crate::api::{AppendOptions, Connector, TypeMapper};
+use crate::cancel::CancelHandle;
+use crate::{ConnectorError, ErrorKind};
 
 pub struct TiberiusConnection<S: AsyncRead + AsyncWrite + Unpin + Send> {
     rt: Arc<Runtime>,
     client: tiberius::Client<S>,
+    type_mapper: Option<Arc<dyn TypeMapper>>,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin + Send> TiberiusConnection<S> {
     pub fn new(rt: Arc<Runtime>, client: tiberius::Client<S>) -> Self {
-        TiberiusConnection { rt, client }
+        TiberiusConnection {
+            rt,
+            client,
+            type_mapper: None,
+        }
+    }
+
+    /// Overrides the built-in type mapping for this connection.
+    pub fn with_type_mapper(mut self, mapper: impl TypeMapper + 'static) -> Self {
+        self.type_mapper = Some(Arc::new(mapper));
+        self
     }
 
     pub fn unwrap(self) -> (Arc<Runtime>, tiberius::Client<S>) {
@@ -31,6 +43,26 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> TiberiusConnection<S> {
     }
 }
 
+pub(crate) fn error_kind(err: &tiberius::error::Error) -> ErrorKind {
+    match err {
+        tiberius::error::Error::Server(e) => match e.code() {
+            2601 | 2627 => ErrorKind::UniqueViolation,
+            547 if e.message().contains("FOREIGN KEY") => ErrorKind::ForeignKeyViolation,
+            515 => ErrorKind::NotNullViolation,
+            208 => ErrorKind::UndefinedTable,
+            207 => ErrorKind::UndefinedColumn,
+            2714 => ErrorKind::TableExists,
+            102 | 156 => ErrorKind::SyntaxError,
+            3960 => ErrorKind::SerializationFailure,
+            1205 => ErrorKind::Deadlock,
+            1222 => ErrorKind::Timeout,
+            _ => ErrorKind::Other,
+        },
+        tiberius::error::Error::Io { .. } => ErrorKind::ConnectionLost,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl<S: AsyncRead + AsyncWrite + Unpin + Send> Connector for TiberiusConnection<S> {
     type Stmt<'conn>
         = query::TiberiusStatement<'conn, S>
@@ -49,8 +81,12 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> Connector for TiberiusConnection<
         })
     }
 
-    fn append<'a>(&'a mut self, table_name: &str) -> Result<Self::Append<'a>, ConnectorError> {
-        append::TiberiusAppender::new(self.rt.clone(), &mut self.client, table_name)
+    fn append_with<'a>(
+        &'a mut self,
+        table_name: &str,
+        options: &AppendOptions,
+    ) -> Result<Self::Append<'a>, ConnectorError> {
+        append::TiberiusAppender::new(self, table_name, options.clone())
     }
 
     #[allow(clippy::get_first)]
@@ -155,6 +191,22 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> Connector for TiberiusConnection<
             .to_string(),
         )
     }
+
+    fn quote_ident(ident: &str) -> String {
+        crate::util::escape::escaped_ident(ident).to_string()
+    }
+
+    fn type_mapper(&self) -> Option<&dyn TypeMapper> {
+        self.type_mapper.as_deref()
+    }
+
+    fn cancel_handle(&self) -> Result<CancelHandle, ConnectorError> {
+        // tiberius does not expose sending attention packets
+        Err(ConnectorError::NotSupported {
+            connector_name: "SQL Server",
+            feature: "query cancellation",
+        })
+    }
 }
 
 fn can_decimal_fit_in_numeric(precision: u8, scale: i8) -> bool {
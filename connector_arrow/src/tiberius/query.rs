@@ -9,8 +9,8 @@ use crate::api::{ResultReader, Statement};
 use crate::impl_produce_unsupported;
 use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
 use crate::util::transport::{self, ProduceTy};
-use crate::util::ArrayCellRef;
 use crate::util::{self, transport::Produce};
+use crate::util::{ArrayCellRef, CastReader};
 use crate::ConnectorError;
 
 pub struct TiberiusStatement<'conn, S: AsyncRead + AsyncWrite + Unpin + Send> {
@@ -22,7 +22,7 @@ impl<'conn, S: AsyncRead + AsyncWrite + Unpin + Send> Statement<'conn>
     for TiberiusStatement<'conn, S>
 {
     type Reader<'stmt>
-        = TiberiusResultReader<'stmt>
+        = CastReader<TiberiusResultReader<'stmt>>
     where
         Self: 'stmt;
 
@@ -47,22 +47,34 @@ impl<'conn, S: AsyncRead + AsyncWrite + Unpin + Send> Statement<'conn>
 
         // get columns
         let columns = self.conn.rt.block_on(stream.columns())?;
-        let schema = super::types::get_result_schema(columns)?;
+        let mapper = self.conn.type_mapper.as_deref();
+        let (schema, mapped_schema) = super::types::get_result_schema(columns, mapper)?;
         self.conn.rt.block_on(stream.next());
 
-        Ok(TiberiusResultReader {
+        let reader = TiberiusResultReader {
             schema,
             stream: TiberiusStream {
                 rt: self.conn.rt.clone(),
                 stream,
             },
-        })
+            rows_read: 0,
+        };
+        Ok(CastReader::new(reader, mapped_schema))
+    }
+
+    fn start_batch_with_schema(
+        &mut self,
+        args: (&RecordBatch, usize),
+        expected: SchemaRef,
+    ) -> Result<Self::Reader<'_>, ConnectorError> {
+        self.start_batch(args)?.cast_into(expected)
     }
 }
 
 pub struct TiberiusResultReader<'stmt> {
     schema: SchemaRef,
     stream: TiberiusStream<'stmt>,
+    rows_read: usize,
 }
 
 struct TiberiusStream<'stmt> {
@@ -80,7 +92,8 @@ impl Iterator for TiberiusResultReader<'_> {
     type Item = Result<RecordBatch, ConnectorError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        util::next_batch_from_rows(&self.schema, &mut self.stream, 1024).transpose()
+        let rows_read = &mut self.rows_read;
+        util::next_batch_from_rows(&self.schema, &mut self.stream, 1024, rows_read).transpose()
     }
 }
 
@@ -1,13 +1,18 @@
 use std::borrow::Cow;
 use std::sync::Arc;
 
-use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use futures::{AsyncRead, AsyncWrite};
 use itertools::Itertools;
 
-use crate::api::{Connector, SchemaEdit, SchemaGet};
-use crate::util::escape::escaped_ident;
-use crate::{ConnectorError, TableCreateError, TableDropError};
+use crate::api::{Relation, RelationKind, SchemaEdit, SchemaGet, TableOptions, TypeMapper};
+use crate::migration::MigrationStep;
+use crate::util::ddl::{
+    column_comment, column_default, table_constraints, with_column_comment, with_column_default,
+    Generated,
+};
+use crate::util::escape::{escaped_ident, escaped_literal};
+use crate::{ColumnEditError, ConnectorError, TableCreateError, TableDropError, TableEditError};
 
 impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConnection<S> {
     fn table_list(&mut self) -> Result<Vec<String>, ConnectorError> {
@@ -34,12 +39,81 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConn
         Ok(table_names)
     }
 
+    fn relation_list(&mut self) -> Result<Vec<Relation>, ConnectorError> {
+        // materialized views are called "indexed views" in MS SQL Server
+        let query = "
+            SELECT
+                o.name,
+                CAST(CASE WHEN o.type = 'V' THEN 1 ELSE 0 END AS bit) AS is_view,
+                CAST(COALESCE(t.is_external, 0) AS bit) AS is_external,
+                CAST(CASE WHEN EXISTS (
+                    SELECT 1 FROM sys.indexes i
+                    JOIN sys.partition_schemes ps ON (i.data_space_id = ps.data_space_id)
+                    WHERE i.object_id = o.object_id
+                ) THEN 1 ELSE 0 END AS bit) AS is_partitioned,
+                CAST(CASE WHEN EXISTS (
+                    SELECT 1 FROM sys.indexes i WHERE i.object_id = o.object_id AND i.index_id = 1
+                ) THEN 1 ELSE 0 END AS bit) AS is_indexed
+            FROM sys.objects o
+            LEFT JOIN sys.tables t ON (t.object_id = o.object_id)
+            WHERE
+                o.schema_id = SCHEMA_ID() AND
+                o.type IN ('U', 'V') AND
+                o.is_ms_shipped = 0
+            ORDER BY o.name
+        ";
+        let res = self.client.query(query, &[]);
+        let res = self.rt.block_on(res)?;
+
+        let res = res.into_first_result();
+        let res = self.rt.block_on(res)?;
+
+        let relations = res
+            .into_iter()
+            .map(|row| {
+                let name = row.get::<&str, _>(0).unwrap().to_string();
+                let is_view: bool = row.get(1).unwrap();
+                let is_external: bool = row.get(2).unwrap();
+                let is_partitioned: bool = row.get(3).unwrap();
+                let is_indexed: bool = row.get(4).unwrap();
+
+                let kind = match (is_view, is_indexed) {
+                    (true, true) => RelationKind::MaterializedView,
+                    (true, false) => RelationKind::View,
+                    _ if is_external => RelationKind::Foreign,
+                    _ if is_partitioned => RelationKind::Partitioned,
+                    _ => RelationKind::Table,
+                };
+                Relation { name, kind }
+            })
+            .collect_vec();
+
+        Ok(relations)
+    }
+
     fn table_get(
         &mut self,
         table_name: &str,
     ) -> Result<arrow::datatypes::SchemaRef, ConnectorError> {
         let query = "
-            SELECT COLUMN_NAME, DATA_TYPE, IS_NULLABLE, NUMERIC_PRECISION
+            SELECT
+                COLUMN_NAME, DATA_TYPE, IS_NULLABLE, NUMERIC_PRECISION, COLUMN_DEFAULT,
+                CAST(COLUMNPROPERTY(
+                    OBJECT_ID(QUOTENAME(TABLE_SCHEMA) + '.' + QUOTENAME(TABLE_NAME)),
+                    COLUMN_NAME, 'IsIdentity'
+                ) AS bit),
+                CAST(COLUMNPROPERTY(
+                    OBJECT_ID(QUOTENAME(TABLE_SCHEMA) + '.' + QUOTENAME(TABLE_NAME)),
+                    COLUMN_NAME, 'IsComputed'
+                ) AS bit),
+                (
+                    SELECT CAST(value AS nvarchar(max))
+                    FROM sys.extended_properties
+                    WHERE
+                        class = 1 AND name = 'MS_Description' AND
+                        major_id = OBJECT_ID(QUOTENAME(TABLE_SCHEMA) + '.' + QUOTENAME(TABLE_NAME)) AND
+                        minor_id = COLUMNPROPERTY(major_id, COLUMN_NAME, 'ColumnId')
+                )
             FROM INFORMATION_SCHEMA.COLUMNS
             WHERE
                 TABLE_CATALOG = DB_NAME() AND
@@ -54,6 +128,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConn
         let res = res.into_first_result();
         let res = self.rt.block_on(res)?;
 
+        let mapper = self.type_mapper.as_deref();
         let fields: Vec<_> = res
             .into_iter()
             .map(|row| -> Result<_, ConnectorError> {
@@ -61,6 +136,13 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConn
                 let data_type: &str = row.get(1).unwrap();
                 let is_nullable: bool = row.get::<&str, _>(2).unwrap() != "NO";
                 let numeric_precision: Option<u8> = row.get(3);
+                let default: Option<&str> = row.get(4);
+                let generated = match (row.get(5), row.get(6)) {
+                    (Some(true), _) => Some(Generated::Identity),
+                    (_, Some(true)) => Some(Generated::Computed),
+                    _ => None,
+                };
+                let comment: Option<&str> = row.get(7);
 
                 let db_type_name = if let Some(numeric_precision) = numeric_precision {
                     Cow::from(format!("{data_type}({numeric_precision})"))
@@ -68,7 +150,10 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConn
                     Cow::from(data_type)
                 };
 
-                Ok(super::types::create_field(name, &db_type_name, is_nullable))
+                let field = super::types::create_field(mapper, name, &db_type_name, is_nullable);
+                let default = default.map(str::to_string);
+                let field = with_column_default(field, default, generated);
+                Ok(with_column_comment(field, comment.map(str::to_string)))
             })
             .try_collect()?;
 
@@ -77,26 +162,77 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaGet for super::TiberiusConn
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaEdit for super::TiberiusConnection<S> {
-    fn table_create(&mut self, name: &str, schema: SchemaRef) -> Result<(), TableCreateError> {
+    fn table_create_with(
+        &mut self,
+        name: &str,
+        schema: SchemaRef,
+        options: &TableOptions,
+    ) -> Result<(), TableCreateError> {
+        // temporary tables are named with a # prefix, which would have to be resolved
+        // by all other functions that refer to the table, including user's queries
+        if options.temporary {
+            return Err(TableCreateError::Connector(ConnectorError::NotSupported {
+                connector_name: "tiberius",
+                feature: "temporary tables",
+            }));
+        }
+
         let column_defs = schema
             .fields()
             .iter()
-            .map(|field| {
-                let ty_name = Self::type_arrow_into_db(field.data_type()).unwrap_or_else(|| {
-                    unimplemented!("cannot store type {} in MS SQL Server", field.data_type());
-                });
+            .map(|field| column_def::<S>(self.type_mapper.as_deref(), name, field, options))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",");
+        let constraints = table_constraints(options, escaped_ident);
 
-                let is_nullable =
-                    field.is_nullable() || matches!(field.data_type(), DataType::Null);
-                let not_null = if is_nullable { "" } else { " NOT NULL" };
+        let create = format!(
+            "CREATE TABLE {} ({column_defs}{constraints});",
+            escaped_ident(name)
+        );
 
-                let name = escaped_ident(field.name());
-                format!("{name} {ty_name}{not_null}",)
-            })
-            .join(",");
+        let mut comments = String::new();
+        for field in schema.fields() {
+            if let Some(comment) = column_comment(field, options) {
+                comments += &add_column_description(name, field.name(), comment);
+            }
+        }
+        if !comments.is_empty() {
+            comments = format!("{DECLARE_SCHEMA}{comments}");
+        }
 
-        let ddl = format!("CREATE TABLE {} ({column_defs});", escaped_ident(name));
+        let batches = if options.if_not_exists {
+            let object_name = escaped_ident(name).to_string();
+            vec![format!(
+                "IF OBJECT_ID(N{}, N'U') IS NULL BEGIN {create}{comments} END",
+                escaped_literal(&object_name)
+            )]
+        } else {
+            vec![create, comments]
+        };
+
+        for ddl in batches.into_iter().filter(|b| !b.is_empty()) {
+            let res = self.client.execute(&ddl, &[]);
+            let res = self.rt.block_on(res);
+
+            match res {
+                Ok(_) => {}
+                Err(tiberius::error::Error::Server(e)) if e.code() == 2714 => {
+                    return Err(TableCreateError::TableExists)
+                }
+                Err(e) => return Err(TableCreateError::Connector(e.into())),
+            }
+        }
+        Ok(())
+    }
 
+    fn table_create_like(&mut self, name: &str, like: &str) -> Result<(), TableCreateError> {
+        // UNION ALL prevents SELECT INTO from copying the IDENTITY property
+        let ddl = format!(
+            "SELECT * INTO {name} FROM {like} WHERE 1 = 0 \
+            UNION ALL SELECT * FROM {like} WHERE 1 = 0;",
+            name = escaped_ident(name),
+            like = escaped_ident(like)
+        );
         let res = self.client.execute(&ddl, &[]);
         let res = self.rt.block_on(res);
 
@@ -122,4 +258,278 @@ impl<S: AsyncRead + AsyncWrite + Unpin + Send> SchemaEdit for super::TiberiusCon
             Err(e) => Err(TableDropError::Connector(e.into())),
         }
     }
+
+    fn table_rename(&mut self, old_name: &str, new_name: &str) -> Result<(), TableEditError> {
+        let ddl = format!(
+            "EXEC sp_rename {}, {};",
+            escaped_literal(&escaped_ident(old_name).to_string()),
+            escaped_literal(new_name)
+        );
+        self.execute_ddl(&ddl).map_err(|e| match e {
+            tiberius::error::Error::Server(e) if e.code() == 15248 => {
+                TableEditError::TableNonexistent
+            }
+            tiberius::error::Error::Server(e) if e.code() == 15335 => TableEditError::TableExists,
+            e => TableEditError::Connector(e.into()),
+        })
+    }
+
+    fn table_truncate(&mut self, name: &str) -> Result<(), TableEditError> {
+        let ddl = format!("TRUNCATE TABLE {};", escaped_ident(name));
+        self.execute_ddl(&ddl).map_err(|e| match e {
+            tiberius::error::Error::Server(e) if e.code() == 4701 => {
+                TableEditError::TableNonexistent
+            }
+            e => TableEditError::Connector(e.into()),
+        })
+    }
+
+    fn column_add(&mut self, table_name: &str, field: &Field) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_add::<S>(self.type_mapper.as_deref(), table_name, field)?;
+        self.execute_ddl(&ddl).map_err(column_edit_error)?;
+
+        if let Some(comment) = column_comment(field, &TableOptions::default()) {
+            let ddl = add_column_description(table_name, field.name(), comment);
+            self.execute_ddl(&format!("{DECLARE_SCHEMA}{ddl}"))
+                .map_err(column_edit_error)?;
+        }
+        Ok(())
+    }
+
+    fn column_drop(&mut self, table_name: &str, column_name: &str) -> Result<(), ColumnEditError> {
+        let ddl = ddl_column_drop(table_name, column_name);
+        self.execute_ddl(&ddl).map_err(column_edit_error)
+    }
+
+    fn column_rename(
+        &mut self,
+        table_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        let old_name = format!("{}.{}", escaped_ident(table_name), escaped_ident(old_name));
+        let ddl = format!(
+            "EXEC sp_rename {}, {}, 'COLUMN';",
+            escaped_literal(&old_name),
+            escaped_literal(new_name)
+        );
+        self.execute_ddl(&ddl).map_err(|e| match e {
+            tiberius::error::Error::Server(e) if e.code() == 15248 => {
+                ColumnEditError::ColumnNonexistent
+            }
+            tiberius::error::Error::Server(e) if e.code() == 15335 => ColumnEditError::ColumnExists,
+            e => column_edit_error(e),
+        })
+    }
+
+    fn column_alter_type(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+        data_type: &DataType,
+    ) -> Result<(), ColumnEditError> {
+        // ALTER COLUMN replaces the whole column definition, so we need to retain nullability
+        let query = "
+            SELECT
+                CAST(CASE WHEN OBJECT_ID(@P1) IS NULL THEN 0 ELSE 1 END AS bit),
+                COLUMNPROPERTY(OBJECT_ID(@P1), @P2, 'AllowsNull')
+        ";
+        let params: [&dyn tiberius::ToSql; 2] = [
+            &escaped_ident(table_name).to_string(),
+            &column_name.to_string(),
+        ];
+        let res = self.client.query(query, &params);
+        let res = self.rt.block_on(res).map_err(column_edit_error)?;
+        let res = res.into_row();
+        let row = self.rt.block_on(res).map_err(column_edit_error)?;
+        let row = row.ok_or(ColumnEditError::Connector(ConnectorError::NoResultSets))?;
+
+        let table_exists: bool = row.get(0).unwrap();
+        if !table_exists {
+            return Err(ColumnEditError::TableNonexistent);
+        }
+        let allows_null: i32 = row.get(1).ok_or(ColumnEditError::ColumnNonexistent)?;
+
+        let field = Field::new(column_name, data_type.clone(), allows_null == 1);
+        let ddl = ddl_column_alter::<S>(self.type_mapper.as_deref(), table_name, &field)?;
+        self.execute_ddl(&ddl).map_err(column_edit_error)
+    }
+
+    fn column_drop_not_null(
+        &mut self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), ColumnEditError> {
+        // ALTER COLUMN replaces the whole column definition, so we need to retain the type
+        let query = "
+            SELECT
+                CAST(CASE WHEN OBJECT_ID(@P1) IS NULL THEN 0 ELSE 1 END AS bit),
+                TYPE_NAME(c.user_type_id), c.max_length, c.precision, c.scale
+            FROM (SELECT 1 AS one) AS dummy
+            LEFT JOIN sys.columns AS c ON c.object_id = OBJECT_ID(@P1) AND c.name = @P2
+        ";
+        let params: [&dyn tiberius::ToSql; 2] = [
+            &escaped_ident(table_name).to_string(),
+            &column_name.to_string(),
+        ];
+        let res = self.client.query(query, &params);
+        let res = self.rt.block_on(res).map_err(column_edit_error)?;
+        let res = res.into_row();
+        let row = self.rt.block_on(res).map_err(column_edit_error)?;
+        let row = row.ok_or(ColumnEditError::Connector(ConnectorError::NoResultSets))?;
+
+        let table_exists: bool = row.get(0).unwrap_or_default();
+        if !table_exists {
+            return Err(ColumnEditError::TableNonexistent);
+        }
+        let ty: &str = row.get(1).ok_or(ColumnEditError::ColumnNonexistent)?;
+        let max_length: i16 = row.get(2).unwrap_or_default();
+        let precision: u8 = row.get(3).unwrap_or_default();
+        let scale: u8 = row.get(4).unwrap_or_default();
+
+        let ty = match ty {
+            "varchar" | "char" | "varbinary" | "binary" if max_length == -1 => format!("{ty}(max)"),
+            "varchar" | "char" | "varbinary" | "binary" => format!("{ty}({max_length})"),
+            "nvarchar" | "nchar" if max_length == -1 => format!("{ty}(max)"),
+            "nvarchar" | "nchar" => format!("{ty}({})", max_length / 2),
+            "decimal" | "numeric" => format!("{ty}({precision},{scale})"),
+            "datetime2" | "datetimeoffset" | "time" => format!("{ty}({scale})"),
+            _ => ty.to_string(),
+        };
+        let ddl = format!(
+            "ALTER TABLE {} ALTER COLUMN {} {ty} NULL;",
+            escaped_ident(table_name),
+            escaped_ident(column_name)
+        );
+        self.execute_ddl(&ddl).map_err(column_edit_error)
+    }
+
+    fn migration_step_ddl(
+        &self,
+        table_name: &str,
+        step: &MigrationStep,
+    ) -> Result<String, ConnectorError> {
+        let mapper = self.type_mapper.as_deref();
+        Ok(match step {
+            MigrationStep::ColumnAdd(field) => {
+                let mut ddl = ddl_column_add::<S>(mapper, table_name, field)?;
+                if let Some(comment) = column_comment(field, &TableOptions::default()) {
+                    ddl += DECLARE_SCHEMA;
+                    ddl += &add_column_description(table_name, field.name(), comment);
+                }
+                ddl
+            }
+            MigrationStep::ColumnDrop(name) => ddl_column_drop(table_name, name),
+            MigrationStep::ColumnWiden(field) | MigrationStep::ColumnDropNotNull(field) => {
+                ddl_column_alter::<S>(mapper, table_name, field)?
+            }
+        })
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> super::TiberiusConnection<S> {
+    fn execute_ddl(&mut self, ddl: &str) -> Result<(), tiberius::error::Error> {
+        let res = self.client.execute(ddl, &[]);
+        self.rt.block_on(res)?;
+        Ok(())
+    }
+}
+
+fn db_type_of<S: AsyncRead + AsyncWrite + Unpin + Send>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    let data_type = field.data_type();
+    crate::util::arrow_into_db::<super::TiberiusConnection<S>>(mapper, field.name(), data_type)
+        .ok_or_else(|| {
+            crate::util::unsupported_column_type(
+                "MS SQL Server",
+                table_name,
+                field.name(),
+                data_type,
+            )
+        })
+}
+
+fn column_def<S: AsyncRead + AsyncWrite + Unpin + Send>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+    options: &TableOptions,
+) -> Result<String, ConnectorError> {
+    let ty_name = db_type_of::<S>(mapper, table_name, field)?;
+
+    let is_nullable = field.is_nullable() || matches!(field.data_type(), DataType::Null);
+    let not_null = if is_nullable { "" } else { " NOT NULL" };
+
+    let default = column_default(field, options)
+        .map(|d| format!(" DEFAULT ({d})"))
+        .unwrap_or_default();
+
+    let name = escaped_ident(field.name());
+    Ok(format!("{name} {ty_name}{not_null}{default}"))
+}
+
+fn ddl_column_add<S: AsyncRead + AsyncWrite + Unpin + Send>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    Ok(format!(
+        "ALTER TABLE {} ADD {};",
+        escaped_ident(table_name),
+        column_def::<S>(mapper, table_name, field, &TableOptions::default())?
+    ))
+}
+
+fn ddl_column_drop(table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP COLUMN {};",
+        escaped_ident(table_name),
+        escaped_ident(column_name)
+    )
+}
+
+fn ddl_column_alter<S: AsyncRead + AsyncWrite + Unpin + Send>(
+    mapper: Option<&dyn TypeMapper>,
+    table_name: &str,
+    field: &Field,
+) -> Result<String, ConnectorError> {
+    let not_null = if field.is_nullable() { "" } else { " NOT NULL" };
+    Ok(format!(
+        "ALTER TABLE {} ALTER COLUMN {} {}{not_null};",
+        escaped_ident(table_name),
+        escaped_ident(field.name()),
+        db_type_of::<S>(mapper, table_name, field)?
+    ))
+}
+
+/// Declares variable @schema, used by [add_column_description].
+const DECLARE_SCHEMA: &str = "DECLARE @schema sysname = SCHEMA_NAME();";
+
+/// Column comments are stored as extended property MS_Description.
+fn add_column_description(table_name: &str, column_name: &str, comment: &str) -> String {
+    format!(
+        "EXEC sp_addextendedproperty \
+            @name = N'MS_Description', @value = N{}, \
+            @level0type = N'SCHEMA', @level0name = @schema, \
+            @level1type = N'TABLE', @level1name = N{}, \
+            @level2type = N'COLUMN', @level2name = N{};",
+        escaped_literal(comment),
+        escaped_literal(table_name),
+        escaped_literal(column_name),
+    )
+}
+
+fn column_edit_error(e: tiberius::error::Error) -> ColumnEditError {
+    match e {
+        // 4902: cannot find the object
+        tiberius::error::Error::Server(e) if e.code() == 4902 => ColumnEditError::TableNonexistent,
+        // 2705: column names in each table must be unique
+        tiberius::error::Error::Server(e) if e.code() == 2705 => ColumnEditError::ColumnExists,
+        // 4924: column does not exist
+        tiberius::error::Error::Server(e) if e.code() == 4924 => ColumnEditError::ColumnNonexistent,
+        e => ColumnEditError::Connector(e.into()),
+    }
 }
@@ -5,27 +5,44 @@ use tiberius::{Column, ColumnType};
 use tokio::net::TcpStream;
 use tokio_util::compat::Compat;
 
-use crate::{api::Connector, ConnectorError};
-
-pub fn get_result_schema(columns: Option<&[Column]>) -> Result<SchemaRef, ConnectorError> {
+use crate::api::TypeMapper;
+use crate::ConnectorError;
+
+/// Returns the schema that the rows are decoded into and, if the mapper changes it,
+/// the schema that the result should be converted into.
+pub fn get_result_schema(
+    columns: Option<&[Column]>,
+    mapper: Option<&dyn TypeMapper>,
+) -> Result<(SchemaRef, Option<SchemaRef>), ConnectorError> {
     let Some(columns) = columns else {
         return Err(ConnectorError::NoResultSets);
     };
 
     let mut fields = Vec::new();
+    let mut db_types = Vec::new();
     for column in columns {
         let db_ty = get_name_of_column_type(&column.column_type());
 
-        fields.push(create_field(column.name(), db_ty, true));
+        fields.push(create_field(None, column.name(), db_ty, true));
+        db_types.push(db_ty);
     }
 
-    Ok(Arc::new(Schema::new(fields)))
+    let schema = Schema::new(fields);
+    let mapped = crate::util::map_schema(mapper, &schema, db_types);
+    Ok((Arc::new(schema), mapped))
 }
 
-pub fn create_field(name: &str, db_ty: &str, nullable: bool) -> Field {
+pub fn create_field(
+    mapper: Option<&dyn TypeMapper>,
+    name: &str,
+    db_ty: &str,
+    nullable: bool,
+) -> Field {
     let mut metadata = HashMap::new();
 
-    let data_type = super::TiberiusConnection::<Compat<TcpStream>>::type_db_into_arrow(db_ty);
+    let data_type = crate::util::db_into_arrow::<super::TiberiusConnection<Compat<TcpStream>>>(
+        mapper, name, db_ty,
+    );
 
     // if we cannot map to an arrow type, map into a binary
     let data_type = data_type.unwrap_or_else(|| {
@@ -34,7 +51,7 @@ pub fn create_field(name: &str, db_ty: &str, nullable: bool) -> Field {
         DataType::Binary
     });
 
-    Field::new(name, data_type, nullable)
+    Field::new(name, data_type, nullable).with_metadata(metadata)
 }
 
 fn get_name_of_column_type(col_ty: &ColumnType) -> &'static str {
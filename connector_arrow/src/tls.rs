@@ -0,0 +1,156 @@
+//! TLS configuration of network connections.
+//!
+//! TLS is implemented with [rustls](https://docs.rs/rustls) and its aws-lc-rs crypto provider,
+//! and requires feature `tls-rustls`.
+//! [TlsConfig] can be passed to [crate::connect_with_tls] or converted into the TLS configuration
+//! of the native client, when constructing the client manually.
+
+use std::path::PathBuf;
+
+/// Certificate configuration of TLS connections.
+///
+/// By default, server certificates are verified against Mozilla's root certificates.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    root_certificate: Option<PathBuf>,
+    accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also trusts certificates signed by the CA certificate in the PEM file at `path`.
+    /// Useful for servers with self-signed certificates.
+    pub fn root_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificate = Some(path.into());
+        self
+    }
+
+    /// Disables verification of server certificates.
+    /// Connection is still encrypted, but is not protected against impersonation of the server.
+    pub fn accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+}
+
+#[cfg(feature = "tls-rustls")]
+mod rustls_impl {
+    use std::sync::Arc;
+
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::{aws_lc_rs, CryptoProvider};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+    use super::TlsConfig;
+    use crate::ConnectorError;
+
+    impl TlsConfig {
+        /// Converts into configuration of a rustls client.
+        pub fn rustls_client_config(&self) -> Result<ClientConfig, ConnectorError> {
+            let provider = Arc::new(aws_lc_rs::default_provider());
+            let builder = ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()
+                .map_err(tls_error)?;
+
+            if self.accept_invalid_certs {
+                let verifier = Arc::new(NoVerification(provider));
+                return Ok(builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(verifier)
+                    .with_no_client_auth());
+            }
+
+            let mut roots = RootCertStore::empty();
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            if let Some(path) = &self.root_certificate {
+                let file = std::fs::File::open(path).map_err(tls_error)?;
+                let mut reader = std::io::BufReader::new(file);
+                for cert in rustls_pemfile::certs(&mut reader) {
+                    roots.add(cert.map_err(tls_error)?).map_err(tls_error)?;
+                }
+            }
+            Ok(builder.with_root_certificates(roots).with_no_client_auth())
+        }
+
+        /// Converts into a TLS connector of [postgres::Client].
+        #[cfg(feature = "src_postgres")]
+        pub fn postgres_connector(
+            &self,
+        ) -> Result<tokio_postgres_rustls::MakeRustlsConnect, ConnectorError> {
+            let config = self.rustls_client_config()?;
+            Ok(tokio_postgres_rustls::MakeRustlsConnect::new(config))
+        }
+
+        /// Converts into TLS options of [mysql::Conn].
+        ///
+        /// mysql builds its own rustls configuration from these options, with the crypto
+        /// provider that rustls selects for the process. This is the aws-lc-rs provider,
+        /// unless the application enables other providers of rustls and installs one of them.
+        #[cfg(feature = "src_mysql")]
+        pub fn mysql_ssl_opts(&self) -> mysql::SslOpts {
+            mysql::SslOpts::default()
+                .with_root_cert_path(self.root_certificate.clone())
+                .with_danger_accept_invalid_certs(self.accept_invalid_certs)
+        }
+
+        /// Requires encryption of a [tiberius::Client] and applies certificate configuration.
+        #[cfg(feature = "src_tiberius")]
+        pub fn apply_to_tiberius(&self, config: &mut tiberius::Config) {
+            config.encryption(tiberius::EncryptionLevel::Required);
+            if self.accept_invalid_certs {
+                config.trust_cert();
+            } else if let Some(path) = &self.root_certificate {
+                config.trust_cert_ca(path.to_string_lossy());
+            }
+        }
+    }
+
+    fn tls_error(err: impl std::fmt::Display) -> ConnectorError {
+        ConnectorError::Tls(err.to_string())
+    }
+
+    /// Accepts any server certificate, but still checks the handshake signatures.
+    #[derive(Debug)]
+    struct NoVerification(Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for NoVerification {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            let algorithms = &self.0.signature_verification_algorithms;
+            rustls::crypto::verify_tls12_signature(message, cert, dss, algorithms)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            let algorithms = &self.0.signature_verification_algorithms;
+            rustls::crypto::verify_tls13_signature(message, cert, dss, algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
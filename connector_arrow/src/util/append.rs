@@ -0,0 +1,435 @@
+//! Preparation of the target table of an append, according to [AppendMode],
+//! accounting of the [AppendSummary] and handling of rows that cannot be written,
+//! according to [OnError]. Used by appender implementations.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use arrow::array::{ArrayRef, Int64Array, StringArray};
+use arrow::compute::concat_batches;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+
+use crate::api::{
+    AppendMode, AppendOptions, AppendSummary, OnError, SchemaEdit, SchemaGet, TableOptions,
+    REJECTED_ERROR_FIELD,
+};
+use crate::{
+    ColumnEditError, ConnectorError, ErrorContext, ErrorKind, TableCreateError, TableDropError,
+    TableEditError,
+};
+
+/// Table that an appender writes into, prepared on the first batch.
+pub(crate) struct AppendTarget {
+    table_name: String,
+    options: AppendOptions,
+    /// Name of the table that the batches are written into, once prepared.
+    prepared: Option<String>,
+}
+
+impl AppendTarget {
+    pub(crate) fn new(table_name: &str, options: AppendOptions) -> Self {
+        AppendTarget {
+            table_name: table_name.to_string(),
+            options,
+            prepared: None,
+        }
+    }
+
+    /// Name of the table that the append is into.
+    #[cfg(feature = "src_mysql")]
+    pub(crate) fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    pub(crate) fn options(&self) -> &AppendOptions {
+        &self.options
+    }
+
+    #[cfg(feature = "src_mysql")]
+    pub(crate) fn is_prepared(&self) -> bool {
+        self.prepared.is_some()
+    }
+
+    /// Name of the table that batches are written into.
+    /// Must not be called before [AppendTarget::prepare].
+    #[cfg(any(
+        feature = "src_sqlite",
+        feature = "src_duckdb",
+        feature = "src_mysql",
+        feature = "src_tiberius"
+    ))]
+    pub(crate) fn prepared_name(&self) -> &str {
+        self.prepared
+            .as_deref()
+            .expect("append target was not prepared")
+    }
+
+    /// Name of the table that batches of `schema` are written into.
+    /// Prepares the table on the first call.
+    pub(crate) fn prepare<C>(
+        &mut self,
+        conn: &mut C,
+        schema: &Schema,
+    ) -> Result<&str, ConnectorError>
+    where
+        C: SchemaGet + SchemaEdit,
+    {
+        if self.prepared.is_none() {
+            let name = prepare_table(conn, &self.table_name, schema, &self.options)?;
+            self.prepared = Some(name);
+        }
+        Ok(self.prepared.as_deref().unwrap())
+    }
+
+    /// Completes the preparation of the table. Must be called before the appended data is
+    /// committed, in the same transaction.
+    pub(crate) fn finish<C: SchemaEdit>(&self, conn: &mut C) -> Result<(), ConnectorError> {
+        match (&self.options.mode, &self.prepared) {
+            (AppendMode::Replace, Some(staging)) => replace_table(conn, staging, &self.table_name),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Prepares the table for batches of `schema`, according to `options.mode`.
+/// Returns the name of the table that the batches are to be written into.
+fn prepare_table<C>(
+    conn: &mut C,
+    table_name: &str,
+    schema: &Schema,
+    options: &AppendOptions,
+) -> Result<String, ConnectorError>
+where
+    C: SchemaGet + SchemaEdit,
+{
+    match options.mode {
+        AppendMode::Append => Ok(table_name.to_string()),
+        AppendMode::CreateIfMissing | AppendMode::EvolveSchema => {
+            // failed statements abort the transaction in some databases, so this must not fail
+            let if_not_exists = TableOptions {
+                if_not_exists: true,
+                ..Default::default()
+            };
+            let schema = column_schema(schema, options);
+            conn.table_create_with(table_name, schema.clone(), &if_not_exists)
+                .map_err(|e| table_create_error(e, table_name))?;
+
+            if options.mode == AppendMode::EvolveSchema {
+                let current = conn.table_get(table_name)?;
+                for field in schema.fields() {
+                    if current.field_with_name(field.name()).is_err() {
+                        let field = field.as_ref().clone().with_nullable(true);
+                        conn.column_add(table_name, &field)
+                            .map_err(|e| column_edit_error(e, table_name))?;
+                    }
+                }
+            }
+            Ok(table_name.to_string())
+        }
+        AppendMode::Replace => {
+            let staging = super::partition::staging_table_names(1).remove(0);
+            conn.table_create(&staging, column_schema(schema, options))
+                .map_err(|e| table_create_error(e, &staging))?;
+            Ok(staging)
+        }
+    }
+}
+
+/// Replaces the table with the staging table that was returned by [prepare_table].
+fn replace_table<C: SchemaEdit>(
+    conn: &mut C,
+    staging: &str,
+    table_name: &str,
+) -> Result<(), ConnectorError> {
+    match conn.table_drop(table_name) {
+        Ok(()) | Err(TableDropError::TableNonexistent) => {}
+        Err(TableDropError::Connector(e)) => return Err(e),
+    }
+    conn.table_rename(staging, table_name).map_err(|e| match e {
+        TableEditError::Connector(e) => e,
+        e => ConnectorError::IncompatibleSchema {
+            table_name: table_name.to_string(),
+            message: e.to_string(),
+            hint: None,
+        },
+    })
+}
+
+/// Schema of the table columns that receive the fields of `schema`.
+fn column_schema(schema: &Schema, options: &AppendOptions) -> SchemaRef {
+    let Some(names) = options.column_names(schema) else {
+        return Arc::new(schema.clone());
+    };
+    let fields: Vec<_> = (schema.fields().iter().zip(names))
+        .map(|(f, name)| f.as_ref().clone().with_name(name))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// Tables are created with if_not_exists or a fresh name, but a concurrent creator can still
+/// make the creation fail.
+fn table_create_error(e: TableCreateError, table_name: &str) -> ConnectorError {
+    match e {
+        TableCreateError::Connector(e) => e,
+        TableCreateError::TableExists => ConnectorError::TableExists {
+            table_name: table_name.to_string(),
+        },
+    }
+}
+
+fn column_edit_error(e: ColumnEditError, table_name: &str) -> ConnectorError {
+    match e {
+        ColumnEditError::Connector(e) => e,
+        e => ConnectorError::IncompatibleSchema {
+            table_name: table_name.to_string(),
+            message: e.to_string(),
+            hint: None,
+        },
+    }
+}
+
+/// Accumulates the [AppendSummary] of an appender.
+pub(crate) struct AppendStats {
+    started: Instant,
+    rows: u64,
+    batches: u64,
+    bytes: u64,
+    generated_keys: Option<Vec<i64>>,
+    rejected: Vec<RecordBatch>,
+}
+
+/// Counts of [AppendStats] at some point, which can be restored.
+#[derive(Clone, Copy)]
+pub(crate) struct StatsMark {
+    rows: u64,
+    generated_keys: usize,
+}
+
+impl AppendStats {
+    pub(crate) fn new(options: &AppendOptions) -> Self {
+        AppendStats {
+            started: Instant::now(),
+            rows: 0,
+            batches: 0,
+            bytes: 0,
+            generated_keys: options.generated_keys.as_ref().map(|_| Vec::new()),
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Records a batch that was passed to the appender. Returns index of the batch.
+    pub(crate) fn batch(&mut self, batch: &RecordBatch) -> usize {
+        self.batches += 1;
+        self.bytes += batch.get_array_memory_size() as u64;
+        (self.batches - 1) as usize
+    }
+
+    /// Records rows written to the data store.
+    pub(crate) fn rows(&mut self, rows: u64) {
+        self.rows += rows;
+    }
+
+    /// Records a generated key.
+    pub(crate) fn generated_key(&mut self, key: i64) {
+        if let Some(keys) = &mut self.generated_keys {
+            keys.push(key);
+        }
+    }
+
+    /// Records rows that could not be written.
+    pub(crate) fn reject(
+        &mut self,
+        rows: &RecordBatch,
+        error: &ConnectorError,
+    ) -> Result<(), ConnectorError> {
+        let schema = rows.schema();
+        let mut fields = schema.fields().to_vec();
+        fields.push(Arc::new(Field::new(
+            REJECTED_ERROR_FIELD,
+            DataType::Utf8,
+            false,
+        )));
+
+        let mut columns = rows.columns().to_vec();
+        let message = error.to_string();
+        columns.push(Arc::new(StringArray::from_iter_values(
+            std::iter::repeat_n(message, rows.num_rows()),
+        )));
+
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+        self.rejected.push(RecordBatch::try_new(schema, columns)?);
+        Ok(())
+    }
+
+    pub(crate) fn mark(&self) -> StatsMark {
+        StatsMark {
+            rows: self.rows,
+            generated_keys: self.generated_keys.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    /// Forgets rows that were recorded after the mark, because they were rolled back.
+    pub(crate) fn restore(&mut self, mark: StatsMark) {
+        self.rows = mark.rows;
+        if let Some(keys) = &mut self.generated_keys {
+            keys.truncate(mark.generated_keys);
+        }
+    }
+
+    pub(crate) fn finish(&mut self) -> Result<AppendSummary, ConnectorError> {
+        let rejected = match self.rejected.first() {
+            Some(first) => {
+                let rejected = concat_batches(&first.schema(), &self.rejected).map_err(|e| {
+                    ConnectorError::DataSchemaMismatch(format!(
+                        "rejected rows of batches with different schemas: {e}"
+                    ))
+                })?;
+                Some(rejected)
+            }
+            None => None,
+        };
+
+        Ok(AppendSummary {
+            rows: self.rows,
+            batches: self.batches,
+            bytes: self.bytes,
+            elapsed: self.started.elapsed(),
+            generated_keys: (self.generated_keys.take())
+                .map(|k| Arc::new(Int64Array::from(k)) as ArrayRef),
+            rejected,
+        })
+    }
+}
+
+/// Writing of rows by an appender, within a transaction that supports savepoints.
+pub(crate) trait WriteRows {
+    /// Writes all rows of the batch.
+    /// Errors might carry context with the row within this batch.
+    fn write_rows(&mut self, batch: &RecordBatch) -> Result<(), ConnectorError>;
+
+    fn savepoint(&mut self, command: Savepoint) -> Result<(), ConnectorError>;
+
+    fn stats(&mut self) -> &mut AppendStats;
+}
+
+pub(crate) enum Savepoint {
+    Create,
+    Release,
+    Rollback,
+}
+
+/// Name of the savepoint used by [OnError::Reject].
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) const SAVEPOINT_NAME: &str = "connector_arrow_reject";
+
+/// Writes a batch that was passed to [crate::api::Append::append], according to
+/// [AppendOptions::on_error].
+pub(crate) fn write_batch<W: WriteRows>(
+    writer: &mut W,
+    batch: &RecordBatch,
+    on_error: OnError,
+) -> Result<(), ConnectorError> {
+    let index = writer.stats().batch(batch);
+    match on_error {
+        OnError::Abort => writer
+            .write_rows(batch)
+            .map_err(|e| locate(e, batch, index, 0)),
+        OnError::Reject => write_rejecting(writer, batch, index, 0),
+    }
+}
+
+/// Writes rows of a batch and diverts the rows that fail into rejected rows.
+/// Failing batch is split in halves, until the failing rows are isolated.
+fn write_rejecting<W: WriteRows>(
+    writer: &mut W,
+    batch: &RecordBatch,
+    index: usize,
+    offset: usize,
+) -> Result<(), ConnectorError> {
+    if batch.num_rows() == 0 {
+        return Ok(());
+    }
+
+    let mark = writer.stats().mark();
+    writer.savepoint(Savepoint::Create)?;
+    let err = match writer.write_rows(batch) {
+        Ok(()) => return writer.savepoint(Savepoint::Release),
+        Err(e) => e,
+    };
+    writer.savepoint(Savepoint::Rollback)?;
+    writer.stats().restore(mark);
+
+    if matches!(
+        err.kind(),
+        ErrorKind::ConnectionLost | ErrorKind::Cancelled | ErrorKind::Timeout | ErrorKind::Busy
+    ) || is_schema_error(&err)
+    {
+        // not caused by the values of the rows
+        return Err(locate(err, batch, index, offset));
+    }
+
+    if batch.num_rows() == 1 {
+        let err = locate(err, batch, index, offset);
+        return writer.stats().reject(batch, &err);
+    }
+
+    let half = batch.num_rows() / 2;
+    write_rejecting(writer, &batch.slice(0, half), index, offset)?;
+    write_rejecting(
+        writer,
+        &batch.slice(half, batch.num_rows() - half),
+        index,
+        offset + half,
+    )
+}
+
+fn is_schema_error(err: &ConnectorError) -> bool {
+    match err {
+        ConnectorError::WithContext { source, .. } => is_schema_error(source),
+        ConnectorError::IncompatibleSchema { .. }
+        | ConnectorError::NotSupported { .. }
+        | ConnectorError::DataSchemaMismatch(_) => true,
+        _ => false,
+    }
+}
+
+/// Adds the location within the appended batch to an error from [WriteRows::write_rows]
+/// that was given rows of `batch`, which start at `offset` in the appended batch.
+fn locate(err: ConnectorError, batch: &RecordBatch, index: usize, offset: usize) -> ConnectorError {
+    let (context, source) = match err {
+        ConnectorError::WithContext { context, source } => (context, *source),
+        ConnectorError::IncompatibleSchema { .. } | ConnectorError::NotSupported { .. } => {
+            return err
+        }
+        err => (ErrorContext::default(), err),
+    };
+
+    let row = context.row.or((batch.num_rows() == 1).then_some(0));
+    let value = match (row, &context.column) {
+        (Some(row), Some(column)) if context.value.is_none() => {
+            let array = batch.column_by_name(column);
+            array.and_then(|a| match a.is_null(row) {
+                true => Some("null".to_string()),
+                false => array_value_to_string(a, row).ok(),
+            })
+        }
+        _ => context.value,
+    };
+
+    ConnectorError::WithContext {
+        context: ErrorContext {
+            batch: Some(index),
+            row: row.map(|r| r + offset),
+            column: context.column,
+            value,
+        },
+        source: Box::new(source),
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+use arrow::datatypes::Schema;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+
+use crate::api::ResultReader;
+use crate::errors::ConnectorError;
+use crate::util::coerce::cast_batch;
+
+/// Reader that casts batches of the inner reader into a different schema.
+///
+/// Values that cannot be cast produce an error instead of a null.
+pub struct CastReader<R> {
+    inner: R,
+    schema: Option<SchemaRef>,
+}
+
+impl<R> CastReader<R> {
+    /// Casts batches into `schema`. When `schema` is None, batches are returned as-is.
+    pub fn new(inner: R, schema: Option<SchemaRef>) -> Self {
+        CastReader { inner, schema }
+    }
+}
+
+impl<'s, R: ResultReader<'s>> CastReader<R> {
+    /// Replaces the schema that batches are cast into with the expected schema.
+    #[cfg(any(
+        feature = "src_duckdb",
+        feature = "src_mysql",
+        feature = "src_tiberius"
+    ))]
+    pub(crate) fn cast_into(mut self, expected: SchemaRef) -> Result<Self, ConnectorError> {
+        check_field_count(self.inner.get_schema()?.fields().len(), &expected)?;
+        self.schema = Some(expected);
+        Ok(self)
+    }
+}
+
+impl<'s, R: ResultReader<'s>> ResultReader<'s> for CastReader<R> {
+    fn get_schema(&mut self) -> Result<SchemaRef, ConnectorError> {
+        match &self.schema {
+            Some(schema) => Ok(schema.clone()),
+            None => self.inner.get_schema(),
+        }
+    }
+}
+
+impl<'s, R: ResultReader<'s>> Iterator for CastReader<R> {
+    type Item = Result<RecordBatch, ConnectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.inner.next()?;
+        let Some(schema) = &self.schema else {
+            return Some(batch);
+        };
+        Some(batch.and_then(|b| cast_batch(&b, schema)))
+    }
+}
+
+/// Checks that the expected schema has a field for each column of the result.
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) fn check_field_count(columns: usize, expected: &Schema) -> Result<(), ConnectorError> {
+    if columns == expected.fields().len() {
+        return Ok(());
+    }
+    Err(ConnectorError::DataSchemaMismatch(format!(
+        "result has {} columns, but expected schema has {} fields",
+        columns,
+        expected.fields().len()
+    )))
+}
@@ -1,11 +1,15 @@
 use std::sync::Arc;
 
 use arrow::array::{Array, ArrayRef, AsArray, Float32Builder, Float64Builder};
+use arrow::compute::CastOptions;
 use arrow::datatypes::{DataType, Field, Float16Type, Schema, SchemaRef};
 use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
 use itertools::Itertools;
 
+use crate::api::{Connector, TypeMapper};
+use crate::ConnectorError;
+
 pub fn coerce_batches<F>(
     schema: SchemaRef,
     batches: &[RecordBatch],
@@ -88,6 +92,95 @@ where
     ))
 }
 
+/// Schema that batches of `schema` are cast into before they are appended into data store `C`.
+///
+/// Fields of types that `C` cannot store are converted into the closest type that it can store,
+/// preferring lossless conversions. Other fields are not changed.
+pub fn coerce_schema_for<C: Connector>(
+    mapper: Option<&dyn TypeMapper>,
+    schema: &Schema,
+) -> SchemaRef {
+    let is_stored =
+        |name: &str, ty: &DataType| crate::util::arrow_into_db::<C>(mapper, name, ty).is_some();
+
+    let fields = (schema.fields().iter())
+        .map(|f| {
+            if is_stored(f.name(), f.data_type()) {
+                return f.clone();
+            }
+            let fallback = fallback_types(f.data_type()).into_iter().find(|ty| {
+                arrow::compute::can_cast_types(f.data_type(), ty) && is_stored(f.name(), ty)
+            });
+            match fallback {
+                Some(ty) => Arc::new(f.as_ref().clone().with_data_type(ty)),
+                None => f.clone(),
+            }
+        })
+        .collect_vec();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// Types that values of type `ty` can be converted into, from the most to the least preferred.
+fn fallback_types(ty: &DataType) -> Vec<DataType> {
+    use arrow::datatypes::TimeUnit::*;
+    use DataType::*;
+
+    match ty {
+        Null => vec![Int64, Utf8],
+        Float16 => vec![Float32, Float64],
+        UInt8 => vec![Int16, Int32, Int64],
+        UInt16 => vec![Int32, Int64],
+        UInt32 => vec![Int64],
+        UInt64 => vec![Decimal128(20, 0), Utf8],
+        LargeUtf8 | Utf8View => vec![Utf8, LargeUtf8],
+        LargeBinary | BinaryView | FixedSizeBinary(_) => vec![Binary, LargeBinary],
+        Timestamp(_, tz) => vec![
+            Timestamp(Microsecond, tz.clone()),
+            Timestamp(Microsecond, None),
+            Int64,
+        ],
+        Date32 | Date64 => vec![Date32, Date64, Timestamp(Microsecond, None)],
+        Time32(_) | Time64(_) => vec![Time64(Microsecond), Time64(Nanosecond), Int64],
+        Duration(_) => vec![Int64],
+        Decimal128(_, _) | Decimal256(_, _) => vec![Utf8],
+        _ => vec![Utf8],
+    }
+}
+
+/// Casts columns of the batch into types of the schema.
+/// Errors name the column that could not be cast.
+pub fn cast_batch(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch, ConnectorError> {
+    if batch.num_columns() != schema.fields().len() {
+        return Err(ConnectorError::DataSchemaMismatch(format!(
+            "expected {} columns, got {}",
+            schema.fields().len(),
+            batch.num_columns()
+        )));
+    }
+
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let columns = (batch.columns().iter().zip(schema.fields()))
+        .map(|(array, field)| -> Result<ArrayRef, ConnectorError> {
+            if array.data_type() == field.data_type() {
+                return Ok(array.clone());
+            }
+            arrow::compute::cast_with_options(array, field.data_type(), &options).map_err(|e| {
+                ConnectorError::DataSchemaMismatch(format!(
+                    "column {}: cannot convert {} into {}: {e}",
+                    field.name(),
+                    array.data_type(),
+                    field.data_type()
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
 fn coerce_float_16_to_32(array: &dyn Array) -> ArrayRef {
     // inefficient, but we don't need efficiency here
 
@@ -0,0 +1,111 @@
+//! Helpers for rendering DDL statements of [crate::api::SchemaEdit]
+//! and for describing columns in [crate::api::SchemaGet::table_get].
+
+use std::fmt::Write;
+
+use arrow::datatypes::Field;
+use itertools::Itertools;
+
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+use crate::api::METADATA_GENERATED;
+use crate::api::{TableOptions, METADATA_COMMENT, METADATA_DEFAULT};
+
+use super::escape::EscapedIdent;
+
+/// Default value of a column, from table options or from field metadata.
+pub fn column_default<'a>(field: &'a Field, options: &'a TableOptions) -> Option<&'a str> {
+    (options.defaults.get(field.name()))
+        .or_else(|| field.metadata().get(METADATA_DEFAULT))
+        .map(String::as_str)
+}
+
+/// Comment of a column, from table options or from field metadata.
+pub fn column_comment<'a>(field: &'a Field, options: &'a TableOptions) -> Option<&'a str> {
+    (options.comments.get(field.name()))
+        .or_else(|| field.metadata().get(METADATA_COMMENT))
+        .map(String::as_str)
+}
+
+/// Renders PRIMARY KEY and UNIQUE constraints, each prefixed by a comma,
+/// so they can be appended to column definitions.
+pub fn table_constraints(options: &TableOptions, escape: fn(&str) -> EscapedIdent<'_>) -> String {
+    let mut res = String::new();
+    if !options.primary_key.is_empty() {
+        let columns = options.primary_key.iter().map(|c| escape(c)).join(",");
+        write!(res, ",PRIMARY KEY ({columns})").unwrap();
+    }
+    for unique in &options.unique {
+        let columns = unique.iter().map(|c| escape(c)).join(",");
+        write!(res, ",UNIQUE ({columns})").unwrap();
+    }
+    res
+}
+
+/// Kind of a column whose values are generated by the data store, see [METADATA_GENERATED].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generated {
+    #[cfg(any(
+        feature = "src_sqlite",
+        feature = "src_postgres",
+        feature = "src_mysql",
+        feature = "src_tiberius"
+    ))]
+    Identity,
+    #[cfg(any(
+        feature = "src_postgres",
+        feature = "src_mysql",
+        feature = "src_tiberius"
+    ))]
+    Computed,
+}
+
+/// Sets [METADATA_DEFAULT] and [METADATA_GENERATED] of a field read from a table.
+/// Default is ignored for generated columns.
+pub fn with_column_default(
+    field: Field,
+    default: Option<String>,
+    generated: Option<Generated>,
+) -> Field {
+    let mut metadata = field.metadata().clone();
+    match (generated, default) {
+        #[cfg(any(
+            feature = "src_sqlite",
+            feature = "src_postgres",
+            feature = "src_mysql",
+            feature = "src_tiberius"
+        ))]
+        (Some(Generated::Identity), _) => {
+            metadata.insert(METADATA_GENERATED.to_string(), "identity".to_string());
+        }
+        #[cfg(any(
+            feature = "src_postgres",
+            feature = "src_mysql",
+            feature = "src_tiberius"
+        ))]
+        (Some(Generated::Computed), _) => {
+            metadata.insert(METADATA_GENERATED.to_string(), "computed".to_string());
+        }
+        (None, Some(default)) => {
+            metadata.insert(METADATA_DEFAULT.to_string(), default);
+        }
+        (None, None) => return field,
+    }
+    field.with_metadata(metadata)
+}
+
+/// Sets [METADATA_COMMENT] of a field read from a table.
+pub fn with_column_comment(field: Field, comment: Option<String>) -> Field {
+    match comment {
+        Some(comment) if !comment.is_empty() => {
+            let mut metadata = field.metadata().clone();
+            metadata.insert(METADATA_COMMENT.to_string(), comment);
+            field.with_metadata(metadata)
+        }
+        _ => field,
+    }
+}
@@ -0,0 +1,285 @@
+//! Column-at-a-time encoding of Arrow arrays.
+//!
+//! Each array is downcast once and its values are passed to a [Consume] in a tight loop,
+//! without per-cell dispatch on the data type.
+//! Used by appenders, which encode all columns of a batch and then assemble rows
+//! from the encoded columns.
+
+use std::ops::Range;
+
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::*;
+use itertools::zip_eq;
+
+use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
+use crate::ConnectorError;
+
+use super::transport::{unsupported_type, Consume, ConsumeTy};
+
+/// Encodes values of `array` at `rows` into `consumer`, in order.
+pub fn encode_column<C: Consume>(
+    field: &Field,
+    array: &dyn Array,
+    rows: Range<usize>,
+    consumer: &mut C,
+) -> Result<(), ConnectorError> {
+    let dt = field.data_type();
+    let nulls = array.nulls().filter(|n| n.null_count() > 0);
+
+    macro_rules! encode_primitive {
+        ($ArrTy: ty) => {{
+            let array = array.as_primitive::<$ArrTy>();
+            let values = array.values();
+            encode_values::<$ArrTy, _>(field, nulls, rows, consumer, |i| values[i])
+        }};
+    }
+
+    match dt {
+        DataType::Null => {
+            if !<C as ConsumeTy<NullType>>::SUPPORTED {
+                return Err(unsupported_type(field.name(), dt));
+            }
+            for _ in rows {
+                ConsumeTy::<NullType>::consume_null(consumer, dt);
+            }
+            Ok(())
+        }
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            encode_values::<BooleanType, _>(field, nulls, rows, consumer, |i| array.value(i))
+        }
+        DataType::Int8 => encode_primitive!(Int8Type),
+        DataType::Int16 => encode_primitive!(Int16Type),
+        DataType::Int32 => encode_primitive!(Int32Type),
+        DataType::Int64 => encode_primitive!(Int64Type),
+        DataType::UInt8 => encode_primitive!(UInt8Type),
+        DataType::UInt16 => encode_primitive!(UInt16Type),
+        DataType::UInt32 => encode_primitive!(UInt32Type),
+        DataType::UInt64 => encode_primitive!(UInt64Type),
+        DataType::Float16 => encode_primitive!(Float16Type),
+        DataType::Float32 => encode_primitive!(Float32Type),
+        DataType::Float64 => encode_primitive!(Float64Type),
+        DataType::Timestamp(TimeUnit::Second, _) => encode_primitive!(TimestampSecondType),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            encode_primitive!(TimestampMillisecondType)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            encode_primitive!(TimestampMicrosecondType)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            encode_primitive!(TimestampNanosecondType)
+        }
+        DataType::Date32 => encode_primitive!(Date32Type),
+        DataType::Date64 => encode_primitive!(Date64Type),
+        DataType::Time32(TimeUnit::Second) => encode_primitive!(Time32SecondType),
+        DataType::Time32(TimeUnit::Millisecond) => encode_primitive!(Time32MillisecondType),
+        DataType::Time64(TimeUnit::Microsecond) => encode_primitive!(Time64MicrosecondType),
+        DataType::Time64(TimeUnit::Nanosecond) => encode_primitive!(Time64NanosecondType),
+        DataType::Interval(IntervalUnit::YearMonth) => encode_primitive!(IntervalYearMonthType),
+        DataType::Interval(IntervalUnit::DayTime) => encode_primitive!(IntervalDayTimeType),
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            encode_primitive!(IntervalMonthDayNanoType)
+        }
+        DataType::Duration(TimeUnit::Second) => encode_primitive!(DurationSecondType),
+        DataType::Duration(TimeUnit::Millisecond) => encode_primitive!(DurationMillisecondType),
+        DataType::Duration(TimeUnit::Microsecond) => encode_primitive!(DurationMicrosecondType),
+        DataType::Duration(TimeUnit::Nanosecond) => encode_primitive!(DurationNanosecondType),
+        DataType::Decimal128(_, _) => encode_primitive!(Decimal128Type),
+        DataType::Decimal256(_, _) => encode_primitive!(Decimal256Type),
+        DataType::Binary => {
+            let array = array.as_binary::<i32>();
+            encode_values::<BinaryType, _>(field, nulls, rows, consumer, |i| {
+                array.value(i).to_vec()
+            })
+        }
+        DataType::LargeBinary => {
+            let array = array.as_binary::<i64>();
+            encode_values::<LargeBinaryType, _>(field, nulls, rows, consumer, |i| {
+                array.value(i).to_vec()
+            })
+        }
+        DataType::FixedSizeBinary(_) => {
+            let array = array.as_fixed_size_binary();
+            encode_values::<FixedSizeBinaryType, _>(field, nulls, rows, consumer, |i| {
+                array.value(i).to_vec()
+            })
+        }
+        DataType::Utf8 => {
+            let array = array.as_string::<i32>();
+            encode_values::<Utf8Type, _>(field, nulls, rows, consumer, |i| {
+                array.value(i).to_string()
+            })
+        }
+        DataType::LargeUtf8 => {
+            let array = array.as_string::<i64>();
+            encode_values::<LargeUtf8Type, _>(field, nulls, rows, consumer, |i| {
+                array.value(i).to_string()
+            })
+        }
+        _ => Err(unsupported_type(field.name(), dt)),
+    }
+}
+
+/// Encodes all columns of a batch into values of type `V`.
+///
+/// Returned iterator yields the values in row-major order, `batch.num_columns()` values per row.
+pub fn encode_batch<V>(batch: &RecordBatch) -> Result<RowMajorValues<V>, ConnectorError>
+where
+    Vec<V>: Consume,
+{
+    let rows = 0..batch.num_rows();
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (array, field) in zip_eq(batch.columns(), batch.schema_ref().fields()) {
+        let mut values = Vec::with_capacity(rows.len());
+        encode_column(field, array, rows.clone(), &mut values)?;
+        columns.push(values.into_iter());
+    }
+    Ok(RowMajorValues {
+        columns,
+        next_column: 0,
+    })
+}
+
+/// Iterator over values of encoded columns in row-major order, produced by [encode_batch].
+pub struct RowMajorValues<V> {
+    columns: Vec<std::vec::IntoIter<V>>,
+    next_column: usize,
+}
+
+impl<V> Iterator for RowMajorValues<V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        let value = self.columns.get_mut(self.next_column)?.next()?;
+        self.next_column = (self.next_column + 1) % self.columns.len();
+        Some(value)
+    }
+}
+
+#[inline]
+fn encode_values<T: ArrowType, C: ConsumeTy<T>>(
+    field: &Field,
+    nulls: Option<&NullBuffer>,
+    rows: Range<usize>,
+    consumer: &mut C,
+    value: impl Fn(usize) -> T::Native,
+) -> Result<(), ConnectorError> {
+    let dt = field.data_type();
+    if !C::SUPPORTED {
+        return Err(unsupported_type(field.name(), dt));
+    }
+
+    if let Some(nulls) = nulls {
+        for i in rows {
+            if nulls.is_null(i) {
+                consumer.consume_null(dt);
+            } else {
+                consumer.consume(dt, value(i));
+            }
+        }
+    } else {
+        for i in rows {
+            consumer.consume(dt, value(i));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, BooleanArray, Int32Array, NullArray, StringArray};
+
+    use super::*;
+
+    /// Values formatted with [std::fmt::Debug], None for nulls.
+    #[derive(Debug, PartialEq)]
+    struct Cell(Option<String>);
+
+    impl Consume for Vec<Cell> {}
+
+    impl<T: ArrowType> ConsumeTy<T> for Vec<Cell>
+    where
+        T::Native: std::fmt::Debug,
+    {
+        fn consume(&mut self, _ty: &DataType, value: T::Native) {
+            self.push(Cell(Some(format!("{value:?}"))));
+        }
+
+        fn consume_null(&mut self, _ty: &DataType) {
+            self.push(Cell(None));
+        }
+    }
+
+    fn cells(values: &[Option<&str>]) -> Vec<Cell> {
+        values.iter().map(|v| Cell(v.map(str::to_string))).collect()
+    }
+
+    fn encode(array: &dyn Array, rows: Range<usize>) -> Vec<Cell> {
+        let field = Field::new("a", array.data_type().clone(), true);
+        let mut values = Vec::new();
+        encode_column(&field, array, rows, &mut values).unwrap();
+        values
+    }
+
+    #[test]
+    fn sliced_primitive() {
+        let array = Int32Array::from(vec![Some(1), None, Some(3), Some(4), None]);
+        let sliced = array.slice(1, 4);
+
+        let expected = cells(&[None, Some("3"), Some("4"), None]);
+        assert_eq!(encode(&sliced, 0..4), expected);
+        assert_eq!(encode(&sliced, 1..3), cells(&[Some("3"), Some("4")]));
+        assert_eq!(encode(&sliced, 2..2), cells(&[]));
+    }
+
+    #[test]
+    fn sliced_without_nulls() {
+        // the slice has no nulls, but the null buffer of the array is still present
+        let array = Int32Array::from(vec![None, Some(2), Some(3)]);
+        let sliced = array.slice(1, 2);
+        assert_eq!(encode(&sliced, 0..2), cells(&[Some("2"), Some("3")]));
+    }
+
+    #[test]
+    fn sliced_boolean() {
+        // values and nulls are bit-packed, so slicing by 1 is not aligned to bytes
+        let array = BooleanArray::from(vec![Some(true), Some(false), Some(true), None]);
+        let sliced = array.slice(1, 3);
+
+        let expected = cells(&[Some("false"), Some("true"), None]);
+        assert_eq!(encode(&sliced, 0..3), expected);
+    }
+
+    #[test]
+    fn sliced_string() {
+        let array = StringArray::from(vec![Some("a"), None, Some("c"), Some("d")]);
+        let sliced = array.slice(1, 3);
+
+        let expected = cells(&[None, Some("\"c\""), Some("\"d\"")]);
+        assert_eq!(encode(&sliced, 0..3), expected);
+        assert_eq!(encode(&sliced, 2..3), cells(&[Some("\"d\"")]));
+    }
+
+    #[test]
+    fn null_type() {
+        let array = NullArray::new(3);
+        assert_eq!(encode(&array, 1..3), cells(&[None, None]));
+    }
+
+    #[test]
+    fn batch_row_major() {
+        let ints: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), None]));
+        let strings: ArrayRef = Arc::new(StringArray::from(vec![Some("x"), None, Some("z")]));
+        let batch = RecordBatch::try_from_iter([("a", ints), ("b", strings)]).unwrap();
+
+        let values: Vec<Cell> = encode_batch(&batch.slice(1, 2)).unwrap().collect();
+        let expected = cells(&[Some("2"), None, None, Some("\"z\"")]);
+        assert_eq!(values, expected);
+
+        let values: Vec<Cell> = encode_batch(&batch.slice(3, 0)).unwrap().collect();
+        assert_eq!(values, cells(&[]));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::ErrorKind;
+
+/// Classifies an error of a database that does not report its cause with an error code.
+///
+/// SQLite reports most errors with the generic `SQLITE_ERROR` and DuckDB reports all
+/// errors with the same code, so the only indication of the cause is the message.
+/// The messages are not part of the API of either database and might change between
+/// versions, which is why all matching of messages is done here.
+///
+/// `message` must not contain the error type prefix of DuckDB (i.e. `Catalog Error: `).
+pub(crate) fn error_kind_from_message(message: &str) -> ErrorKind {
+    // SQLite: `no such table: t`
+    // DuckDB: `Table with name t does not exist!`
+    if message.starts_with("no such table")
+        || (message.starts_with("Table with name") && message.contains("does not exist"))
+    {
+        return ErrorKind::UndefinedTable;
+    }
+
+    // SQLite: `no such column: c`
+    // DuckDB: `Referenced column "c" not found in FROM clause!`
+    // DuckDB: `Table "t" does not have a column named "c"`
+    if message.starts_with("no such column")
+        || (message.contains("column") && message.contains("not found"))
+        || message.contains("does not have a column")
+    {
+        return ErrorKind::UndefinedColumn;
+    }
+
+    // SQLite: `table t already exists`
+    // SQLite: `there is already another table or index with this name: t`
+    // DuckDB: `Table with name t already exists!`
+    if (message.starts_with("table ") && message.ends_with("already exists"))
+        || message.starts_with("there is already another table")
+        || (message.starts_with("Table with name") && message.ends_with("already exists!"))
+    {
+        return ErrorKind::TableExists;
+    }
+
+    // SQLite: `near "x": syntax error`
+    if message.contains("syntax error") {
+        return ErrorKind::SyntaxError;
+    }
+
+    // DuckDB: `Duplicate key "id: 1" violates primary key constraint.`
+    // DuckDB: `PRIMARY KEY or UNIQUE constraint violation: duplicate key "1"`
+    // DuckDB: `Violates foreign key constraint because key "id: 1" does not exist ...`
+    // DuckDB: `NOT NULL constraint failed: t.c`
+    // SQLite reports constraint violations with extended codes, so they are not matched here.
+    if message.starts_with("Duplicate key") || message.contains("violation: duplicate key") {
+        return ErrorKind::UniqueViolation;
+    }
+    if message.contains("foreign key constraint") {
+        return ErrorKind::ForeignKeyViolation;
+    }
+    if message.starts_with("NOT NULL") {
+        return ErrorKind::NotNullViolation;
+    }
+
+    // DuckDB: `Catalog write-write conflict on create with "t"`
+    // DuckDB: `Conflict on tuple deletion!`
+    // DuckDB: `Transaction conflict: cannot update a table that has been altered!`
+    if message.contains("write conflict")
+        || message.starts_with("Conflict on")
+        || message.starts_with("Transaction conflict")
+    {
+        return ErrorKind::SerializationFailure;
+    }
+
+    ErrorKind::Other
+}
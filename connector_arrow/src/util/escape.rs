@@ -42,3 +42,14 @@ impl fmt::Display for EscapedIdent<'_> {
         }
     }
 }
+
+/// Quotes a string literal, escaping single quotes by doubling them.
+#[cfg(any(
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub fn escaped_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -2,16 +2,63 @@
 //! Used by database client implementations.
 
 mod arrow_reader;
+mod cast_reader;
 pub mod coerce;
 pub mod decimal;
+pub mod encode;
+pub mod partition;
+pub mod retry;
 mod row_collect;
 mod row_reader;
 mod row_writer;
 pub mod transport;
+mod type_mapper;
 
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) mod append;
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) mod ddl;
+#[cfg(any(feature = "src_sqlite", feature = "src_duckdb"))]
+pub(crate) mod error_message;
 pub(crate) mod escape;
 
 pub use arrow_reader::ArrowReader;
+pub use cast_reader::CastReader;
+
+#[cfg(any(feature = "src_sqlite", feature = "src_postgres"))]
+pub(crate) use cast_reader::check_field_count;
 pub use row_collect::{collect_rows_to_arrow, next_batch_from_rows, CellReader, RowsReader};
 pub use row_reader::ArrayCellRef;
 pub use row_writer::ArrowRowWriter;
+pub use type_mapper::TypeRules;
+
+#[cfg(feature = "src_postgres")]
+pub(crate) use type_mapper::map_field;
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) use type_mapper::map_schema;
+#[cfg(any(
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) use type_mapper::unsupported_column_type;
+pub(crate) use type_mapper::{arrow_into_db, db_into_arrow};
@@ -0,0 +1,473 @@
+//! Reading and writing data in parallel, over multiple connections.
+
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use itertools::Itertools;
+
+use crate::api::{Append, Connector, ResultReader, SchemaEdit, SchemaGet, Statement};
+use crate::util::coerce::cast_batch;
+use crate::{ConnectorError, TableCreateError};
+
+/// Executes the query in `n` partitions, each on its own connection and in its own thread.
+///
+/// Partitions are ranges of values of `partition_on`, which must be an integer expression
+/// over the result columns of the query (i.e. a column name, quoted if needed).
+/// Bounds are computed from MIN and MAX of `partition_on`. Rows where `partition_on` is NULL
+/// are included in the first partition.
+///
+/// Connections are opened by calling `conn_factory` within the worker threads.
+/// Returned reader yields batches of all partitions in order in which they are received,
+/// cast into a schema that is consistent across partitions.
+pub fn partitioned_query<C, F>(
+    conn_factory: F,
+    sql: &str,
+    partition_on: &str,
+    n: usize,
+) -> Result<PartitionedReader, ConnectorError>
+where
+    C: Connector,
+    F: Fn() -> Result<C, ConnectorError> + Send + Sync + 'static,
+{
+    let bounds = {
+        let mut conn = conn_factory()?;
+        query_bounds(&mut conn, sql, partition_on)?
+    };
+
+    let queries = match bounds {
+        Some((min, max)) => partition_queries(sql, partition_on, min, max, n.max(1)),
+        None => vec![sql.to_string()],
+    };
+    log::debug!("partitioned queries: {queries:?}");
+
+    let partitions = queries.len();
+    let (sender, receiver) = sync_channel(partitions * 2);
+    let conn_factory = Arc::new(conn_factory);
+    for query in queries {
+        let conn_factory = conn_factory.clone();
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = run_partition(conn_factory.as_ref(), &query, &sender) {
+                let _ = sender.send(Message::Batch(Err(e)));
+            }
+        });
+    }
+
+    Ok(PartitionedReader {
+        receiver,
+        partitions,
+        schemas: Vec::with_capacity(partitions),
+        schema: None,
+        buffer: VecDeque::new(),
+    })
+}
+
+/// Returns MIN and MAX of the partition expression, or None if the result is empty.
+fn query_bounds<C: Connector>(
+    conn: &mut C,
+    sql: &str,
+    partition_on: &str,
+) -> Result<Option<(i64, i64)>, ConnectorError> {
+    let query = format!("SELECT MIN({partition_on}), MAX({partition_on}) FROM ({sql}) AS t");
+    let batches = crate::query(conn, &query)?;
+    let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+        return Ok(None);
+    };
+
+    let mut bounds = [0; 2];
+    for (bound, column) in bounds.iter_mut().zip(batch.columns()) {
+        if column.is_null(0) {
+            return Ok(None);
+        }
+        if !column.data_type().is_integer() {
+            return Err(ConnectorError::DataSchemaMismatch(format!(
+                "partition expression {partition_on} must be an integer, got {}",
+                column.data_type()
+            )));
+        }
+        let column = arrow::compute::cast(column, &DataType::Int64)?;
+        *bound = column.as_primitive::<Int64Type>().value(0);
+    }
+    Ok(Some((bounds[0], bounds[1])))
+}
+
+/// Splits range `min..=max` into `n` queries. Never produces empty ranges.
+fn partition_queries(sql: &str, partition_on: &str, min: i64, max: i64, n: usize) -> Vec<String> {
+    let span = max as i128 - min as i128 + 1;
+    let n = (n as i128).min(span);
+
+    let bound = |i: i128| (min as i128 + span * i / n) as i64;
+    (0..n)
+        .map(|i| {
+            let lower = bound(i);
+            let mut condition = if i + 1 == n {
+                format!("{partition_on} >= {lower} AND {partition_on} <= {max}")
+            } else {
+                let upper = bound(i + 1);
+                format!("{partition_on} >= {lower} AND {partition_on} < {upper}")
+            };
+            if i == 0 {
+                condition = format!("({condition}) OR {partition_on} IS NULL");
+            }
+            format!("SELECT * FROM ({sql}) AS t WHERE {condition}")
+        })
+        .collect()
+}
+
+enum Message {
+    Schema(SchemaRef),
+    Batch(Result<RecordBatch, ConnectorError>),
+}
+
+fn run_partition<C, F>(
+    conn_factory: &F,
+    query: &str,
+    sender: &SyncSender<Message>,
+) -> Result<(), ConnectorError>
+where
+    C: Connector,
+    F: Fn() -> Result<C, ConnectorError>,
+{
+    let mut conn = conn_factory()?;
+    let mut stmt = conn.query(query)?;
+    let mut reader = stmt.start([])?;
+
+    let schema = reader.get_schema()?;
+    if sender.send(Message::Schema(schema)).is_err() {
+        return Ok(());
+    }
+    for batch in reader {
+        if sender.send(Message::Batch(batch)).is_err() {
+            // the receiver was dropped, no need to continue
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Reader over results of [partitioned_query].
+pub struct PartitionedReader {
+    receiver: Receiver<Message>,
+    partitions: usize,
+
+    /// Schemas received from partitions so far.
+    schemas: Vec<SchemaRef>,
+    /// Schema unified from schemas of all partitions.
+    schema: Option<SchemaRef>,
+
+    /// Batches that were received before schemas of all partitions.
+    buffer: VecDeque<Result<RecordBatch, ConnectorError>>,
+}
+
+impl PartitionedReader {
+    /// Waits for schemas of all partitions and unifies them.
+    fn ensure_schema(&mut self) -> Result<SchemaRef, ConnectorError> {
+        if let Some(schema) = &self.schema {
+            return Ok(schema.clone());
+        }
+
+        while self.schemas.len() < self.partitions {
+            match self.receiver.recv() {
+                Ok(Message::Schema(schema)) => self.schemas.push(schema),
+                Ok(Message::Batch(Err(e))) => return Err(e),
+                Ok(Message::Batch(batch)) => self.buffer.push_back(batch),
+                Err(_) => {
+                    return Err(ConnectorError::DataSchemaMismatch(
+                        "partition did not return a schema".into(),
+                    ))
+                }
+            }
+        }
+
+        let schema = unify_schemas(&self.schemas)?;
+        self.schema = Some(schema.clone());
+        Ok(schema)
+    }
+}
+
+/// Combines schemas of partitions, which might differ in nullability and in columns
+/// whose values were all NULL in some partitions.
+fn unify_schemas(schemas: &[SchemaRef]) -> Result<SchemaRef, ConnectorError> {
+    let first = &schemas[0];
+    let mut fields: Vec<Field> = first.fields().iter().map(|f| f.as_ref().clone()).collect();
+
+    for schema in &schemas[1..] {
+        if schema.fields().len() != fields.len() {
+            return Err(ConnectorError::DataSchemaMismatch(
+                "partitions returned different number of columns".into(),
+            ));
+        }
+        for (field, other) in fields.iter_mut().zip(schema.fields()) {
+            let nullable = field.is_nullable() || other.is_nullable();
+            if field.data_type() == &DataType::Null {
+                *field = field.clone().with_data_type(other.data_type().clone());
+            } else if other.data_type() != &DataType::Null && other.data_type() != field.data_type()
+            {
+                return Err(ConnectorError::DataSchemaMismatch(format!(
+                    "column {}: partitions returned different types {} and {}",
+                    field.name(),
+                    field.data_type(),
+                    other.data_type()
+                )));
+            }
+            *field = field.clone().with_nullable(nullable);
+        }
+    }
+
+    Ok(Arc::new(Schema::new_with_metadata(
+        fields,
+        first.metadata().clone(),
+    )))
+}
+
+impl ResultReader<'_> for PartitionedReader {
+    fn get_schema(&mut self) -> Result<SchemaRef, ConnectorError> {
+        self.ensure_schema()
+    }
+}
+
+impl Iterator for PartitionedReader {
+    type Item = Result<RecordBatch, ConnectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let schema = match self.ensure_schema() {
+            Ok(schema) => schema,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let batch = match self.buffer.pop_front() {
+            Some(batch) => batch,
+            None => loop {
+                match self.receiver.recv() {
+                    Ok(Message::Batch(batch)) => break batch,
+                    Ok(Message::Schema(_)) => continue,
+                    // all workers are done
+                    Err(_) => return None,
+                }
+            },
+        };
+        Some(batch.and_then(|b| cast_batch(&b, &schema)))
+    }
+}
+
+/// When data of [partitioned_append] is committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitMode {
+    /// Each worker commits its own append when the input is exhausted.
+    ///
+    /// If any of the workers fails, partitions of other workers might already be committed.
+    PerPartition,
+
+    /// Workers append into their own staging tables, which are moved into the target table
+    /// with a single `INSERT INTO ... SELECT` statement, after all workers have succeeded.
+    ///
+    /// If any of the workers fails, the target table is not changed.
+    /// Staging tables are dropped in both cases.
+    AllOrNothing,
+}
+
+/// Appends batches of `reader` into table `table_name`, over `n` connections in parallel.
+///
+/// Connections are opened by calling `conn_factory` within the worker threads, each of
+/// which drives its own [Append]. Batches are read on the calling thread and
+/// handed to the first idle worker, so order of rows in the table is not preserved.
+///
+/// Returns number of rows appended by each of the workers.
+pub fn partitioned_append<C, F, R>(
+    conn_factory: F,
+    table_name: &str,
+    reader: R,
+    n: usize,
+    commit: CommitMode,
+) -> Result<Vec<usize>, ConnectorError>
+where
+    C: Connector + SchemaGet + SchemaEdit,
+    F: Fn() -> Result<C, ConnectorError> + Sync,
+    R: RecordBatchReader,
+{
+    let n = n.max(1);
+    match commit {
+        CommitMode::PerPartition => {
+            let tables = vec![table_name.to_string(); n];
+            append_in_workers(&conn_factory, &tables, reader)
+        }
+        CommitMode::AllOrNothing => {
+            let mut conn = conn_factory()?;
+
+            // staging tables copy column types of the target table,
+            // other constraints are checked when rows are moved into the target table
+            let staging = staging_table_names(n);
+            let mut created = Vec::with_capacity(n);
+            let mut res = Ok(Vec::new());
+            for name in &staging {
+                match conn.table_create_like(name, table_name) {
+                    Ok(()) => created.push(name.clone()),
+                    Err(TableCreateError::Connector(e)) => {
+                        res = Err(e);
+                        break;
+                    }
+                    Err(TableCreateError::TableExists) => {
+                        res = Err(ConnectorError::IncompatibleSchema {
+                            table_name: name.clone(),
+                            message: "staging table already exists".into(),
+                            hint: None,
+                        });
+                        break;
+                    }
+                }
+            }
+
+            // panics of workers are caught, so staging tables can be dropped
+            let mut panic = None;
+            if res.is_ok() {
+                let append =
+                    AssertUnwindSafe(|| append_in_workers(&conn_factory, &staging, reader));
+                match std::panic::catch_unwind(append) {
+                    Ok(Ok(rows)) => {
+                        res = move_staging_into(&mut conn, &staging, table_name).map(|_| rows)
+                    }
+                    Ok(Err(e)) => res = Err(e),
+                    Err(p) => panic = Some(p),
+                }
+            }
+
+            for name in created {
+                if let Err(e) = conn.table_drop(&name) {
+                    log::warn!("cannot drop staging table {name}: {e}");
+                }
+            }
+            if let Some(panic) = panic {
+                std::panic::resume_unwind(panic);
+            }
+            res
+        }
+    }
+}
+
+/// Names of staging tables, unique within the process and valid as unquoted identifiers.
+pub(crate) fn staging_table_names(n: usize) -> Vec<String> {
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let pid = std::process::id();
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros());
+    (0..n)
+        .map(|i| format!("connector_arrow_staging_{time}_{pid}_{id}_{i}"))
+        .collect()
+}
+
+fn move_staging_into<C: Connector>(
+    conn: &mut C,
+    staging: &[String],
+    table_name: &str,
+) -> Result<(), ConnectorError> {
+    let select = staging
+        .iter()
+        .map(|name| format!("SELECT * FROM {}", C::quote_ident(name)))
+        .join(" UNION ALL ");
+    let query = format!("INSERT INTO {} {select}", C::quote_ident(table_name));
+
+    let mut stmt = conn.query(&query)?;
+    for batch in stmt.start([])? {
+        batch?;
+    }
+    Ok(())
+}
+
+/// Spawns a worker for each of the tables and feeds them batches of the reader.
+fn append_in_workers<C, F, R>(
+    conn_factory: &F,
+    tables: &[String],
+    reader: R,
+) -> Result<Vec<usize>, ConnectorError>
+where
+    C: Connector,
+    F: Fn() -> Result<C, ConnectorError> + Sync,
+    R: RecordBatchReader,
+{
+    let (sender, receiver) = sync_channel::<RecordBatch>(tables.len());
+    // only workers hold the receiver, so it is dropped when the last of them exits,
+    // which unblocks the sender even if they all fail
+    let receiver = Arc::new(Mutex::new(receiver));
+    let failed = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = tables
+            .iter()
+            .map(|table_name| {
+                let (receiver, failed) = (receiver.clone(), &failed);
+                scope.spawn(move || {
+                    let res = append_worker(conn_factory, table_name, &receiver);
+                    drop(receiver);
+                    if res.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    res
+                })
+            })
+            .collect();
+        drop(receiver);
+
+        let mut res = Ok(());
+        for batch in reader {
+            if failed.load(Ordering::Relaxed) {
+                break;
+            }
+            match batch {
+                Ok(batch) => {
+                    if sender.send(batch).is_err() {
+                        // all workers have exited, their errors are returned below
+                        break;
+                    }
+                }
+                Err(e) => {
+                    res = Err(e.into());
+                    failed.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+        drop(sender);
+
+        let rows = workers
+            .into_iter()
+            .map(|w| w.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        res.map(|_| rows)
+    })
+}
+
+fn append_worker<C, F>(
+    conn_factory: &F,
+    table_name: &str,
+    receiver: &Mutex<Receiver<RecordBatch>>,
+) -> Result<usize, ConnectorError>
+where
+    C: Connector,
+    F: Fn() -> Result<C, ConnectorError>,
+{
+    let mut conn = conn_factory()?;
+    let mut appender = conn.append(table_name)?;
+
+    let mut rows = 0;
+    loop {
+        // the lock is released before appending, so other workers can receive
+        let batch = receiver.lock().unwrap().recv();
+        let Ok(batch) = batch else {
+            // all batches were sent
+            break;
+        };
+        rows += batch.num_rows();
+        appender.append(batch)?;
+    }
+    appender.finish()?;
+    Ok(rows)
+}
@@ -0,0 +1,218 @@
+//! Retrying of operations that failed because of transient errors.
+//!
+//! ```no_run
+//! use connector_arrow::util::retry::{RetryPolicy, RetryingConnector};
+//!
+//! # fn main() -> Result<(), connector_arrow::ConnectorError> {
+//! let open = || {
+//!     let conn = rusqlite::Connection::open("my_db.sqlite")?;
+//!     Ok(connector_arrow::sqlite::SQLiteConnection::new(conn))
+//! };
+//! let mut conn = RetryingConnector::new(open, RetryPolicy::new().max_retries(5));
+//!
+//! let batches = conn.query("SELECT * FROM my_table")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use arrow::record_batch::RecordBatch;
+
+use crate::api::{Append, AppendSummary, Connector, Statement};
+use crate::{ConnectorError, ErrorKind};
+
+/// Describes which errors are retried and how long to wait between attempts.
+///
+/// Default policy retries 3 times, with backoff starting at 100ms and doubling up to 5s.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of attempts after the first one. Zero disables retrying.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Waits `initial` before the first retry and doubles the wait for each subsequent retry,
+    /// up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Errors that might not occur again if the operation is retried:
+    /// serialization failures, deadlocks, busy databases and lost connections.
+    pub fn is_transient(err: &ConnectorError) -> bool {
+        matches!(
+            err.kind(),
+            ErrorKind::SerializationFailure
+                | ErrorKind::Deadlock
+                | ErrorKind::Busy
+                | ErrorKind::ConnectionLost
+        )
+    }
+
+    fn backoff_before(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry.min(31) as u32);
+        (self.initial_backoff.saturating_mul(factor)).min(self.max_backoff)
+    }
+
+    /// Calls `op` until it succeeds, it returns an error that is not transient,
+    /// or the retries are exhausted.
+    ///
+    /// `op` must be idempotent.
+    pub fn run<T>(
+        &self,
+        mut op: impl FnMut() -> Result<T, ConnectorError>,
+    ) -> Result<T, ConnectorError> {
+        self.run_attempts(|| op().map_err(|e| (e, true)))
+    }
+
+    /// Like [RetryPolicy::run], but `op` decides if its error may be retried.
+    fn run_attempts<T>(
+        &self,
+        mut op: impl FnMut() -> Result<T, (ConnectorError, bool)>,
+    ) -> Result<T, ConnectorError> {
+        let mut retry = 0;
+        loop {
+            match op() {
+                Ok(res) => return Ok(res),
+                Err((e, retryable))
+                    if retryable && retry < self.max_retries && Self::is_transient(&e) =>
+                {
+                    let backoff = self.backoff_before(retry);
+                    log::warn!("retrying in {backoff:?} after a transient error: {e}");
+                    std::thread::sleep(backoff);
+                    retry += 1;
+                }
+                Err((e, _)) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Connection wrapper that retries queries and appends on transient errors
+/// and reconnects when the connection is lost.
+///
+/// Connections are opened with `conn_factory`, lazily on first use and again after a
+/// connection has been lost or an append has failed.
+pub struct RetryingConnector<C, F> {
+    conn: Option<C>,
+    conn_factory: F,
+    policy: RetryPolicy,
+}
+
+impl<C, F> RetryingConnector<C, F>
+where
+    C: Connector,
+    F: FnMut() -> Result<C, ConnectorError>,
+{
+    pub fn new(conn_factory: F, policy: RetryPolicy) -> Self {
+        RetryingConnector {
+            conn: None,
+            conn_factory,
+            policy,
+        }
+    }
+
+    /// Current connection, which is opened if needed. Operations on it are not retried.
+    pub fn connection(&mut self) -> Result<&mut C, ConnectorError> {
+        let conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => (self.conn_factory)()?,
+        };
+        Ok(self.conn.insert(conn))
+    }
+
+    /// Executes an idempotent query and collects the result.
+    pub fn query(&mut self, query: &str) -> Result<Vec<RecordBatch>, ConnectorError> {
+        let mut batches = Vec::new();
+        self.query_for_each(query, |batch| {
+            batches.push(batch);
+            Ok(())
+        })?;
+        Ok(batches)
+    }
+
+    /// Executes an idempotent query and passes each batch of the result to `f`.
+    ///
+    /// The query is retried only until the first batch is passed to `f`.
+    /// Errors after that, including the errors returned by `f`, are returned as they are.
+    pub fn query_for_each(
+        &mut self,
+        query: &str,
+        mut f: impl FnMut(RecordBatch) -> Result<(), ConnectorError>,
+    ) -> Result<(), ConnectorError> {
+        let policy = self.policy.clone();
+        policy.run_attempts(|| {
+            let mut yielded = false;
+            let res = self.connection().and_then(|conn| {
+                let mut stmt = conn.query(query)?;
+                for batch in stmt.start([])? {
+                    yielded = true;
+                    f(batch?)?;
+                }
+                Ok(())
+            });
+            res.map_err(|e| (self.discard_if_lost(e), !yielded))
+        })
+    }
+
+    /// Appends all batches into a table with a single appender.
+    ///
+    /// The whole load is retried on a new connection, as long as the error happens before
+    /// the appender is finished. Errors while finishing are never retried, because the data
+    /// might have already been committed.
+    pub fn append(
+        &mut self,
+        table_name: &str,
+        batches: &[RecordBatch],
+    ) -> Result<AppendSummary, ConnectorError> {
+        let policy = self.policy.clone();
+        policy.run_attempts(|| {
+            let mut finishing = false;
+            let res = self.connection().and_then(|conn| {
+                let mut appender = conn.append(table_name)?;
+                for batch in batches {
+                    appender.append(batch.clone())?;
+                }
+                finishing = true;
+                appender.finish()
+            });
+            res.map_err(|e| {
+                // an unfinished append might have left an open transaction on the connection,
+                // which must not be committed by a subsequent append
+                self.conn = None;
+                (e, !finishing)
+            })
+        })
+    }
+
+    fn discard_if_lost(&mut self, err: ConnectorError) -> ConnectorError {
+        if err.kind() == ErrorKind::ConnectionLost {
+            self.conn = None;
+        }
+        err
+    }
+}
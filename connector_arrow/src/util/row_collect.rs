@@ -2,26 +2,35 @@ use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use itertools::Itertools;
 
-use crate::errors::ConnectorError;
-use crate::util::{transport, ArrowRowWriter};
+use crate::errors::{ConnectorError, ErrorContext};
+use crate::util::transport::{self, Transporter};
+use crate::util::ArrowRowWriter;
 
 /// Get next [RecordBatch] from a row-major reader.
+///
+/// `rows_read` is the number of rows read by previous calls. It locates errors within
+/// the whole result and is advanced by the number of rows in the returned batch.
 pub fn next_batch_from_rows<'stmt, T: RowsReader<'stmt>>(
     schema: &SchemaRef,
     rows_reader: &mut T,
     batch_size: usize,
+    rows_read: &mut usize,
 ) -> Result<Option<RecordBatch>, ConnectorError> {
     let mut writer = ArrowRowWriter::new(schema.clone(), batch_size);
+    let transporters = Transporter::compile_all(&schema.fields);
 
     for _ in 0..batch_size {
         if let Some(mut cell_reader) = rows_reader.next_row()? {
             writer.prepare_for_batch(1)?;
 
-            for field in &schema.fields {
+            for (field, transporter) in schema.fields.iter().zip(&transporters) {
                 let cell_ref = cell_reader.next_cell();
 
-                transport::transport(field, cell_ref.unwrap(), &mut writer)?;
+                transporter
+                    .transport(cell_ref.unwrap(), &mut writer)
+                    .map_err(|e| with_location(e, *rows_read, field.name()))?;
             }
+            *rows_read += 1;
         } else {
             break;
         }
@@ -42,23 +51,38 @@ pub fn collect_rows_to_arrow<'stmt, T: RowsReader<'stmt>>(
     batch_size: usize,
 ) -> Result<Vec<RecordBatch>, ConnectorError> {
     let mut writer = ArrowRowWriter::new(schema.clone(), batch_size);
+    let transporters = Transporter::compile_all(&schema.fields);
     log::debug!("reading rows");
 
+    let mut row = 0;
     while let Some(mut row_reader) = rows_reader.next_row()? {
         writer.prepare_for_batch(1)?;
 
-        log::debug!("reading row");
-        for field in &schema.fields {
-            log::debug!("reading cell");
+        for (field, transporter) in schema.fields.iter().zip(&transporters) {
             let cell_ref = row_reader.next_cell();
 
-            log::debug!("transporting cell: {cell_ref:?}");
-            transport::transport(field, cell_ref.unwrap(), &mut writer)?;
+            transporter
+                .transport(cell_ref.unwrap(), &mut writer)
+                .map_err(|e| with_location(e, row, field.name()))?;
         }
+        row += 1;
     }
     writer.finish()
 }
 
+/// Adds the location of the value to errors about values that cannot be converted.
+/// Row is counted from the first row of the result.
+fn with_location(err: ConnectorError, row: usize, column_name: &str) -> ConnectorError {
+    match err {
+        ConnectorError::IncompatibleSchema { .. } | ConnectorError::NotSupported { .. } => err,
+        err => err.with_context(ErrorContext {
+            row: Some(row),
+            column: Some(column_name.to_string()),
+            ..Default::default()
+        }),
+    }
+}
+
 /// Iterator over rows.
 // Cannot be an actual iterator, because of lifetime requirements (I think).
 pub trait RowsReader<'stmt> {
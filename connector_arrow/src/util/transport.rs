@@ -4,36 +4,156 @@ use arrow::datatypes::*;
 use crate::errors::ConnectorError;
 use crate::types::{ArrowType, FixedSizeBinaryType, NullType};
 
-macro_rules! impl_transport_match {
-    ($f: expr, $c: expr, $p: expr, $({ $Pat: pat => $ArrTy: ty })*) => {
-        let dt = $f.data_type();
-        if !$f.is_nullable() {
-            match dt {
-                Null => ConsumeTy::<NullType>::consume_null($c, dt),
-                $(
-                    $Pat => ConsumeTy::<$ArrTy>::consume($c, dt, ProduceTy::<$ArrTy>::produce($p)?),
-                )*
-                _ => todo!("unimplemented transport of {:?}", dt),
+macro_rules! impl_transporter {
+    ($({ $Pat: pat => $Kind: ident, $ArrTy: ty })*) => {
+        /// Arrow type of a [Transporter], resolved from [DataType].
+        // This is a tag and not a function pointer, because producers borrow rows
+        // with a different lifetime for each row, so their type cannot be fixed in advance.
+        #[derive(Debug, Clone, Copy)]
+        enum TransportKind {
+            Null,
+            Unsupported,
+            $($Kind,)*
+        }
+
+        impl TransportKind {
+            fn of(dt: &DataType) -> Self {
+                use DataType::*;
+                match dt {
+                    Null => TransportKind::Null,
+                    $($Pat => TransportKind::$Kind,)*
+                    _ => TransportKind::Unsupported,
+                }
             }
-        } else {
-            match dt {
-                Null => ConsumeTy::<NullType>::consume_null($c, dt),
-                $(
-                    $Pat => {
-                        if let Some(v) = ProduceTy::<$ArrTy>::produce_opt($p)? {
-                            ConsumeTy::<$ArrTy>::consume($c, dt, v)
-                        } else {
-                            ConsumeTy::<$ArrTy>::consume_null($c, dt)
+        }
+
+        impl Transporter {
+            /// Take a value from [Produce] and insert it into [Consume].
+            #[inline]
+            pub fn transport<'r, P: Produce<'r>, C: Consume>(
+                &self,
+                producer: P,
+                consumer: &mut C,
+            ) -> Result<(), ConnectorError> {
+                let dt = &self.data_type;
+                match (self.kind, self.nullable) {
+                    (TransportKind::Null, _) => {
+                        if !<C as ConsumeTy<NullType>>::SUPPORTED {
+                            return Err(unsupported_type(&self.name, dt));
+                        }
+                        ConsumeTy::<NullType>::consume_null(consumer, dt)
+                    }
+                    (TransportKind::Unsupported, _) => {
+                        return Err(unsupported_type(&self.name, dt));
+                    }
+                    $(
+                        (TransportKind::$Kind, _)
+                            if !<P as ProduceTy<$ArrTy>>::SUPPORTED
+                                || !<C as ConsumeTy<$ArrTy>>::SUPPORTED =>
+                        {
+                            return Err(unsupported_type(&self.name, dt));
+                        }
+                        (TransportKind::$Kind, false) => {
+                            let v = ProduceTy::<$ArrTy>::produce(producer)?;
+                            ConsumeTy::<$ArrTy>::consume(consumer, dt, v)
                         }
-                    },
-                )*
-                _ => todo!("unimplemented transport of {:?}", dt),
+                        (TransportKind::$Kind, true) => {
+                            if let Some(v) = ProduceTy::<$ArrTy>::produce_opt(producer)? {
+                                ConsumeTy::<$ArrTy>::consume(consumer, dt, v)
+                            } else {
+                                ConsumeTy::<$ArrTy>::consume_null(consumer, dt)
+                            }
+                        }
+                    )*
+                }
+                Ok(())
             }
         }
     };
 }
 
+/// Moves values of a single field from producers into consumers.
+///
+/// Resolving the arrow type of the field is done once, in [Transporter::compile],
+/// instead of matching on [DataType] for every value.
+///
+/// Compile one transporter for each field of a schema and reuse them for all rows.
+#[derive(Debug, Clone)]
+pub struct Transporter {
+    name: String,
+    data_type: DataType,
+    nullable: bool,
+    kind: TransportKind,
+}
+
+impl Transporter {
+    pub fn compile(field: &Field) -> Self {
+        Transporter {
+            name: field.name().clone(),
+            data_type: field.data_type().clone(),
+            nullable: field.is_nullable(),
+            kind: TransportKind::of(field.data_type()),
+        }
+    }
+
+    /// Compiles transporters for all fields of a schema.
+    pub fn compile_all(fields: &Fields) -> Vec<Self> {
+        fields.iter().map(|f| Self::compile(f)).collect()
+    }
+}
+
+impl_transporter!(
+    { Boolean => Boolean, BooleanType }
+    { Int8 => Int8, Int8Type }
+    { Int16 => Int16, Int16Type }
+    { Int32 => Int32, Int32Type }
+    { Int64 => Int64, Int64Type }
+    { UInt8 => UInt8, UInt8Type }
+    { UInt16 => UInt16, UInt16Type }
+    { UInt32 => UInt32, UInt32Type }
+    { UInt64 => UInt64, UInt64Type }
+    { Float16 => Float16, Float16Type }
+    { Float32 => Float32, Float32Type }
+    { Float64 => Float64, Float64Type }
+    { Timestamp(TimeUnit::Second, _) => TimestampSecond, TimestampSecondType }
+    { Timestamp(TimeUnit::Millisecond, _) => TimestampMillisecond, TimestampMillisecondType }
+    { Timestamp(TimeUnit::Microsecond, _) => TimestampMicrosecond, TimestampMicrosecondType }
+    { Timestamp(TimeUnit::Nanosecond, _) => TimestampNanosecond, TimestampNanosecondType }
+    { Date32 => Date32, Date32Type }
+    { Date64 => Date64, Date64Type }
+    { Time32(TimeUnit::Second) => Time32Second, Time32SecondType }
+    { Time32(TimeUnit::Millisecond) => Time32Millisecond, Time32MillisecondType }
+    { Time64(TimeUnit::Microsecond) => Time64Microsecond, Time64MicrosecondType }
+    { Time64(TimeUnit::Nanosecond) => Time64Nanosecond, Time64NanosecondType }
+    { Interval(IntervalUnit::YearMonth) => IntervalYearMonth, IntervalYearMonthType }
+    { Interval(IntervalUnit::DayTime) => IntervalDayTime, IntervalDayTimeType }
+    { Interval(IntervalUnit::MonthDayNano) => IntervalMonthDayNano, IntervalMonthDayNanoType }
+    { Duration(TimeUnit::Second) => DurationSecond, DurationSecondType }
+    { Duration(TimeUnit::Millisecond) => DurationMillisecond, DurationMillisecondType }
+    { Duration(TimeUnit::Microsecond) => DurationMicrosecond, DurationMicrosecondType }
+    { Duration(TimeUnit::Nanosecond) => DurationNanosecond, DurationNanosecondType }
+    { Binary => Binary, BinaryType }
+    { LargeBinary => LargeBinary, LargeBinaryType }
+    { FixedSizeBinary(_) => FixedSizeBinary, FixedSizeBinaryType }
+    { Utf8 => Utf8, Utf8Type }
+    { LargeUtf8 => LargeUtf8, LargeUtf8Type }
+    { Decimal128(_, _) => Decimal128, Decimal128Type }
+    { Decimal256(_, _) => Decimal256, Decimal256Type }
+);
+
+/// Error for a column of a type that cannot be transported.
+pub(crate) fn unsupported_type(column_name: &str, data_type: &DataType) -> ConnectorError {
+    ConnectorError::IncompatibleSchema {
+        table_name: String::new(),
+        message: format!("column `{column_name}` has type {data_type}, which is not supported"),
+        hint: None,
+    }
+}
+
 /// Take a value of type `ty` from [Produce] and insert it into [Consume].
+///
+/// Resolves the type for each call. When transporting many values,
+/// use a [Transporter] instead.
 pub fn transport<'r, P: Produce<'r>, C: Consume>(
     field: &Field,
     producer: P,
@@ -41,52 +161,7 @@ pub fn transport<'r, P: Produce<'r>, C: Consume>(
 ) -> Result<(), ConnectorError> {
     log::debug!("transporting value of type {field:?}");
 
-    // TODO: connector-x goes a step further here: instead of having this match in the hot path,
-    // this function returns a transporter function that is stored in a vec, for faster lookup.
-
-    use DataType::*;
-    impl_transport_match!(
-        field,
-        consumer,
-        producer,
-        { Boolean => BooleanType }
-        { Int8 => Int8Type }
-        { Int16 => Int16Type }
-        { Int32 => Int32Type }
-        { Int64 => Int64Type }
-        { UInt8 => UInt8Type }
-        { UInt16 => UInt16Type }
-        { UInt32 => UInt32Type }
-        { UInt64 => UInt64Type }
-        { Float16 => Float16Type }
-        { Float32 => Float32Type }
-        { Float64 => Float64Type }
-        { Timestamp(TimeUnit::Second, _) => TimestampSecondType }
-        { Timestamp(TimeUnit::Millisecond, _) => TimestampMillisecondType }
-        { Timestamp(TimeUnit::Microsecond, _) => TimestampMicrosecondType }
-        { Timestamp(TimeUnit::Nanosecond, _) => TimestampNanosecondType }
-        { Date32 => Date32Type }
-        { Date64 => Date64Type }
-        { Time32(TimeUnit::Second) => Time32SecondType }
-        { Time32(TimeUnit::Millisecond) => Time32MillisecondType }
-        { Time64(TimeUnit::Microsecond) => Time64MicrosecondType }
-        { Time64(TimeUnit::Nanosecond) => Time64NanosecondType }
-        { Interval(IntervalUnit::YearMonth) => IntervalYearMonthType }
-        { Interval(IntervalUnit::DayTime) => IntervalDayTimeType }
-        { Interval(IntervalUnit::MonthDayNano) => IntervalMonthDayNanoType }
-        { Duration(TimeUnit::Second) => DurationSecondType }
-        { Duration(TimeUnit::Millisecond) => DurationMillisecondType }
-        { Duration(TimeUnit::Microsecond) => DurationMicrosecondType }
-        { Duration(TimeUnit::Nanosecond) => DurationNanosecondType }
-        { Binary => BinaryType }
-        { LargeBinary => LargeBinaryType }
-        { FixedSizeBinary(_) => FixedSizeBinaryType }
-        { Utf8 => Utf8Type }
-        { LargeUtf8 => LargeUtf8Type }
-        { Decimal128(_, _) => Decimal128Type }
-        { Decimal256(_, _) => Decimal256Type }
-    );
-    Ok(())
+    Transporter::compile(field).transport(producer, consumer)
 }
 
 /// Ability to produce values of all arrow types.
@@ -132,6 +207,9 @@ pub trait Produce<'r>:
 
 /// Ability to produce a value of an arrow type
 pub trait ProduceTy<'r, T: ArrowType> {
+    /// False for types that this producer cannot produce.
+    const SUPPORTED: bool = true;
+
     fn produce(self) -> Result<T::Native, ConnectorError>;
 
     fn produce_opt(self) -> Result<Option<T::Native>, ConnectorError>;
@@ -181,6 +259,10 @@ pub trait Consume:
 
 /// Ability to consume a value of an an arrow type
 pub trait ConsumeTy<T: ArrowType> {
+    /// False for types that this consumer cannot store.
+    /// Values of such types are never passed to the consumer.
+    const SUPPORTED: bool = true;
+
     fn consume(&mut self, ty: &DataType, value: T::Native);
 
     fn consume_null(&mut self, ty: &DataType);
@@ -212,11 +294,17 @@ macro_rules! impl_produce_unsupported {
     ($p: ty, ($($t: ty,)+)) => {
         $(
             impl<'r> $crate::util::transport::ProduceTy<'r, $t> for $p {
+                const SUPPORTED: bool = false;
+
                 fn produce(self) -> Result<<$t as $crate::types::ArrowType>::Native, ConnectorError> {
-                   unimplemented!("unsupported");
+                    Err(ConnectorError::IncompatibleSchema {
+                        table_name: String::new(),
+                        message: format!("reading values of {} is not supported", stringify!($t)),
+                        hint: None,
+                    })
                 }
                 fn produce_opt(self) -> Result<Option<<$t as $crate::types::ArrowType>::Native>, ConnectorError> {
-                   unimplemented!("unsupported");
+                    $crate::util::transport::ProduceTy::<$t>::produce(self).map(Some)
                 }
             }
         )+
@@ -228,11 +316,13 @@ macro_rules! impl_consume_unsupported {
     ($c: ty, ($($t: ty,)+)) => {
         $(
             impl $crate::util::transport::ConsumeTy<$t> for $c {
+                const SUPPORTED: bool = false;
+
                 fn consume(&mut self, _ty: &arrow::datatypes::DataType, _val: <$t as $crate::types::ArrowType>::Native) {
-                    unimplemented!("unsupported");
+                    unreachable!("unsupported");
                 }
                 fn consume_null(&mut self, _ty: &DataType) {
-                    unimplemented!("unsupported");
+                    unreachable!("unsupported");
                 }
             }
         )+
@@ -0,0 +1,202 @@
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+use arrow::datatypes::{Field, Schema, SchemaRef};
+
+use crate::api::{Connector, TypeMapper};
+#[cfg(any(
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+use crate::ConnectorError;
+
+/// A [TypeMapper] that is configured with a list of rules.
+///
+/// Rules are consulted in order of insertion and first matching rule wins.
+///
+/// ```
+/// use connector_arrow::arrow::datatypes::{DataType, TimeUnit};
+/// use connector_arrow::util::TypeRules;
+///
+/// let rules = TypeRules::new()
+///     .db_type("text", DataType::LargeUtf8)
+///     .column("id", DataType::Int32, "INTEGER")
+///     .arrow_type(DataType::Timestamp(TimeUnit::Microsecond, None), "timestamptz");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TypeRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    DbType {
+        db_ty: String,
+        ty: DataType,
+    },
+    Column {
+        name: String,
+        ty: DataType,
+        db_ty: String,
+    },
+    ArrowType {
+        ty: DataType,
+        db_ty: String,
+    },
+}
+
+impl TypeRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Columns of database type `db_ty` will be read as `ty`.
+    /// Database type names are compared case-insensitively.
+    pub fn db_type(mut self, db_ty: &str, ty: DataType) -> Self {
+        self.rules.push(Rule::DbType {
+            db_ty: db_ty.to_string(),
+            ty,
+        });
+        self
+    }
+
+    /// Column named `name` will be read as `ty` and created with database type `db_ty`.
+    pub fn column(mut self, name: &str, ty: DataType, db_ty: &str) -> Self {
+        self.rules.push(Rule::Column {
+            name: name.to_string(),
+            ty,
+            db_ty: db_ty.to_string(),
+        });
+        self
+    }
+
+    /// Columns of arrow type `ty` will be created with database type `db_ty`.
+    pub fn arrow_type(mut self, ty: DataType, db_ty: &str) -> Self {
+        self.rules.push(Rule::ArrowType {
+            ty,
+            db_ty: db_ty.to_string(),
+        });
+        self
+    }
+}
+
+impl TypeMapper for TypeRules {
+    fn db_into_arrow(&self, column_name: &str, db_ty: &str) -> Option<DataType> {
+        self.rules.iter().find_map(|rule| match rule {
+            Rule::DbType { db_ty: r, ty } if r.eq_ignore_ascii_case(db_ty) => Some(ty.clone()),
+            Rule::Column { name, ty, .. } if name == column_name => Some(ty.clone()),
+            _ => None,
+        })
+    }
+
+    fn arrow_into_db(&self, column_name: &str, ty: &DataType) -> Option<String> {
+        self.rules.iter().find_map(|rule| match rule {
+            Rule::Column { name, db_ty, .. } if name == column_name => Some(db_ty.clone()),
+            Rule::ArrowType { ty: r, db_ty } if r == ty => Some(db_ty.clone()),
+            _ => None,
+        })
+    }
+}
+
+/// Applies the mapper to a field that was created from a column of database type `db_ty`.
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) fn map_field(mapper: Option<&dyn TypeMapper>, field: Field, db_ty: &str) -> Field {
+    match mapper.and_then(|m| m.db_into_arrow(field.name(), db_ty)) {
+        Some(ty) => field.with_data_type(ty),
+        None => field,
+    }
+}
+
+/// Applies the mapper to a schema whose fields were created from columns of `db_types`.
+/// Returns None when no field was changed.
+#[cfg(any(
+    feature = "src_sqlite",
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) fn map_schema<'a>(
+    mapper: Option<&dyn TypeMapper>,
+    schema: &Schema,
+    db_types: impl IntoIterator<Item = &'a str>,
+) -> Option<SchemaRef> {
+    let mapper = mapper?;
+
+    let mut changed = false;
+    let fields: Vec<_> = (schema.fields().iter().zip(db_types))
+        .map(|(field, db_ty)| {
+            let mapped = map_field(Some(mapper), field.as_ref().clone(), db_ty);
+            changed |= mapped.data_type() != field.data_type();
+            mapped
+        })
+        .collect();
+
+    changed.then(|| Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())))
+}
+
+/// Database type of the column of arrow type `ty`, consulting the mapper first.
+pub(crate) fn arrow_into_db<C: Connector>(
+    mapper: Option<&dyn TypeMapper>,
+    column_name: &str,
+    ty: &DataType,
+) -> Option<String> {
+    mapper
+        .and_then(|m| m.arrow_into_db(column_name, ty))
+        .or_else(|| C::type_arrow_into_db(ty))
+}
+
+/// Error for a column of arrow type `ty`, which cannot be stored in database `db_name`.
+#[cfg(any(
+    feature = "src_duckdb",
+    feature = "src_postgres",
+    feature = "src_mysql",
+    feature = "src_tiberius"
+))]
+pub(crate) fn unsupported_column_type(
+    db_name: &str,
+    table_name: &str,
+    column_name: &str,
+    ty: &DataType,
+) -> ConnectorError {
+    ConnectorError::IncompatibleSchema {
+        table_name: table_name.to_string(),
+        message: format!(
+            "column `{column_name}` has type {ty}, which cannot be stored in {db_name}"
+        ),
+        hint: Some("cast the column to a supported type or map it with a TypeMapper".to_string()),
+    }
+}
+
+/// Arrow type of the column of database type `db_ty`, consulting the mapper first.
+pub(crate) fn db_into_arrow<C: Connector>(
+    mapper: Option<&dyn TypeMapper>,
+    column_name: &str,
+    db_ty: &str,
+) -> Option<DataType> {
+    mapper
+        .and_then(|m| m.db_into_arrow(column_name, db_ty))
+        .or_else(|| C::type_db_into_arrow(db_ty))
+}
@@ -1,4 +1,5 @@
 use arrow::array::*;
+use arrow::buffer::{OffsetBuffer, ScalarBuffer};
 use arrow::datatypes::*;
 use half::f16;
 use rand::Rng;
@@ -298,26 +299,24 @@ fn generate_array<R: Rng>(data_type: &DataType, values: &[ValuesSpec], rng: &mut
             ]
         }
         DataType::Interval(IntervalUnit::MonthDayNano) => {
-            let array = gen_array![
+            gen_array![
                 values,
-                Decimal128Builder::with_capacity(capacity),
-                i128::MIN,
-                i128::MAX,
-                0,
-                rng.gen_range(i128::MIN..=i128::MAX)
-            ];
-            arrow::compute::cast(&array, data_type).unwrap()
+                IntervalMonthDayNanoBuilder::with_capacity(capacity),
+                IntervalMonthDayNano::MIN,
+                IntervalMonthDayNano::MAX,
+                IntervalMonthDayNano::ZERO,
+                IntervalMonthDayNano::new(rng.gen(), rng.gen(), rng.gen())
+            ]
         }
         DataType::Interval(IntervalUnit::DayTime) => {
-            let array = gen_array![
+            gen_array![
                 values,
-                Int64Builder::with_capacity(capacity),
-                i64::MIN,
-                i64::MAX,
-                0,
-                rng.gen_range(i64::MIN..=i64::MAX)
-            ];
-            arrow::compute::cast(&array, data_type).unwrap()
+                IntervalDayTimeBuilder::with_capacity(capacity),
+                IntervalDayTime::MIN,
+                IntervalDayTime::MAX,
+                IntervalDayTime::ZERO,
+                IntervalDayTime::new(rng.gen(), rng.gen())
+            ]
         }
         DataType::Binary => {
             gen_array![
@@ -369,12 +368,50 @@ fn generate_array<R: Rng>(data_type: &DataType, values: &[ValuesSpec], rng: &mut
                 gen_string(0..128, rng)
             ]
         }
-        DataType::List(_) => todo!(),
-        DataType::FixedSizeList(_, _) => todo!(),
-        DataType::LargeList(_) => todo!(),
-        DataType::Struct(_) => todo!(),
-        DataType::Union(_, _) => todo!(),
-        DataType::Dictionary(_, _) => todo!(),
+        // nested values are generated with the same process as the list, one item per list
+        DataType::List(field) => {
+            let items = generate_array(field.data_type(), values, rng);
+            let offsets = OffsetBuffer::from_lengths(vec![1; capacity]);
+            Arc::new(ListArray::new(field.clone(), offsets, items, None))
+        }
+        DataType::FixedSizeList(field, size) => {
+            let values = repeat_values(values, *size as usize);
+            let items = generate_array(field.data_type(), &values, rng);
+            Arc::new(FixedSizeListArray::new(field.clone(), *size, items, None))
+        }
+        DataType::LargeList(field) => {
+            let items = generate_array(field.data_type(), values, rng);
+            let offsets = OffsetBuffer::from_lengths(vec![1; capacity]);
+            Arc::new(LargeListArray::new(field.clone(), offsets, items, None))
+        }
+        DataType::Struct(fields) => {
+            let columns = (fields.iter())
+                .map(|f| generate_array(f.data_type(), values, rng))
+                .collect();
+            Arc::new(StructArray::new(fields.clone(), columns, None))
+        }
+        // all values are of the first variant
+        DataType::Union(fields, mode) => {
+            let mut variants = fields.iter();
+            let (type_id, first) = variants.next().unwrap();
+            let type_ids = ScalarBuffer::from(vec![type_id; capacity]);
+            let mut children = vec![generate_array(first.data_type(), values, rng)];
+            let offsets = match mode {
+                UnionMode::Sparse => {
+                    children.extend(variants.map(|(_, f)| new_null_array(f.data_type(), capacity)));
+                    None
+                }
+                UnionMode::Dense => {
+                    children.extend(variants.map(|(_, f)| new_empty_array(f.data_type())));
+                    Some((0..capacity as i32).collect())
+                }
+            };
+            Arc::new(UnionArray::try_new(fields.clone(), type_ids, offsets, children).unwrap())
+        }
+        DataType::Dictionary(_, value_type) => {
+            let array = generate_array(value_type, values, rng);
+            arrow::compute::cast(&array, data_type).unwrap()
+        }
         DataType::Decimal128(precision, _) => {
             let max = if *precision == 38 {
                 999_99999_99999_99999_99999_99999_99999_99999i128
@@ -424,15 +461,86 @@ fn generate_array<R: Rng>(data_type: &DataType, values: &[ValuesSpec], rng: &mut
                 )
             ]
         }
-        DataType::Map(_, _) => todo!(),
-        DataType::RunEndEncoded(_, _) => todo!(),
-        DataType::BinaryView => todo!(),
-        DataType::Utf8View => todo!(),
+        DataType::Map(field, sorted) => {
+            let DataType::Struct(fields) = field.data_type() else {
+                unreachable!()
+            };
+            // keys cannot be null
+            let key_values = (values.iter())
+                .map(|v| ValuesSpec {
+                    gen_process: match v.gen_process {
+                        ValueGenProcess::Null => ValueGenProcess::Unit,
+                        p => p,
+                    },
+                    repeat: v.repeat,
+                })
+                .collect::<Vec<_>>();
+            let keys = generate_array(fields[0].data_type(), &key_values, rng);
+            let items = generate_array(fields[1].data_type(), values, rng);
+            let entries = StructArray::new(fields.clone(), vec![keys, items], None);
+            let offsets = OffsetBuffer::from_lengths(vec![1; capacity]);
+            Arc::new(MapArray::new(
+                field.clone(),
+                offsets,
+                entries,
+                None,
+                *sorted,
+            ))
+        }
+        // each value is its own run
+        DataType::RunEndEncoded(run_ends, field) => {
+            let items = generate_array(field.data_type(), values, rng);
+            match run_ends.data_type() {
+                DataType::Int16 => {
+                    let run_ends = Int16Array::from_iter_values(1..=capacity as i16);
+                    Arc::new(RunArray::try_new(&run_ends, &items).unwrap())
+                }
+                DataType::Int32 => {
+                    let run_ends = Int32Array::from_iter_values(1..=capacity as i32);
+                    Arc::new(RunArray::try_new(&run_ends, &items).unwrap())
+                }
+                DataType::Int64 => {
+                    let run_ends = Int64Array::from_iter_values(1..=capacity as i64);
+                    Arc::new(RunArray::try_new(&run_ends, &items).unwrap())
+                }
+                _ => unreachable!(),
+            }
+        }
+        DataType::BinaryView => {
+            gen_array![
+                values,
+                BinaryViewBuilder::with_capacity(capacity),
+                [],
+                [255, 0, 128, 127, 0, 0, 255, 0],
+                [0, 0, 0, 0],
+                gen_binary(0..32, rng)
+            ]
+        }
+        DataType::Utf8View => {
+            gen_array![
+                values,
+                StringViewBuilder::with_capacity(capacity),
+                "what's \"low\" in a string?",
+                "let's say that special characters are high: !@#$%^&*()_\\'",
+                "",
+                gen_string(0..32, rng)
+            ]
+        }
         DataType::ListView(_) => todo!(),
         DataType::LargeListView(_) => todo!(),
     }
 }
 
+/// Repeats each of the values `n` times.
+fn repeat_values(values: &[ValuesSpec], n: usize) -> Vec<ValuesSpec> {
+    (values.iter())
+        .map(|v| ValuesSpec {
+            gen_process: v.gen_process,
+            repeat: v.repeat * n,
+        })
+        .collect()
+}
+
 fn gen_binary_of_size<R: Rng>(size: usize, rng: &mut R) -> Vec<u8> {
     let mut buf = Vec::<u8>::with_capacity(size);
     for _ in 0..size {
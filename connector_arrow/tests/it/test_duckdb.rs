@@ -62,6 +62,22 @@ fn schema_edit() {
     super::tests::schema_edit(&mut conn, table_name, spec::basic_types());
 }
 
+#[test]
+fn schema_alter() {
+    let table_name = "schema_alter";
+
+    let mut conn = init();
+    super::tests::schema_alter(&mut conn, table_name, '"', true);
+}
+
+#[test]
+fn plan_migration() {
+    let table_name = "plan_migration";
+
+    let mut conn = init();
+    super::tests::plan_migration(&mut conn, table_name);
+}
+
 #[test]
 fn ident_escaping() {
     let table_name = "simple::ident_escaping";
@@ -69,3 +85,185 @@ fn ident_escaping() {
     let mut conn = init();
     super::tests::ident_escaping(&mut conn, table_name);
 }
+
+#[test]
+fn relation_list() {
+    let table_name = "relation_list";
+
+    let mut conn = init();
+    super::tests::relation_list(&mut conn, table_name, '"');
+}
+
+#[test]
+fn table_create_with() {
+    let table_name = "table_create_with";
+
+    let mut conn = init();
+    super::tests::table_create_with(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_modes() {
+    let table_name = "append_modes";
+
+    let mut conn = init();
+    super::tests::append_modes(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_generated_keys() {
+    let table_name = "append_generated_keys";
+
+    let mut conn = init();
+    super::tests::append_generated_keys(
+        &mut conn,
+        table_name,
+        &[
+            "CREATE OR REPLACE SEQUENCE append_generated_keys_id",
+            "CREATE TABLE append_generated_keys (id BIGINT DEFAULT nextval('append_generated_keys_id'), a BIGINT)",
+        ],
+    );
+}
+
+#[test]
+fn append_reject() {
+    let table_name = "append_reject";
+
+    let mut conn = init();
+    super::tests::append_reject(&mut conn, table_name, '"', false, false);
+}
+
+#[test]
+fn copy() {
+    let table_name = "copy";
+
+    let mut src = init();
+    let mut dst = init();
+    super::tests::copy(&mut src, &mut dst, table_name, '"');
+}
+
+#[test]
+fn append_by_name() {
+    let table_name = "append_by_name";
+
+    let mut conn = init();
+    super::tests::append_by_name(&mut conn, table_name, '"');
+}
+
+#[test]
+fn check_append() {
+    let table_name = "check_append";
+
+    let mut conn = init();
+    super::tests::check_append(&mut conn, table_name);
+}
+
+#[test]
+fn check_append_generated() {
+    let table_name = "check_append_generated";
+
+    let mut conn = init();
+    let seq = format!("{table_name}_seq");
+    crate::util::execute(&mut conn, &format!("CREATE SEQUENCE {seq}")).unwrap();
+    let identity_column = format!("\"id\" BIGINT NOT NULL DEFAULT nextval('{seq}')");
+    super::tests::check_append_generated(&mut conn, table_name, &identity_column, '"');
+}
+
+#[test]
+fn data_type_matrix() {
+    let table_name = "data_type_matrix";
+
+    let mut conn = init();
+    super::tests::data_type_matrix(&mut conn, table_name, '"');
+}
+
+#[test]
+fn error_kind() {
+    let mut conn = init();
+    super::tests::error_kind(&mut conn, "error_kind", '"');
+}
+
+#[test]
+fn table_create_temporary() {
+    let table_name = "table_create_temporary";
+
+    let mut conn = init();
+    super::tests::table_create_temporary(&mut conn, table_name, '"');
+}
+
+#[test]
+fn start_with_schema() {
+    let mut conn = init();
+    super::tests::start_with_schema(&mut conn);
+}
+
+#[test]
+fn cancel() {
+    use connector_arrow::api::Connector;
+
+    let conn = init();
+    let err = conn.cancel_handle().unwrap_err();
+    assert!(err.to_string().contains("cancellation"), "{}", err);
+}
+
+#[test]
+fn partitioned_append() {
+    use std::sync::Mutex;
+
+    let db = Mutex::new(duckdb::Connection::open_in_memory().unwrap());
+    let conn = db.lock().unwrap().try_clone().unwrap();
+    let mut conn = connector_arrow::duckdb::DuckDBConnection::new(conn);
+    let open = move || {
+        let conn = db.lock().unwrap().try_clone()?;
+        Ok(connector_arrow::duckdb::DuckDBConnection::new(conn))
+    };
+    super::tests::partitioned_append(&mut conn, open, "partitioned_append");
+}
+
+#[test]
+fn partitioned_query() {
+    use std::sync::Mutex;
+
+    // in-memory databases are shared between clones of a connection
+    let db = Mutex::new(duckdb::Connection::open_in_memory().unwrap());
+    let mut conn =
+        connector_arrow::duckdb::DuckDBConnection::new(db.lock().unwrap().try_clone().unwrap());
+    let open = move || {
+        let conn = db.lock().unwrap().try_clone()?;
+        Ok(connector_arrow::duckdb::DuckDBConnection::new(conn))
+    };
+    super::tests::partitioned_query(&mut conn, open, "partitioned_query");
+}
+
+#[test]
+fn dyn_connector() {
+    let mut conn: Box<dyn connector_arrow::dynamic::DynConnector> = Box::new(init());
+    super::tests::dyn_connector(conn.as_mut(), "dyn_connector");
+}
+
+#[test]
+fn type_mapper() {
+    use arrow::datatypes::DataType;
+    use connector_arrow::api::SchemaGet;
+    use connector_arrow::util::TypeRules;
+
+    let rules = TypeRules::new()
+        .db_type("VARCHAR", DataType::LargeUtf8)
+        .column("id", DataType::Int64, "INTEGER");
+    let mut conn = init().with_type_mapper(rules);
+
+    let statements = [
+        "CREATE TABLE type_mapper (id INTEGER, name VARCHAR)",
+        "INSERT INTO type_mapper VALUES (1, 'a')",
+    ];
+    for statement in statements {
+        crate::util::execute(&mut conn, statement).unwrap();
+    }
+
+    let schema = conn.table_get("type_mapper").unwrap();
+    assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+    assert_eq!(schema.field(1).data_type(), &DataType::LargeUtf8);
+
+    let batches = connector_arrow::query(&mut conn, "SELECT id, name FROM type_mapper").unwrap();
+    assert_eq!(batches[0].schema(), schema);
+}
@@ -11,6 +11,16 @@ fn init() -> MySQLConnection<mysql::Conn> {
     MySQLConnection::new(conn)
 }
 
+#[test]
+fn cancel() {
+    let url = std::env::var("MYSQL_URL").unwrap();
+    let opts = mysql::Opts::from_url(&url).unwrap();
+    let mut conn = init().with_cancel_opts(opts).unwrap();
+
+    // SLEEP returns 1 when it is killed, so only duration is checked
+    super::tests::cancel(&mut conn, "SELECT SLEEP(10)").unwrap();
+}
+
 #[test]
 fn query_01() {
     let mut conn = init();
@@ -35,6 +45,22 @@ fn schema_edit() {
     super::tests::schema_edit(&mut conn, table_name, column_spec);
 }
 
+#[test]
+fn schema_alter() {
+    let table_name = "schema_alter";
+
+    let mut conn = init();
+    super::tests::schema_alter(&mut conn, table_name, '`', true);
+}
+
+#[test]
+fn plan_migration() {
+    let table_name = "plan_migration";
+
+    let mut conn = init();
+    super::tests::plan_migration(&mut conn, table_name);
+}
+
 #[test]
 fn ident_escaping() {
     // https://github.com/blackbeam/rust_mysql_common/issues/129
@@ -44,6 +70,119 @@ fn ident_escaping() {
     super::tests::ident_escaping(&mut conn, table_name);
 }
 
+#[test]
+fn relation_list() {
+    let table_name = "relation_list";
+
+    let mut conn = init();
+    super::tests::relation_list(&mut conn, table_name, '`');
+}
+
+#[test]
+fn table_create_with() {
+    let table_name = "table_create_with";
+
+    let mut conn = init();
+    super::tests::table_create_with(&mut conn, table_name, '`');
+}
+
+#[test]
+fn append_modes() {
+    let table_name = "append_modes";
+
+    let mut conn = init();
+    super::tests::append_modes(&mut conn, table_name, '`');
+}
+
+#[test]
+fn append_generated_keys() {
+    let table_name = "append_generated_keys";
+
+    let mut conn = init();
+    super::tests::append_generated_keys(
+        &mut conn,
+        table_name,
+        &["CREATE TABLE append_generated_keys (id BIGINT AUTO_INCREMENT PRIMARY KEY, a BIGINT)"],
+    );
+}
+
+#[test]
+fn append_reject() {
+    let table_name = "append_reject";
+
+    let mut conn = init();
+    super::tests::append_reject(&mut conn, table_name, '`', true, false);
+}
+
+#[test]
+fn copy() {
+    let table_name = "copy";
+
+    let mut src = init();
+    let mut dst = init();
+    super::tests::copy(&mut src, &mut dst, table_name, '`');
+}
+
+#[test]
+fn partitioned_append() {
+    // name that must be quoted, which MySQL does with backticks
+    let table_name = "partitioned Append";
+
+    let mut conn = init();
+    super::tests::partitioned_append(&mut conn, || Ok(init()), table_name);
+}
+
+#[test]
+fn append_by_name() {
+    let table_name = "append_by_name";
+
+    let mut conn = init();
+    super::tests::append_by_name(&mut conn, table_name, '`');
+}
+
+#[test]
+fn check_append() {
+    let table_name = "check_append";
+
+    let mut conn = init();
+    super::tests::check_append(&mut conn, table_name);
+}
+
+#[test]
+fn check_append_generated() {
+    let table_name = "check_append_generated";
+
+    let mut conn = init();
+    let identity_column = "`id` BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY";
+    super::tests::check_append_generated(&mut conn, table_name, identity_column, '`');
+}
+
+#[test]
+fn data_type_matrix() {
+    let table_name = "data_type_matrix";
+
+    let mut conn = init();
+    super::tests::data_type_matrix(&mut conn, table_name, '`');
+}
+
+#[test]
+fn error_kind() {
+    let mut conn = init();
+    super::tests::error_kind(&mut conn, "error_kind", '`');
+
+    let err = connector_arrow::query(&mut conn, "SELEC 1").unwrap_err();
+    assert_eq!(err.sql_state(), Some("42000"));
+    assert_eq!(err.vendor_code(), Some(1064));
+}
+
+#[test]
+fn table_create_temporary() {
+    let table_name = "table_create_temporary";
+
+    let mut conn = init();
+    super::tests::table_create_temporary(&mut conn, table_name, '`');
+}
+
 #[rstest]
 #[case::empty("roundtrip__empty", spec::empty())]
 #[case::null_bool("roundtrip__null_bool", spec::null_bool())]
@@ -106,3 +245,35 @@ mod literals_cases {
         ]
     }
 }
+
+#[test]
+#[cfg(feature = "tls-rustls")]
+fn connect_tls() {
+    use connector_arrow::api::Statement;
+    use connector_arrow::tls::TlsConfig;
+
+    // server with a certificate signed by the CA from dbs/certs, which requires TLS
+    let url = std::env::var("MYSQL_TLS_URL").unwrap();
+
+    let trusted = format!("{url}?sslmode=require&sslrootcert=../dbs/certs/ca.crt");
+    let mut conn = connector_arrow::connect(&trusted).unwrap();
+    let mut stmt = conn.query("SELECT 1").unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    drop(stmt);
+
+    // unencrypted connections are rejected
+    connector_arrow::connect(&url).err().unwrap();
+
+    // certificate is not trusted without the CA
+    let untrusted = format!("{url}?sslmode=require");
+    connector_arrow::connect(&untrusted).err().unwrap();
+
+    // ... unless verification is disabled
+    let tls = TlsConfig::new().accept_invalid_certs(true);
+    connector_arrow::connect_with_tls(&untrusted, &tls).unwrap();
+}
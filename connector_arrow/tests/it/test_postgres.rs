@@ -1,4 +1,5 @@
-use connector_arrow::postgres::PostgresConnection;
+use connector_arrow::api::SchemaEdit;
+use connector_arrow::postgres::{CopyFormat, CopySource, PostgresConnection};
 use rstest::rstest;
 
 use crate::{spec, util::QueryOfSingleLiteral};
@@ -47,6 +48,22 @@ fn schema_edit() {
     super::tests::schema_edit(&mut conn, table_name, column_spec);
 }
 
+#[test]
+fn schema_alter() {
+    let table_name = "schema_alter";
+
+    let mut conn = init();
+    super::tests::schema_alter(&mut conn, table_name, '"', true);
+}
+
+#[test]
+fn plan_migration() {
+    let table_name = "plan_migration";
+
+    let mut conn = init();
+    super::tests::plan_migration(&mut conn, table_name);
+}
+
 #[test]
 fn ident_escaping() {
     let table_name = "ident_escaping";
@@ -55,6 +72,458 @@ fn ident_escaping() {
     super::tests::ident_escaping(&mut conn, table_name);
 }
 
+#[test]
+fn relation_list() {
+    let table_name = "relation_list";
+
+    let mut conn = init();
+    super::tests::relation_list(&mut conn, table_name, '"');
+}
+
+#[test]
+fn table_create_with() {
+    let table_name = "table_create_with";
+
+    let mut conn = init();
+    super::tests::table_create_with(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_modes() {
+    let table_name = "append_modes";
+
+    let mut conn = init();
+    super::tests::append_modes(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_in_transaction() {
+    use std::sync::Arc;
+
+    use arrow::array::{Int64Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use connector_arrow::api::{Append, Connector};
+
+    let table_name = "append_in_transaction";
+    let mut conn = init();
+    let _ = conn.table_drop(table_name);
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    conn.table_create(table_name, schema.clone()).unwrap();
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2]))]);
+    let batch = batch.unwrap();
+    let count = |conn: &mut PostgresConnection| {
+        let query = format!("SELECT COUNT(*) FROM {table_name}");
+        let batches = connector_arrow::query(conn, &query).unwrap();
+        let count = batches[0].column(0).as_any().downcast_ref::<Int64Array>();
+        count.unwrap().value(0)
+    };
+
+    // finished append does not commit the transaction of the caller
+    crate::util::execute(&mut conn, "BEGIN").unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch.clone()).unwrap();
+    appender.finish().unwrap();
+    assert_eq!(count(&mut conn), 2);
+    crate::util::execute(&mut conn, "ROLLBACK").unwrap();
+    assert_eq!(count(&mut conn), 0);
+
+    // dropped append rolls back only its own rows
+    crate::util::execute(&mut conn, "BEGIN").unwrap();
+    let insert = format!("INSERT INTO {table_name} VALUES (3)");
+    crate::util::execute(&mut conn, &insert).unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch).unwrap();
+    drop(appender);
+    crate::util::execute(&mut conn, "COMMIT").unwrap();
+    assert_eq!(count(&mut conn), 1);
+
+    conn.table_drop(table_name).unwrap();
+}
+
+#[test]
+fn append_generated_keys() {
+    let table_name = "append_generated_keys";
+
+    let mut conn = init();
+    super::tests::append_generated_keys(
+        &mut conn,
+        table_name,
+        &["CREATE TABLE append_generated_keys (id SERIAL PRIMARY KEY, a BIGINT)"],
+    );
+}
+
+#[test]
+fn append_reject() {
+    let table_name = "append_reject";
+
+    let mut conn = init();
+    super::tests::append_reject(&mut conn, table_name, '"', true, true);
+
+    // rows are located from the context of text COPY errors as well
+    let mut conn = init().with_copy_format(CopyFormat::Text);
+    super::tests::append_reject(&mut conn, table_name, '"', true, true);
+}
+
+#[test]
+fn copy() {
+    let table_name = "copy";
+
+    let mut src = init();
+    let mut dst = init();
+    super::tests::copy(&mut src, &mut dst, table_name, '"');
+}
+
+#[test]
+fn append_by_name() {
+    let table_name = "append_by_name";
+
+    let mut conn = init();
+    super::tests::append_by_name(&mut conn, table_name, '"');
+}
+
+#[test]
+fn check_append() {
+    let table_name = "check_append";
+
+    let mut conn = init();
+    super::tests::check_append(&mut conn, table_name);
+}
+
+#[test]
+fn check_append_generated() {
+    let table_name = "check_append_generated";
+
+    let mut conn = init();
+    let identity_column = "\"id\" SERIAL";
+    super::tests::check_append_generated(&mut conn, table_name, identity_column, '"');
+}
+
+#[test]
+fn data_type_matrix() {
+    let table_name = "data_type_matrix";
+
+    let mut conn = init();
+    super::tests::data_type_matrix(&mut conn, table_name, '"');
+}
+
+#[test]
+fn error_kind() {
+    let mut conn = init();
+    super::tests::error_kind(&mut conn, "error_kind", '"');
+
+    let err = connector_arrow::query(&mut conn, "SELEC 1").unwrap_err();
+    assert_eq!(err.sql_state(), Some("42601"));
+    assert_eq!(err.vendor_code(), None);
+}
+
+#[test]
+fn table_create_temporary() {
+    let table_name = "table_create_temporary";
+
+    let mut conn = init();
+    super::tests::table_create_temporary(&mut conn, table_name, '"');
+}
+
+#[test]
+fn relation_list_kinds() {
+    use connector_arrow::api::{Relation, RelationKind, SchemaGet};
+
+    let mut conn = init();
+    let statements = [
+        "DROP MATERIALIZED VIEW IF EXISTS relation_kinds_mat",
+        "DROP TABLE IF EXISTS relation_kinds_part",
+        "CREATE TABLE relation_kinds_part (a INT) PARTITION BY RANGE (a)",
+        "CREATE MATERIALIZED VIEW relation_kinds_mat AS SELECT 1::INT AS a",
+        "CREATE TEMPORARY TABLE relation_kinds_temp (a INT)",
+    ];
+    for statement in statements {
+        crate::util::execute(&mut conn, statement).unwrap();
+    }
+
+    let relations = conn.relation_list().unwrap();
+    let expected = [
+        ("relation_kinds_part", RelationKind::Partitioned),
+        ("relation_kinds_mat", RelationKind::MaterializedView),
+        ("relation_kinds_temp", RelationKind::Temporary),
+    ];
+    for (name, kind) in expected {
+        let relation = Relation {
+            name: name.to_string(),
+            kind,
+        };
+        assert!(relations.contains(&relation), "missing {:?}", relation);
+
+        let schema = conn.table_get(name).unwrap();
+        assert_eq!(schema.field(0).name(), "a");
+    }
+}
+
+#[test]
+fn start_with_schema() {
+    let mut conn = init();
+    super::tests::start_with_schema(&mut conn);
+}
+
+#[test]
+fn dyn_connector() {
+    let mut conn: Box<dyn connector_arrow::dynamic::DynConnector> = Box::new(init());
+    super::tests::dyn_connector(conn.as_mut(), "dyn_connector");
+}
+
+#[test]
+fn cancel() {
+    let mut conn = init();
+    let err = super::tests::cancel(&mut conn, "SELECT pg_sleep(10)").unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("canceling statement"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn retry() {
+    use std::cell::Cell;
+
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
+    use connector_arrow::util::retry::{RetryPolicy, RetryingConnector};
+
+    let opened = Cell::new(0);
+    let open = || {
+        opened.set(opened.get() + 1);
+        Ok(init())
+    };
+    let mut conn = RetryingConnector::new(open, RetryPolicy::new());
+    let batches = conn.query("SELECT pg_backend_pid()").unwrap();
+    let pid = batches[0].column(0).as_primitive::<Int32Type>().value(0);
+
+    // terminate the connection from another one
+    let query = format!("SELECT pg_terminate_backend({pid})");
+    connector_arrow::query(&mut init(), &query).unwrap();
+
+    // the query is retried on a new connection
+    let batches = conn.query("SELECT 1").unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    drop(conn);
+    assert_eq!(opened.get(), 2);
+}
+
+#[test]
+fn partitioned_append() {
+    let mut conn = init();
+    super::tests::partitioned_append(&mut conn, || Ok(init()), "partitioned Append");
+}
+
+#[test]
+fn partitioned_query() {
+    let mut conn = init();
+    super::tests::partitioned_query(&mut conn, || Ok(init()), "partitioned_query");
+}
+
+#[test]
+fn connect() {
+    use arrow::array::AsArray;
+    use connector_arrow::api::Statement;
+
+    let url = std::env::var("POSTGRES_URL").unwrap();
+    let url = format!("{url}?application_name=connector%20test&timezone=Europe/Ljubljana");
+    let mut conn = connector_arrow::connect(&url).unwrap();
+    super::tests::dyn_connector(conn.as_mut(), "connect");
+
+    let query = "SELECT current_setting('application_name'), current_setting('TimeZone')";
+    let mut stmt = conn.query(query).unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        batches[0].column(0).as_string::<i32>().value(0),
+        "connector test"
+    );
+    assert_eq!(
+        batches[0].column(1).as_string::<i32>().value(0),
+        "Europe/Ljubljana"
+    );
+    drop(stmt);
+
+    // TLS cannot be negotiated without the feature
+    #[cfg(not(feature = "tls-rustls"))]
+    {
+        let url = std::env::var("POSTGRES_URL").unwrap();
+        let err = connector_arrow::connect(&format!("{url}?sslmode=prefer"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("tls-rustls"), "{}", err);
+    }
+}
+
+#[test]
+#[cfg(feature = "tls-rustls")]
+fn connect_tls() {
+    use arrow::array::AsArray;
+    use connector_arrow::api::Statement;
+    use connector_arrow::tls::TlsConfig;
+
+    // server with a certificate signed by the CA from dbs/certs
+    let url = std::env::var("POSTGRES_TLS_URL").unwrap();
+
+    let trusted = format!("{url}?sslmode=require&sslrootcert=../dbs/certs/ca.crt");
+    let mut conn = connector_arrow::connect(&trusted).unwrap();
+    let query = "SELECT ssl FROM pg_stat_ssl WHERE pid = pg_backend_pid()";
+    let mut stmt = conn.query(query).unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(batches[0].column(0).as_boolean().value(0));
+    drop(stmt);
+
+    // cancel requests are sent over TLS as well
+    let cancel = conn.cancel_handle().unwrap();
+    let mut stmt = conn.query("SELECT pg_sleep(10)").unwrap();
+    let res = {
+        let _timeout = cancel.timeout(std::time::Duration::from_millis(200));
+        stmt.start([])
+            .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+    };
+    let err = res.unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("canceling statement"),
+        "{:?}",
+        err
+    );
+    drop(stmt);
+
+    // TLS is used when preferred ...
+    let preferred = format!("{url}?sslmode=prefer&sslrootcert=../dbs/certs/ca.crt");
+    let mut conn = connector_arrow::connect(&preferred).unwrap();
+    let mut stmt = conn.query(query).unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(batches[0].column(0).as_boolean().value(0));
+    drop(stmt);
+
+    // ... unless the server does not support it
+    let plain_url = std::env::var("POSTGRES_URL").unwrap();
+    let mut conn = connector_arrow::connect(&format!("{plain_url}?sslmode=prefer")).unwrap();
+    let mut stmt = conn.query(query).unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(!batches[0].column(0).as_boolean().value(0));
+    drop(stmt);
+
+    // certificate is not trusted without the CA
+    let untrusted = format!("{url}?sslmode=require");
+    let err = connector_arrow::connect(&untrusted).err().unwrap();
+    assert!(err.to_string().contains("TLS"), "{}", err);
+
+    // ... unless verification is disabled
+    let tls = TlsConfig::new().accept_invalid_certs(true);
+    connector_arrow::connect_with_tls(&untrusted, &tls).unwrap();
+}
+
+#[test]
+fn type_mapper() {
+    use std::sync::Arc;
+
+    use arrow::array::{AsArray, RecordBatch, StringArray, TimestampMicrosecondArray};
+    use arrow::datatypes::{DataType, Field, Int64Type, Schema, TimeUnit};
+    use connector_arrow::api::{Append, Connector, SchemaEdit, SchemaGet};
+    use connector_arrow::util::TypeRules;
+
+    let table_name = "type_mapper";
+    let ts = DataType::Timestamp(TimeUnit::Microsecond, None);
+    let ts_utc = DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()));
+
+    let rules = TypeRules::new()
+        .db_type("text", DataType::LargeUtf8)
+        .arrow_type(ts.clone(), "timestamptz");
+    let mut conn = init().with_type_mapper(rules);
+
+    let _ = conn.table_drop(table_name);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("ts", ts, true),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    conn.table_create(table_name, schema.clone()).unwrap();
+
+    let schema_get = conn.table_get(table_name).unwrap();
+    assert_eq!(schema_get.field(0).data_type(), &ts_utc);
+    assert_eq!(schema_get.field(1).data_type(), &DataType::LargeUtf8);
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(TimestampMicrosecondArray::from(vec![
+                0,
+                1_700_000_000_123_456,
+            ])),
+            Arc::new(StringArray::from(vec!["a", "b"])),
+        ],
+    )
+    .unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch).unwrap();
+    appender.finish().unwrap();
+
+    let query =
+        "SELECT ts, name, EXTRACT(EPOCH FROM ts)::bigint AS epoch FROM type_mapper ORDER BY ts";
+    let batches = connector_arrow::query(&mut conn, query).unwrap();
+    let batch = &batches[0];
+    assert_eq!(batch.schema().field(0).data_type(), &ts_utc);
+    assert_eq!(batch.schema().field(1).data_type(), &DataType::LargeUtf8);
+    assert_eq!(batch.column(1).as_string::<i64>().value(1), "b");
+
+    let epoch = batch.column(2).as_primitive::<Int64Type>();
+    assert_eq!(epoch.values(), &[0, 1_700_000_000]);
+}
+
+#[test]
+fn start_with_schema_decimal() {
+    use std::sync::Arc;
+
+    use arrow::array::AsArray;
+    use arrow::datatypes::{DataType, Decimal128Type, Field, Schema, TimeUnit};
+    use connector_arrow::api::{Connector, Statement};
+
+    let mut conn = init();
+
+    let query = "SELECT 1234.567::numeric AS a, now()::timestamp AS b";
+    let mut stmt = conn.query(query).unwrap();
+    let expected = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Decimal128(10, 3), true),
+        Field::new("b", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+    ]));
+    let batches = stmt
+        .start_with_schema([], expected.clone())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(batches[0].schema(), expected);
+    let decimal = batches[0].column(0).as_primitive::<Decimal128Type>();
+    assert_eq!(decimal.value(0), 1_234_567);
+
+    // values that do not fit are not replaced with nulls
+    let expected = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Decimal128(3, 1), true),
+        Field::new("b", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+    ]));
+    let err = stmt
+        .start_with_schema([], expected)
+        .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+        .unwrap_err();
+    assert!(err.to_string().contains("column a"), "{}", err);
+}
+
 #[rstest]
 #[case::empty("roundtrip::empty", spec::empty())]
 #[case::null_bool("roundtrip::null_bool", spec::null_bool())]
@@ -74,6 +543,97 @@ fn roundtrip(#[case] table_name: &str, #[case] spec: spec::ArrowGenSpec) {
     super::tests::roundtrip(&mut conn, table_name, spec, '"', false);
 }
 
+#[rstest]
+#[case::text_int("roundtrip_copy::text_int", CopyFormat::Text, spec::int())]
+#[case::text_float("roundtrip_copy::text_float", CopyFormat::Text, spec::float())]
+#[case::text_decimal("roundtrip_copy::text_decimal", CopyFormat::Text, spec::decimal())]
+#[case::text_timestamp("roundtrip_copy::text_timestamp", CopyFormat::Text, spec::timestamp())]
+#[case::text_utf8("roundtrip_copy::text_utf8", CopyFormat::Text, spec::utf8_large())]
+#[case::text_binary("roundtrip_copy::text_binary", CopyFormat::Text, spec::binary_large())]
+#[case::csv_null_bool("roundtrip_copy::csv_null_bool", CopyFormat::Csv, spec::null_bool())]
+#[case::csv_uint("roundtrip_copy::csv_uint", CopyFormat::Csv, spec::uint())]
+#[case::csv_date("roundtrip_copy::csv_date", CopyFormat::Csv, spec::date())]
+#[case::csv_utf8("roundtrip_copy::csv_utf8", CopyFormat::Csv, spec::utf8_large())]
+#[case::csv_binary("roundtrip_copy::csv_binary", CopyFormat::Csv, spec::binary_large())]
+fn roundtrip_copy_format(
+    #[case] table_name: &str,
+    #[case] format: CopyFormat,
+    #[case] spec: spec::ArrowGenSpec,
+) {
+    let mut conn = init().with_copy_format(format);
+    super::tests::roundtrip(&mut conn, table_name, spec, '"', false);
+}
+
+#[rstest]
+#[case::binary(CopyFormat::Binary)]
+#[case::text(CopyFormat::Text)]
+#[case::csv(CopyFormat::Csv)]
+fn copy_out(#[case] format: CopyFormat) {
+    use arrow::util::pretty::pretty_format_batches;
+
+    let mut conn = init().with_copy_format(format);
+
+    let cases = [
+        literals_cases::bool(),
+        literals_cases::int(),
+        literals_cases::float(),
+        literals_cases::decimal(),
+        literals_cases::timestamp(),
+        literals_cases::date(),
+        literals_cases::time(),
+        literals_cases::interval(),
+        literals_cases::binary(),
+        literals_cases::text(),
+    ];
+    let selects = (cases.iter().flatten().enumerate())
+        .map(|(i, q)| match q.inject_sql_cast {
+            true => format!("CAST({} AS {}) AS f_{i}", q.value_sql, q.db_ty),
+            false => format!("{} AS f_{i}", q.value_sql),
+        })
+        .collect::<Vec<_>>();
+    let query = format!(
+        "SELECT {} UNION ALL SELECT {}",
+        selects.join(", "),
+        (0..selects.len())
+            .map(|_| "NULL")
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let expected = connector_arrow::query(&mut conn, &query).unwrap();
+    let batches = conn
+        .copy_out(CopySource::Query(&format!("{query};\n")))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    similar_asserts::assert_eq!(
+        pretty_format_batches(&expected).unwrap().to_string(),
+        pretty_format_batches(&batches).unwrap().to_string()
+    );
+
+    // tables are copied directly
+    let table_name = format!("copy out {format:?}").to_lowercase();
+    let _ = conn.table_drop(&table_name);
+    let create = format!("CREATE TABLE \"{table_name}\" AS {query}");
+    super::util::execute(&mut conn, &create).unwrap();
+    for schema in [None, Some("public")] {
+        let source = CopySource::Table {
+            schema,
+            name: &table_name,
+        };
+        let batches = conn
+            .copy_out(source)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        similar_asserts::assert_eq!(
+            pretty_format_batches(&expected).unwrap().to_string(),
+            pretty_format_batches(&batches).unwrap().to_string()
+        );
+    }
+    conn.table_drop(&table_name).unwrap();
+}
+
 #[rstest]
 #[case::bool(literals_cases::bool())]
 #[case::int(literals_cases::int())]
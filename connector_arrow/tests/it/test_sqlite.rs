@@ -66,6 +66,22 @@ fn schema_edit() {
     super::tests::schema_edit(&mut conn, table_name, column_spec);
 }
 
+#[test]
+fn schema_alter() {
+    let table_name = "schema_alter";
+
+    let mut conn = init();
+    super::tests::schema_alter(&mut conn, table_name, '"', false);
+}
+
+#[test]
+fn plan_migration() {
+    let table_name = "plan_migration";
+
+    let mut conn = init();
+    super::tests::plan_migration(&mut conn, table_name);
+}
+
 #[test]
 fn ident_escaping() {
     let table_name = "simple::ident_escaping";
@@ -73,3 +89,342 @@ fn ident_escaping() {
     let mut conn = init();
     super::tests::ident_escaping(&mut conn, table_name);
 }
+
+#[test]
+fn relation_list() {
+    let table_name = "relation_list";
+
+    let mut conn = init();
+    super::tests::relation_list(&mut conn, table_name, '"');
+}
+
+#[test]
+fn table_create_with() {
+    let table_name = "table_create_with";
+
+    let mut conn = init();
+    super::tests::table_create_with(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_modes() {
+    let table_name = "append_modes";
+
+    let mut conn = init();
+    super::tests::append_modes(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_generated_keys() {
+    let table_name = "append_generated_keys";
+
+    let mut conn = init();
+    super::tests::append_generated_keys(
+        &mut conn,
+        table_name,
+        &["CREATE TABLE append_generated_keys (id INTEGER PRIMARY KEY, a BIGINT)"],
+    );
+}
+
+#[test]
+fn append_reject() {
+    let table_name = "append_reject";
+
+    let mut conn = init();
+    super::tests::append_reject(&mut conn, table_name, '"', true, false);
+}
+
+#[test]
+fn copy() {
+    let table_name = "copy";
+
+    let mut src = init();
+    let mut dst = init();
+    super::tests::copy(&mut src, &mut dst, table_name, '"');
+}
+
+#[test]
+fn append_by_name() {
+    let table_name = "append_by_name";
+
+    let mut conn = init();
+    super::tests::append_by_name(&mut conn, table_name, '"');
+}
+
+#[test]
+fn check_append() {
+    let table_name = "check_append";
+
+    let mut conn = init();
+    super::tests::check_append(&mut conn, table_name);
+}
+
+#[test]
+fn check_append_generated() {
+    let table_name = "check_append_generated";
+
+    let mut conn = init();
+    let identity_column = "\"id\" INTEGER NOT NULL PRIMARY KEY";
+    super::tests::check_append_generated(&mut conn, table_name, identity_column, '"');
+}
+
+#[test]
+fn data_type_matrix() {
+    let table_name = "data_type_matrix";
+
+    let mut conn = init();
+    super::tests::data_type_matrix(&mut conn, table_name, '"');
+}
+
+#[test]
+fn error_kind() {
+    use connector_arrow::ErrorKind;
+
+    let mut conn = init();
+    super::tests::error_kind(&mut conn, "error_kind", '"');
+
+    let err = connector_arrow::query(&mut conn, "SELEC 1").unwrap_err();
+    assert_eq!(err.vendor_code(), Some(1));
+    assert_eq!(err.sql_state(), None);
+
+    // writing from a read transaction that is behind another writer fails with
+    // the extended code SQLITE_BUSY_SNAPSHOT
+    let path = std::env::temp_dir().join("connector_arrow_error_kind.db");
+    let _ = std::fs::remove_file(&path);
+    let open = || {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        connector_arrow::sqlite::SQLiteConnection::new(conn)
+    };
+    let (mut reader, mut writer) = (open(), open());
+    let setup = "PRAGMA journal_mode = WAL; CREATE TABLE t (a INTEGER)";
+    reader.inner_mut().execute_batch(setup).unwrap();
+    let read = "BEGIN; SELECT * FROM t";
+    reader.inner_mut().execute_batch(read).unwrap();
+    writer
+        .inner_mut()
+        .execute_batch("INSERT INTO t VALUES (1)")
+        .unwrap();
+    let err = connector_arrow::query(&mut reader, "INSERT INTO t VALUES (2)").unwrap_err();
+    assert_eq!(err.vendor_code(), Some(517));
+    assert_eq!(err.kind(), ErrorKind::Busy, "{}", err);
+    assert!(connector_arrow::util::retry::RetryPolicy::is_transient(
+        &err
+    ));
+}
+
+#[test]
+fn table_create_temporary() {
+    let table_name = "table_create_temporary";
+
+    let mut conn = init();
+    super::tests::table_create_temporary(&mut conn, table_name, '"');
+}
+
+#[test]
+fn start_with_schema() {
+    let mut conn = init();
+    super::tests::start_with_schema(&mut conn);
+}
+
+#[test]
+fn dyn_connector() {
+    let mut conn: Box<dyn connector_arrow::dynamic::DynConnector> = Box::new(init());
+    super::tests::dyn_connector(conn.as_mut(), "dyn_connector");
+}
+
+#[test]
+fn cancel() {
+    let mut conn = init();
+    let query = "
+        WITH RECURSIVE t(n) AS (VALUES (1) UNION ALL SELECT n+1 FROM t WHERE n < 1000000000)
+        SELECT COUNT(*) FROM t
+    ";
+    let err = super::tests::cancel(&mut conn, query).unwrap_err();
+    assert!(err.to_string().contains("interrupt"), "{}", err);
+}
+
+#[test]
+fn retry() {
+    use std::cell::Cell;
+    use std::sync::Arc;
+
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use connector_arrow::api::SchemaEdit;
+    use connector_arrow::util::retry::{RetryPolicy, RetryingConnector};
+
+    let opened = Cell::new(0);
+    let open = || {
+        opened.set(opened.get() + 1);
+        Ok(init())
+    };
+    let mut conn = RetryingConnector::new(open, RetryPolicy::new());
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let table_create = conn
+        .connection()
+        .unwrap()
+        .table_create("retry", schema.clone());
+    table_create.unwrap();
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2]))]).unwrap();
+    conn.append("retry", &[batch]).unwrap();
+    let batches = conn.query("SELECT a FROM retry").unwrap();
+    assert_eq!(batches[0].num_rows(), 2);
+
+    // errors that are not transient are not retried
+    let attempts = Cell::new(0);
+    let err = RetryPolicy::new()
+        .run(|| {
+            attempts.set(attempts.get() + 1);
+            connector_arrow::query(conn.connection()?, "SELECT * FROM nonexistent")
+        })
+        .unwrap_err();
+    assert!(!RetryPolicy::is_transient(&err), "{}", err);
+    assert_eq!(attempts.get(), 1);
+
+    // ... and do not reopen the connection
+    conn.query("SELECT * FROM nonexistent").unwrap_err();
+    assert_eq!(opened.get(), 1);
+}
+
+#[test]
+fn partitioned_append() {
+    let path = std::env::temp_dir().join("connector_arrow_partitioned_append.db");
+    let _ = std::fs::remove_file(&path);
+
+    let open = move || {
+        let conn = rusqlite::Connection::open(&path)?;
+        Ok(connector_arrow::sqlite::SQLiteConnection::new(conn))
+    };
+    let mut conn = open().unwrap();
+    super::tests::partitioned_append(&mut conn, open, "partitioned_append");
+}
+
+#[test]
+fn partitioned_query() {
+    let path = std::env::temp_dir().join("connector_arrow_partitioned_query.db");
+    let _ = std::fs::remove_file(&path);
+
+    let open = move || {
+        let conn = rusqlite::Connection::open(&path)?;
+        Ok(connector_arrow::sqlite::SQLiteConnection::new(conn))
+    };
+    let mut conn = open().unwrap();
+    super::tests::partitioned_query(&mut conn, open, "partitioned_query");
+}
+
+#[test]
+fn connect() {
+    use std::sync::Arc;
+
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    let mut conn = connector_arrow::connect("sqlite://:memory:").unwrap();
+    super::tests::dyn_connector(conn.as_mut(), "connect");
+
+    let path = std::env::temp_dir().join("connector_arrow_connect.db");
+    let _ = std::fs::remove_file(&path);
+    let url = format!("sqlite://{}", path.display());
+    let mut conn = connector_arrow::connect(&url).unwrap();
+    super::tests::dyn_connector(conn.as_mut(), "connect");
+    drop(conn);
+
+    let mut conn = connector_arrow::connect(&format!("{url}?mode=ro")).unwrap();
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    conn.table_create("connect", schema).unwrap_err();
+
+    let err = connector_arrow::connect(&format!("{url}?foo=bar"))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("foo"), "{}", err);
+
+    let err = connector_arrow::connect("oracle://localhost")
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("oracle"), "{}", err);
+}
+
+#[test]
+fn type_mapper() {
+    use arrow::array::AsArray;
+    use arrow::datatypes::{DataType, Int32Type};
+    use connector_arrow::api::SchemaGet;
+    use connector_arrow::util::TypeRules;
+
+    let rules = TypeRules::new()
+        .column("id", DataType::Int32, "INTEGER")
+        .db_type("REAL", DataType::Float32);
+    let mut conn = init().with_type_mapper(rules);
+
+    let statements = [
+        "CREATE TABLE type_mapper (id INTEGER, val REAL, name TEXT)",
+        "INSERT INTO type_mapper VALUES (1, 0.5, 'a')",
+    ];
+    for statement in statements {
+        crate::util::execute(&mut conn, statement).unwrap();
+    }
+
+    let schema = conn.table_get("type_mapper").unwrap();
+    assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+    assert_eq!(schema.field(1).data_type(), &DataType::Float32);
+    assert_eq!(schema.field(2).data_type(), &DataType::LargeUtf8);
+
+    let query = "SELECT id, val, name, id + 1 AS other FROM type_mapper";
+    let batches = connector_arrow::query(&mut conn, query).unwrap();
+    let batch = &batches[0];
+    assert_eq!(batch.schema().field(0).data_type(), &DataType::Int32);
+    assert_eq!(batch.schema().field(1).data_type(), &DataType::Float32);
+    assert_eq!(batch.schema().field(2).data_type(), &DataType::Utf8);
+    assert_eq!(batch.schema().field(3).data_type(), &DataType::Int64);
+    assert_eq!(batch.column(0).as_primitive::<Int32Type>().value(0), 1);
+
+    // values that do not fit into the mapped type are not silently replaced with nulls
+    let statement = "INSERT INTO type_mapper VALUES (1099511627776, 0.5, 'b')";
+    crate::util::execute(&mut conn, statement).unwrap();
+    let err = connector_arrow::query(&mut conn, query).unwrap_err();
+    assert!(err.to_string().contains("column id"), "{}", err);
+}
+
+#[test]
+fn start_with_schema_mixed_storage() {
+    use std::sync::Arc;
+
+    use arrow::array::AsArray;
+    use arrow::datatypes::{DataType, Field, Float64Type, Schema};
+    use connector_arrow::api::{Connector, Statement};
+
+    let mut conn = init();
+
+    let statements = [
+        "CREATE TABLE mixed (val)",
+        "INSERT INTO mixed VALUES (1), (2.5), ('3'), (NULL)",
+    ];
+    for statement in statements {
+        crate::util::execute(&mut conn, statement).unwrap();
+    }
+
+    let mut stmt = conn.query("SELECT val FROM mixed").unwrap();
+    let read = |stmt: &mut connector_arrow::sqlite::SQLiteStatement, ty: DataType| {
+        let expected = Arc::new(Schema::new(vec![Field::new("val", ty, true)]));
+        stmt.start_with_schema([], expected)
+            .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+    };
+
+    let batches = read(&mut stmt, DataType::Float64).unwrap();
+    let floats = batches[0].column(0).as_primitive::<Float64Type>();
+    assert_eq!(
+        floats.iter().collect::<Vec<_>>(),
+        [Some(1.0), Some(2.5), Some(3.0), None]
+    );
+
+    let batches = read(&mut stmt, DataType::Utf8).unwrap();
+    let strings = batches[0].column(0).as_string::<i32>();
+    assert_eq!(
+        strings.iter().collect::<Vec<_>>(),
+        [Some("1"), Some("2.5"), Some("3"), None]
+    );
+
+    let err = read(&mut stmt, DataType::Int64).unwrap_err();
+    assert!(err.to_string().contains("column val"), "{}", err);
+}
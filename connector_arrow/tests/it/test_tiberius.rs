@@ -77,6 +77,22 @@ fn schema_edit() {
     super::tests::schema_edit(&mut conn, table_name, column_spec);
 }
 
+#[test]
+fn schema_alter() {
+    let table_name = "schema_alter";
+
+    let mut conn = init();
+    super::tests::schema_alter(&mut conn, table_name, '"', true);
+}
+
+#[test]
+fn plan_migration() {
+    let table_name = "plan_migration";
+
+    let mut conn = init();
+    super::tests::plan_migration(&mut conn, table_name);
+}
+
 #[test]
 #[ignore]
 fn ident_escaping() {
@@ -86,6 +102,120 @@ fn ident_escaping() {
     super::tests::ident_escaping(&mut conn, table_name);
 }
 
+#[test]
+fn relation_list() {
+    let table_name = "relation_list";
+
+    let mut conn = init();
+    super::tests::relation_list(&mut conn, table_name, '"');
+}
+
+#[test]
+fn error_kind() {
+    let mut conn = init();
+    super::tests::error_kind(&mut conn, "error_kind", '"');
+}
+
+#[test]
+fn table_create_with() {
+    let table_name = "table_create_with";
+
+    let mut conn = init();
+    super::tests::table_create_with(&mut conn, table_name, '"');
+}
+
+#[test]
+fn table_create_temporary() {
+    use arrow::datatypes::{DataType, Field, Schema};
+    use connector_arrow::api::{SchemaEdit, TableOptions};
+    use connector_arrow::{ConnectorError, TableCreateError};
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let options = TableOptions {
+        temporary: true,
+        ..Default::default()
+    };
+
+    let mut conn = init();
+    let err = conn
+        .table_create_with("table_create_temporary", schema, &options)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        TableCreateError::Connector(ConnectorError::NotSupported { .. })
+    ));
+}
+
+#[test]
+fn append_modes() {
+    let table_name = "append_modes";
+
+    let mut conn = init();
+    super::tests::append_modes(&mut conn, table_name, '"');
+}
+
+#[test]
+fn append_generated_keys() {
+    let table_name = "append_generated_keys";
+
+    let mut conn = init();
+    super::tests::append_generated_keys(
+        &mut conn,
+        table_name,
+        &["CREATE TABLE append_generated_keys (id BIGINT IDENTITY(1,1) PRIMARY KEY, a BIGINT)"],
+    );
+}
+
+#[test]
+fn append_reject() {
+    let table_name = "append_reject";
+
+    let mut conn = init();
+    super::tests::append_reject(&mut conn, table_name, '"', true, false);
+}
+
+#[test]
+fn copy() {
+    let table_name = "copy";
+
+    let mut src = init();
+    let mut dst = init();
+    super::tests::copy(&mut src, &mut dst, table_name, '"');
+}
+
+#[test]
+fn append_by_name() {
+    let table_name = "append_by_name";
+
+    let mut conn = init();
+    super::tests::append_by_name(&mut conn, table_name, '"');
+}
+
+#[test]
+fn check_append() {
+    let table_name = "check_append";
+
+    let mut conn = init();
+    super::tests::check_append(&mut conn, table_name);
+}
+
+#[test]
+fn check_append_generated() {
+    let table_name = "check_append_generated";
+
+    let mut conn = init();
+    let identity_column = "\"id\" BIGINT IDENTITY NOT NULL";
+    super::tests::check_append_generated(&mut conn, table_name, identity_column, '"');
+}
+
+#[test]
+fn data_type_matrix() {
+    let table_name = "data_type_matrix";
+
+    let mut conn = init();
+    super::tests::data_type_matrix(&mut conn, table_name, '"');
+}
+
 #[rstest]
 #[case::empty("roundtrip::empty", spec::empty())]
 #[case::null_bool("roundtrip::null_bool", spec::null_bool())]
@@ -415,3 +545,56 @@ mod literals_cases {
     // txid_snapshot
     //
 }
+
+#[test]
+#[cfg(feature = "tls-rustls")]
+fn connect_tls() {
+    use connector_arrow::api::Statement;
+    use connector_arrow::tls::TlsConfig;
+
+    // server with a certificate signed by the CA from dbs/certs, which requires TLS
+    let url = std::env::var("TIBERIUS_TLS_URL").unwrap();
+
+    let trusted = format!("{url}?sslmode=require&sslrootcert=../dbs/certs/ca.crt");
+    let mut conn = connector_arrow::connect(&trusted).unwrap();
+    let mut stmt = conn.query("SELECT 1").unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    drop(stmt);
+
+    // unencrypted connections are rejected
+    connector_arrow::connect(&url).err().unwrap();
+
+    // certificate is not trusted without the CA
+    let untrusted = format!("{url}?sslmode=require");
+    connector_arrow::connect(&untrusted).err().unwrap();
+
+    // ... unless verification is disabled
+    let tls = TlsConfig::new().accept_invalid_certs(true);
+    connector_arrow::connect_with_tls(&untrusted, &tls).unwrap();
+}
+
+#[test]
+fn connect_ipv6() {
+    use connector_arrow::api::Statement;
+
+    let err = connector_arrow::connect("mssql://sa:pass@[::1:1433")
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("invalid host"), "{}", err);
+
+    let url = std::env::var("TIBERIUS_URL").unwrap();
+    let url = url.replace("@localhost:", "@[::1]:");
+    let mut conn = connector_arrow::connect(&url).unwrap();
+    let mut stmt = conn.query("SELECT 1").unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+}
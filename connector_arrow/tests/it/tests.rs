@@ -1,17 +1,28 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use arrow::array::{ArrayRef, Int64Builder, RecordBatch};
-use arrow::datatypes::{Field, Schema};
+use arrow::array::{
+    ArrayRef, AsArray, Decimal128Array, Int64Array, Int64Builder, RecordBatch, RecordBatchIterator,
+    TimestampMicrosecondArray,
+};
+use arrow::datatypes::{DataType, Field, Int32Type, Int64Type, Schema, SchemaRef, TimeUnit};
 use arrow::util::pretty::pretty_format_batches;
 use connector_arrow::api::{
-    Append, ArrowValue, Connector, ResultReader, SchemaEdit, SchemaGet, Statement,
+    Append, AppendMode, AppendOptions, ArrowValue, ColumnMatch, Connector, Relation, RelationKind,
+    ResultReader, SchemaEdit, SchemaGet, Statement, TableOptions, METADATA_COMMENT,
+    METADATA_DEFAULT, METADATA_GENERATED, REJECTED_ERROR_FIELD,
+};
+use connector_arrow::dynamic::DynConnector;
+use connector_arrow::migration::{Incompatibility, MigrationStep};
+use connector_arrow::{
+    util::coerce, AppendCheckError, ColumnEditError, ConnectorError, CopyOptions, CopyProgress,
+    TableCreateError, TableDropError, TableEditError,
 };
-use connector_arrow::{util::coerce, TableCreateError, TableDropError};
 use rand::SeedableRng;
 
-use crate::util::{coerce_type, load_into_table, query_table};
+use crate::util::{coerce_type, execute, load_into_table, query_table};
 use crate::{generator::generate_batch, spec::ArrowGenSpec};
 
 pub fn query_01<C: Connector>(conn: &mut C) {
@@ -140,6 +151,900 @@ where
     ));
 }
 
+pub fn schema_alter<C>(
+    conn: &mut C,
+    table_name: &str,
+    ident_quote_char: char,
+    supports_alter_type: bool,
+) where
+    C: Connector + SchemaEdit + SchemaGet,
+{
+    let q = ident_quote_char;
+    let table_name2 = table_name.to_string() + "_renamed";
+    let _ = conn.table_drop(table_name);
+    let _ = conn.table_drop(&table_name2);
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+    conn.table_create(table_name, schema).unwrap();
+
+    // column add
+    let field_b = Field::new("b", DataType::Int32, true);
+    conn.column_add(table_name, &field_b).unwrap();
+    assert!(matches!(
+        conn.column_add(table_name, &field_b).unwrap_err(),
+        ColumnEditError::ColumnExists
+    ));
+    assert!(matches!(
+        conn.column_add(&table_name2, &field_b).unwrap_err(),
+        ColumnEditError::TableNonexistent
+    ));
+
+    // column rename
+    conn.column_rename(table_name, "b", "c").unwrap();
+    assert!(matches!(
+        conn.column_rename(table_name, "b", "d").unwrap_err(),
+        ColumnEditError::ColumnNonexistent
+    ));
+
+    // column alter type
+    let res = conn.column_alter_type(table_name, "c", &DataType::Int64);
+    if supports_alter_type {
+        res.unwrap();
+    } else {
+        assert!(matches!(
+            res.unwrap_err(),
+            ColumnEditError::Connector(ConnectorError::NotSupported { .. })
+        ));
+    }
+
+    let schema = conn.table_get(table_name).unwrap();
+    let field_c = schema.field_with_name("c").unwrap();
+    assert!(field_c.is_nullable());
+    if supports_alter_type {
+        assert_eq!(field_c.data_type(), &DataType::Int64);
+    }
+
+    // column drop
+    conn.column_drop(table_name, "c").unwrap();
+    assert!(matches!(
+        conn.column_drop(table_name, "c").unwrap_err(),
+        ColumnEditError::ColumnNonexistent
+    ));
+    assert_eq!(conn.table_get(table_name).unwrap().fields().len(), 1);
+
+    // table truncate
+    execute(
+        conn,
+        &format!("INSERT INTO {q}{table_name}{q} (a) VALUES (1)"),
+    )
+    .unwrap();
+    conn.table_truncate(table_name).unwrap();
+    let (_, batches) = query_table(conn, table_name, q).unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+    // table rename
+    conn.table_rename(table_name, &table_name2).unwrap();
+    assert!(matches!(
+        conn.table_truncate(table_name).unwrap_err(),
+        TableEditError::TableNonexistent
+    ));
+
+    // table rename onto an existing table
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    conn.table_create(table_name, schema).unwrap();
+    let err = conn.table_rename(&table_name2, table_name).unwrap_err();
+    assert!(matches!(err, TableEditError::TableExists), "{:?}", err);
+    conn.table_drop(table_name).unwrap();
+    conn.table_drop(&table_name2).unwrap();
+}
+
+pub fn plan_migration<C>(conn: &mut C, table_name: &str)
+where
+    C: Connector + SchemaEdit + SchemaGet,
+{
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Utf8, true),
+        Field::new("c", DataType::Int64, true),
+    ]));
+    conn.table_create(table_name, schema).unwrap();
+
+    let desired = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, true),
+        Field::new("d", DataType::Float64, true),
+    ]));
+    let plan = conn.plan_migration(table_name, desired.clone()).unwrap();
+    assert_eq!(plan.incompatible, vec![]);
+    let mut expected = vec![
+        MigrationStep::ColumnAdd(desired.field(2).clone()),
+        MigrationStep::ColumnDrop("c".to_string()),
+    ];
+    // column a needs widening only if Int32 is stored in a narrower type than Int64
+    if C::type_arrow_into_db(&DataType::Int32) != C::type_arrow_into_db(&DataType::Int64) {
+        let widened = Field::new("a", DataType::Int64, false);
+        expected.insert(0, MigrationStep::ColumnWiden(widened));
+    }
+    assert_eq!(plan.steps, expected);
+
+    let ddl = plan.to_ddl(&*conn).unwrap();
+    assert_eq!(ddl.len(), plan.steps.len());
+
+    plan.apply(conn).unwrap();
+    let plan = conn.plan_migration(table_name, desired).unwrap();
+    assert!(plan.is_empty(), "{:?}", plan);
+
+    // incompatible type change
+    let desired = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Int64, true),
+        Field::new("d", DataType::Float64, true),
+    ]));
+    let plan = conn.plan_migration(table_name, desired).unwrap();
+    assert!(matches!(
+        plan.incompatible.as_slice(),
+        [Incompatibility::TypeChange { column_name, .. }] if column_name == "b"
+    ));
+    assert!(matches!(
+        plan.apply(conn).unwrap_err(),
+        ColumnEditError::Connector(ConnectorError::IncompatibleSchema { .. })
+    ));
+
+    // added columns that cannot be stored or cannot be filled for existing rows
+    use arrow::datatypes::{UnionFields, UnionMode};
+    let union = DataType::Union(UnionFields::empty(), UnionMode::Sparse);
+    let with_default = HashMap::from([(METADATA_DEFAULT.to_string(), "0".to_string())]);
+    let desired = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, true),
+        Field::new("d", DataType::Float64, true),
+        Field::new("e", union.clone(), true),
+        Field::new("f", DataType::Int64, false),
+        Field::new("g", DataType::Int64, false).with_metadata(with_default),
+    ]));
+    let plan = conn.plan_migration(table_name, desired.clone()).unwrap();
+    assert_eq!(
+        plan.steps,
+        vec![MigrationStep::ColumnAdd(desired.field(5).clone())]
+    );
+    assert_eq!(
+        plan.incompatible,
+        vec![
+            Incompatibility::UnsupportedType {
+                column_name: "e".to_string(),
+                data_type: union,
+            },
+            Incompatibility::ColumnAddNotNull {
+                column_name: "f".to_string(),
+            },
+        ]
+    );
+
+    // requiring values might fail on existing rows
+    let desired = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, false),
+        Field::new("d", DataType::Float64, true),
+    ]));
+    let plan = conn.plan_migration(table_name, desired).unwrap();
+    assert_eq!(plan.steps, vec![]);
+    assert_eq!(
+        plan.incompatible,
+        vec![Incompatibility::Nullability {
+            column_name: "b".to_string(),
+        }]
+    );
+
+    // allowing nulls is lossless
+    let desired = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Utf8, true),
+        Field::new("d", DataType::Float64, true),
+    ]));
+    let plan = conn.plan_migration(table_name, desired.clone()).unwrap();
+    assert_eq!(plan.incompatible, vec![]);
+    assert!(matches!(
+        plan.steps.as_slice(),
+        [MigrationStep::ColumnDropNotNull(field)] if field.name() == "a" && field.is_nullable()
+    ));
+    match plan.apply(conn) {
+        Ok(()) => {
+            let plan = conn.plan_migration(table_name, desired).unwrap();
+            assert!(plan.is_empty(), "{:?}", plan);
+        }
+        Err(ColumnEditError::Connector(ConnectorError::NotSupported { .. })) => {}
+        Err(e) => panic!("{:?}", e),
+    }
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn check_append<C>(conn: &mut C, table_name: &str)
+where
+    C: Connector + SchemaEdit + SchemaGet,
+{
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Float64, true),
+    ]));
+    conn.table_create(table_name, schema).unwrap();
+
+    // reordered and partial
+    let appended = Schema::new(vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("id", DataType::Int32, false),
+    ]);
+    let plan = conn.check_append(table_name, &appended).unwrap();
+    assert_eq!(plan.column_names().collect::<Vec<_>>(), vec!["name", "id"]);
+    assert_eq!(plan.omitted.len(), 1);
+    assert_eq!(plan.omitted[0].name(), "score");
+
+    // all problems are reported at once
+    let appended = Schema::new(vec![
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Utf8, true),
+        Field::new("extra", DataType::Int64, true),
+    ]);
+    let AppendCheckError::Incompatible(incompatible) =
+        conn.check_append(table_name, &appended).unwrap_err()
+    else {
+        panic!()
+    };
+    assert_eq!(incompatible.len(), 3, "{:?}", incompatible);
+    assert!(matches!(
+        &incompatible[0],
+        Incompatibility::TypeChange { column_name, .. } if column_name == "score"
+    ));
+    assert_eq!(
+        incompatible[1],
+        Incompatibility::ColumnNonexistent {
+            column_name: "extra".to_string()
+        }
+    );
+    assert_eq!(
+        incompatible[2],
+        Incompatibility::ColumnMissing {
+            column_name: "id".to_string()
+        }
+    );
+
+    let appended = Schema::new(vec![Field::new("id", DataType::Int64, true)]);
+    let AppendCheckError::Incompatible(incompatible) =
+        conn.check_append(table_name, &appended).unwrap_err()
+    else {
+        panic!()
+    };
+    assert_eq!(
+        incompatible,
+        vec![Incompatibility::Nullability {
+            column_name: "id".to_string()
+        }]
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+/// Columns with defaults and generated columns, such as identity columns declared by
+/// `identity_column`, can be left out of appended batches.
+pub fn check_append_generated<C>(
+    conn: &mut C,
+    table_name: &str,
+    identity_column: &str,
+    ident_quote_char: char,
+) where
+    C: Connector + SchemaEdit + SchemaGet,
+{
+    let q = ident_quote_char;
+    let _ = conn.table_drop(table_name);
+    let create = format!(
+        "CREATE TABLE {q}{table_name}{q} ({identity_column}, \
+        {q}flag{q} BIGINT NOT NULL DEFAULT 0, {q}val{q} BIGINT)"
+    );
+    execute(conn, &create).unwrap();
+
+    let schema = conn.table_get(table_name).unwrap();
+    let id = schema.field_with_name("id").unwrap();
+    assert!(!id.is_nullable());
+    assert!(
+        id.metadata().get(METADATA_GENERATED).map(String::as_str) == Some("identity")
+            || id.metadata().contains_key(METADATA_DEFAULT),
+        "{:?}",
+        id
+    );
+    let flag = schema.field_with_name("flag").unwrap();
+    assert!(flag.metadata().contains_key(METADATA_DEFAULT), "{:?}", flag);
+    assert!(
+        !flag.metadata().contains_key(METADATA_GENERATED),
+        "{:?}",
+        flag
+    );
+
+    let appended = Arc::new(Schema::new(vec![Field::new("val", DataType::Int64, true)]));
+    let plan = conn.check_append(table_name, &appended).unwrap();
+    assert_eq!(plan.column_names().collect::<Vec<_>>(), vec!["val"]);
+    let omitted: Vec<_> = plan.omitted.iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(omitted, vec!["id", "flag"]);
+
+    let batch = RecordBatch::try_new(appended, vec![Arc::new(Int64Array::from(vec![7, 8]))]);
+    let mut appender = (conn.append_with(table_name, &AppendOptions::by_name())).unwrap();
+    appender.append(batch.unwrap()).unwrap();
+    appender.finish().unwrap();
+
+    let query = format!("SELECT {q}id{q}, {q}flag{q} FROM {q}{table_name}{q} ORDER BY {q}val{q}");
+    let batches = connector_arrow::query(conn, &query).unwrap();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(batch.column(0).null_count(), 0);
+    let flags = arrow::compute::cast(batch.column(1), &DataType::Int64).unwrap();
+    assert_eq!(
+        flags.as_primitive::<Int64Type>(),
+        &Int64Array::from(vec![0, 0])
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn append_by_name<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit,
+{
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false).with_metadata(HashMap::from([(
+            METADATA_DEFAULT.to_string(),
+            "42".to_string(),
+        )])),
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+    conn.table_create(table_name, schema.clone()).unwrap();
+
+    // reordered, renamed and without the defaulted column
+    let appended = Arc::new(Schema::new(vec![
+        Field::new("b", DataType::Utf8, true),
+        Field::new("x", DataType::Int64, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        appended,
+        vec![
+            Arc::new(arrow::array::StringArray::from(vec!["one", "two"])),
+            Arc::new(Int64Array::from(vec![1, 2])),
+        ],
+    )
+    .unwrap();
+    let options = AppendOptions {
+        columns: ColumnMatch::Name {
+            renames: HashMap::from([("x".to_string(), "a".to_string())]),
+        },
+        ..Default::default()
+    };
+    let mut appender = conn.append_with(table_name, &options).unwrap();
+    appender.append(batch.clone()).unwrap();
+    appender.append(batch).unwrap();
+    appender.finish().unwrap();
+
+    let (_, batches) = query_table(conn, table_name, ident_quote_char).unwrap();
+    let batches: Vec<_> = batches
+        .iter()
+        .map(|b| coerce::cast_batch(b, &schema).unwrap())
+        .collect();
+    similar_asserts::assert_eq!(
+        pretty_format_batches(&batches).unwrap().to_string(),
+        "+----+---+-----+
+| id | a | b   |
++----+---+-----+
+| 42 | 1 | one |
+| 42 | 2 | two |
+| 42 | 1 | one |
+| 42 | 2 | two |
++----+---+-----+"
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn append_modes<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaGet + SchemaEdit,
+{
+    let _ = conn.table_drop(table_name);
+
+    let append = |conn: &mut C, mode: AppendMode, batch: &RecordBatch, finish: bool| {
+        let options = AppendOptions {
+            mode,
+            ..Default::default()
+        };
+        let mut appender = conn.append_with(table_name, &options).unwrap();
+        appender.append(batch.clone()).unwrap();
+        if finish {
+            appender.finish().unwrap();
+        }
+    };
+    let contents = |conn: &mut C, schema: &SchemaRef| {
+        let (_, batches) = query_table(conn, table_name, ident_quote_char).unwrap();
+        let batches: Vec<_> = batches
+            .iter()
+            .map(|b| coerce::cast_batch(b, schema).unwrap())
+            .collect();
+        pretty_format_batches(&batches).unwrap().to_string()
+    };
+
+    // table is created from the schema of the batch
+    let schema_a = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let batch = RecordBatch::try_new(
+        schema_a.clone(),
+        vec![Arc::new(Int64Array::from(vec![1, 2]))],
+    )
+    .unwrap();
+    append(conn, AppendMode::CreateIfMissing, &batch, true);
+    append(conn, AppendMode::CreateIfMissing, &batch, true);
+    similar_asserts::assert_eq!(
+        contents(conn, &schema_a),
+        "+---+
+| a |
++---+
+| 1 |
+| 2 |
+| 1 |
+| 2 |
++---+"
+    );
+
+    // missing column is added
+    let schema_ab = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema_ab.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![3])),
+            Arc::new(Int64Array::from(vec![30])),
+        ],
+    )
+    .unwrap();
+    append(conn, AppendMode::EvolveSchema, &batch, true);
+    let table = conn.table_get(table_name).unwrap();
+    assert_eq!(table.fields().len(), 2);
+    assert!(table.field(1).is_nullable());
+    let evolved = contents(conn, &schema_ab);
+    similar_asserts::assert_eq!(
+        evolved,
+        "+---+----+
+| a | b  |
++---+----+
+| 1 |    |
+| 2 |    |
+| 1 |    |
+| 2 |    |
+| 3 | 30 |
++---+----+"
+    );
+
+    // unfinished replace does not change the table
+    let schema_c = Arc::new(Schema::new(vec![Field::new("c", DataType::Int64, true)]));
+    let batch =
+        RecordBatch::try_new(schema_c.clone(), vec![Arc::new(Int64Array::from(vec![7]))]).unwrap();
+    append(conn, AppendMode::Replace, &batch, false);
+    similar_asserts::assert_eq!(contents(conn, &schema_ab), evolved);
+
+    // replace swaps both the schema and the contents
+    append(conn, AppendMode::Replace, &batch, true);
+    let table = conn.table_get(table_name).unwrap();
+    assert_eq!(table.fields().len(), 1);
+    assert_eq!(table.field(0).name(), "c");
+    similar_asserts::assert_eq!(
+        contents(conn, &schema_c),
+        "+---+
+| c |
++---+
+| 7 |
++---+"
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+/// Appends into a table with a generated key column `id`, created by `ddl`.
+pub fn append_generated_keys<C>(conn: &mut C, table_name: &str, ddl: &[&str])
+where
+    C: Connector + SchemaEdit,
+{
+    let _ = conn.table_drop(table_name);
+    for query in ddl {
+        execute(conn, query).unwrap();
+    }
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let batch =
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![10, 20, 30]))]).unwrap();
+
+    // without generated keys
+    let mut appender = conn
+        .append_with(table_name, &AppendOptions::by_name())
+        .unwrap();
+    appender.append(batch.clone()).unwrap();
+    let summary = appender.finish().unwrap();
+    assert_eq!(summary.rows, 3);
+    assert_eq!(summary.batches, 1);
+    assert!(summary.bytes > 0);
+    assert!(summary.generated_keys.is_none());
+
+    // with generated keys
+    let options = AppendOptions::by_name().with_generated_keys("id");
+    let mut appender = conn.append_with(table_name, &options).unwrap();
+    appender.append(batch.clone()).unwrap();
+    appender.append(batch).unwrap();
+    let summary = appender.finish().unwrap();
+    assert_eq!(summary.rows, 6);
+    assert_eq!(summary.batches, 2);
+    let keys = summary.generated_keys.unwrap();
+    assert_eq!(
+        keys.as_primitive::<Int64Type>().values().as_ref(),
+        &[4, 5, 6, 7, 8, 9]
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+/// Appends rows that violate a NOT NULL constraint.
+/// `reports_row` is set for connectors that locate the failing row within the batch.
+pub fn append_reject<C>(
+    conn: &mut C,
+    table_name: &str,
+    ident_quote_char: char,
+    supports_reject: bool,
+    reports_row: bool,
+) where
+    C: Connector + SchemaEdit,
+{
+    let q = ident_quote_char;
+    let _ = conn.table_drop(table_name);
+    execute(
+        conn,
+        &format!("CREATE TABLE {q}{table_name}{q} (a BIGINT NOT NULL, b BIGINT)"),
+    )
+    .unwrap();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![
+                Some(1),
+                None,
+                Some(3),
+                None,
+                Some(5),
+            ])),
+            Arc::new(Int64Array::from(vec![10, 20, 30, 40, 50])),
+        ],
+    )
+    .unwrap();
+
+    // abort
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch.slice(0, 1)).unwrap();
+    let err = appender.append(batch.clone()).unwrap_err();
+    let context = err.context().unwrap();
+    assert_eq!(context.batch, Some(1));
+    if reports_row {
+        assert_eq!(context.row, Some(1), "{}", err);
+    }
+    drop(appender);
+
+    let options = AppendOptions::default().with_rejected_rows();
+    if !supports_reject {
+        let err = conn.append_with(table_name, &options).err().unwrap();
+        assert!(
+            matches!(err, ConnectorError::NotSupported { .. }),
+            "{}",
+            err
+        );
+        conn.table_drop(table_name).unwrap();
+        return;
+    }
+
+    // reject
+    let mut appender = conn.append_with(table_name, &options).unwrap();
+    appender.append(batch.clone()).unwrap();
+    appender.append(batch).unwrap();
+    let summary = appender.finish().unwrap();
+    assert_eq!(summary.rows, 6);
+    assert_eq!(summary.batches, 2);
+
+    let rejected = summary.rejected.unwrap();
+    assert_eq!(rejected.num_rows(), 4);
+    assert_eq!(
+        rejected
+            .column(1)
+            .as_primitive::<Int64Type>()
+            .values()
+            .as_ref(),
+        &[20, 40, 20, 40]
+    );
+    let errors = rejected.column_by_name(REJECTED_ERROR_FIELD).unwrap();
+    let errors = errors.as_string::<i32>();
+    assert!(
+        errors.value(0).contains("batch 0, row 1"),
+        "{}",
+        errors.value(0)
+    );
+    assert!(
+        errors.value(3).contains("batch 1, row 3"),
+        "{}",
+        errors.value(3)
+    );
+
+    let (_, batches) = query_table(conn, table_name, q).unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 6);
+
+    conn.table_drop(table_name).unwrap();
+}
+
+/// Copies a table from `src` into a new table in `dst`.
+pub fn copy<C>(src: &mut C, dst: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit,
+{
+    let q = ident_quote_char;
+    let src_table = format!("{table_name}_src");
+    let _ = src.table_drop(&src_table);
+    let _ = dst.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+    let batches: Vec<_> = (0..3)
+        .map(|i| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![i * 2, i * 2 + 1])),
+                    Arc::new(arrow::array::StringArray::from(vec![
+                        Some(format!("v{i}")),
+                        None,
+                    ])),
+                ],
+            )
+            .unwrap()
+        })
+        .collect();
+
+    src.table_create(&src_table, schema.clone()).unwrap();
+    let mut appender = src.append(&src_table).unwrap();
+    let reader = RecordBatchIterator::new(batches.clone().into_iter().map(Ok), schema.clone());
+    appender.append_reader(reader).unwrap();
+    appender.finish().unwrap();
+
+    let mut progress_rows = 0;
+    let mut on_progress = |p: &CopyProgress| progress_rows = p.rows;
+    let options = CopyOptions {
+        append: AppendOptions {
+            mode: AppendMode::CreateIfMissing,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+    .with_progress(&mut on_progress);
+    let query = format!("SELECT * FROM {q}{src_table}{q} ORDER BY {q}a{q}");
+    let summary = connector_arrow::copy(src, &query, dst, table_name, options).unwrap();
+    assert_eq!(summary.rows, 6);
+    assert_eq!(progress_rows, 6);
+
+    let (_, copied) = query_table(dst, table_name, q).unwrap();
+    let copied: Vec<_> = copied
+        .iter()
+        .map(|b| coerce::cast_batch(b, &schema).unwrap())
+        .collect();
+    similar_asserts::assert_eq!(
+        pretty_format_batches(&copied).unwrap().to_string(),
+        pretty_format_batches(&batches).unwrap().to_string()
+    );
+
+    // types that cannot be stored are converted into types that can
+    let unsupported = Schema::new(vec![
+        Field::new("u", DataType::UInt64, true),
+        Field::new("f", DataType::Float16, true),
+        Field::new("d", DataType::Duration(TimeUnit::Second), true),
+        Field::new("s", DataType::LargeUtf8, true),
+    ]);
+    let coerced = coerce::coerce_schema_for::<C>(None, &unsupported);
+    for field in coerced.fields() {
+        assert!(
+            C::type_arrow_into_db(field.data_type()).is_some(),
+            "{}",
+            field
+        );
+    }
+
+    src.table_drop(&src_table).unwrap();
+    dst.table_drop(table_name).unwrap();
+}
+
+pub fn table_create_with<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaGet + SchemaEdit,
+{
+    let q = ident_quote_char;
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("val", DataType::Int64, true).with_metadata(HashMap::from([
+            (METADATA_DEFAULT.to_string(), "42".to_string()),
+            (METADATA_COMMENT.to_string(), "the 'answer'".to_string()),
+        ])),
+    ]));
+    let options = TableOptions {
+        primary_key: vec!["id".to_string()],
+        unique: vec![vec!["id".to_string(), "val".to_string()]],
+        comments: HashMap::from([("id".to_string(), "the key".to_string())]),
+        ..Default::default()
+    };
+    conn.table_create_with(table_name, schema.clone(), &options)
+        .unwrap();
+
+    // comments, from options and from field metadata
+    let table = conn.table_get(table_name).unwrap();
+    let comment = |name: &str| {
+        let field = table.field_with_name(name).unwrap();
+        field.metadata().get(METADATA_COMMENT).cloned()
+    };
+    assert_eq!(comment("id").as_deref(), Some("the key"));
+    assert_eq!(comment("val").as_deref(), Some("the 'answer'"));
+    assert!(matches!(
+        conn.table_create_with(table_name, schema.clone(), &options)
+            .unwrap_err(),
+        TableCreateError::TableExists
+    ));
+
+    let options_if_not_exists = TableOptions {
+        if_not_exists: true,
+        ..options.clone()
+    };
+    conn.table_create_with(table_name, schema, &options_if_not_exists)
+        .unwrap();
+
+    // default value
+    let insert = format!("INSERT INTO {q}{table_name}{q} (id) VALUES (1)");
+    execute(conn, &insert).unwrap();
+    let (_, batches) = query_table(conn, table_name, q).unwrap();
+    let val = batches[0].column(1).as_primitive::<Int64Type>();
+    assert_eq!(val, &Int64Array::from(vec![42]));
+
+    // primary key
+    execute(conn, &insert).unwrap_err();
+}
+
+pub fn error_kind<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit,
+{
+    use connector_arrow::ErrorKind;
+
+    let q = ident_quote_char;
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("val", DataType::Int64, false),
+    ]));
+    let options = TableOptions {
+        primary_key: vec!["id".to_string()],
+        ..Default::default()
+    };
+    conn.table_create_with(table_name, schema, &options)
+        .unwrap();
+
+    let table = format!("{q}{table_name}{q}");
+    execute(
+        conn,
+        &format!("INSERT INTO {table} (id, val) VALUES (1, 1)"),
+    )
+    .unwrap();
+
+    let cases = [
+        (
+            format!("INSERT INTO {table} (id, val) VALUES (1, 1)"),
+            ErrorKind::UniqueViolation,
+        ),
+        (
+            format!("INSERT INTO {table} (id, val) VALUES (2, NULL)"),
+            ErrorKind::NotNullViolation,
+        ),
+        (
+            "SELECT * FROM error_kind_nonexistent".to_string(),
+            ErrorKind::UndefinedTable,
+        ),
+        (
+            format!("SELECT nonexistent FROM {table}"),
+            ErrorKind::UndefinedColumn,
+        ),
+        (
+            format!("CREATE TABLE {table} (id INTEGER)"),
+            ErrorKind::TableExists,
+        ),
+        ("SELEC 1".to_string(), ErrorKind::SyntaxError),
+    ];
+    for (query, expected) in cases {
+        let err = execute(conn, &query).unwrap_err();
+        assert_eq!(err.kind(), expected, "{}: {:?}", query, err);
+    }
+    assert_eq!(ConnectorError::NoResultSets.kind(), ErrorKind::Other);
+    let exists = ConnectorError::TableExists {
+        table_name: table_name.to_string(),
+    };
+    assert_eq!(exists.kind(), ErrorKind::TableExists);
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn table_create_temporary<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit,
+{
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let options = TableOptions {
+        temporary: true,
+        ..Default::default()
+    };
+    conn.table_create_with(table_name, schema.clone(), &options)
+        .unwrap();
+
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2]))]).unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch.clone()).unwrap();
+    appender.finish().unwrap();
+
+    let (_, batches) = query_table(conn, table_name, ident_quote_char).unwrap();
+    assert_eq!(batches, vec![batch]);
+}
+
+pub fn relation_list<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit + SchemaGet,
+{
+    let view_name = table_name.to_string() + "_view";
+    let q = ident_quote_char;
+
+    execute(conn, &format!("DROP VIEW IF EXISTS {q}{view_name}{q}")).unwrap();
+    let _ = conn.table_drop(table_name);
+
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+    conn.table_create(table_name, schema).unwrap();
+    execute(
+        conn,
+        &format!("CREATE VIEW {q}{view_name}{q} AS SELECT a FROM {q}{table_name}{q}"),
+    )
+    .unwrap();
+
+    let relations = conn.relation_list().unwrap();
+    assert!(relations.contains(&Relation {
+        name: table_name.to_string(),
+        kind: RelationKind::Table
+    }));
+    assert!(relations.contains(&Relation {
+        name: view_name.clone(),
+        kind: RelationKind::View
+    }));
+
+    // table_get works on views
+    let schema_view = conn.table_get(&view_name).unwrap();
+    assert_eq!(schema_view.fields().len(), 1);
+    assert_eq!(schema_view.field(0).name(), "a");
+}
+
 pub fn ident_escaping<C>(conn: &mut C, table_name_prefix: &str)
 where
     C: Connector + SchemaEdit + SchemaGet,
@@ -175,6 +1080,378 @@ where
     appender.append(batch).unwrap();
 }
 
+pub fn start_with_schema<C: Connector>(conn: &mut C) {
+    let query = "SELECT 1 as a, 'x' as b";
+    let mut stmt = conn.query(query).unwrap();
+
+    let expected = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("name", DataType::LargeUtf8, true),
+    ]));
+    let mut reader = stmt.start_with_schema([], expected.clone()).unwrap();
+    assert_eq!(reader.get_schema().unwrap(), expected);
+
+    let batch = reader.next().unwrap().unwrap();
+    assert_eq!(batch.schema(), expected);
+    assert_eq!(batch.column(0).as_primitive::<Int32Type>().value(0), 1);
+    assert_eq!(batch.column(1).as_string::<i64>().value(0), "x");
+    drop(reader);
+
+    // number of fields must match
+    let expected = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, true)]));
+    let err = stmt.start_with_schema([], expected).err().unwrap();
+    assert!(
+        matches!(err, ConnectorError::DataSchemaMismatch(_)),
+        "{:?}",
+        err
+    );
+
+    // values that cannot be converted produce an error that names the column
+    let expected = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Int32, true),
+    ]));
+    let err = stmt
+        .start_with_schema([], expected)
+        .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+        .unwrap_err();
+    assert!(err.to_string().contains("column b"), "{}", err);
+}
+
+pub fn dyn_connector(conn: &mut dyn DynConnector, table_name: &str) {
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+    let _ = conn.table_drop(table_name);
+    conn.table_create(table_name, schema.clone()).unwrap();
+    assert_eq!(conn.table_get(table_name).unwrap().field(0).name(), "a");
+
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2]))]).unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch).unwrap();
+    appender.finish().unwrap();
+
+    let mut stmt = conn.query(&format!("SELECT a FROM {table_name}")).unwrap();
+    let batches = stmt
+        .start([])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let values: Vec<_> = batches
+        .iter()
+        .map(|b| arrow::compute::cast(b.column(0), &DataType::Int64).unwrap())
+        .flat_map(|a| a.as_primitive::<Int64Type>().values().to_vec())
+        .collect();
+    assert_eq!(values, vec![1, 2]);
+    drop(stmt);
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn partitioned_query<C, F>(conn: &mut C, conn_factory: F, table_name: &str)
+where
+    C: Connector + SchemaEdit,
+    F: Fn() -> Result<C, ConnectorError> + Send + Sync + 'static,
+{
+    use connector_arrow::util::partition::partitioned_query;
+
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let _ = conn.table_drop(table_name);
+    conn.table_create(table_name, schema.clone()).unwrap();
+
+    let ids: Int64Array = (1..=100).map(Some).chain(std::iter::once(None)).collect();
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(ids)]).unwrap();
+    let mut appender = conn.append(table_name).unwrap();
+    appender.append(batch).unwrap();
+    appender.finish().unwrap();
+
+    let query = format!("SELECT id FROM {table_name}");
+    let mut reader = partitioned_query(conn_factory, &query, "id", 4).unwrap();
+    let schema = reader.get_schema().unwrap();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(batches.iter().all(|b| b.schema() == schema));
+
+    let mut values: Vec<_> = batches
+        .iter()
+        .map(|b| arrow::compute::cast(b.column(0), &DataType::Int64).unwrap())
+        .flat_map(|a| a.as_primitive::<Int64Type>().iter().collect::<Vec<_>>())
+        .collect();
+    values.sort();
+    let expected: Vec<_> = std::iter::once(None).chain((1..=100).map(Some)).collect();
+    assert_eq!(values, expected);
+
+    conn.table_drop(table_name).unwrap();
+}
+
+pub fn partitioned_append<C, F>(conn: &mut C, conn_factory: F, table_name: &str)
+where
+    C: Connector + SchemaGet + SchemaEdit,
+    F: Fn() -> Result<C, ConnectorError> + Sync,
+{
+    use arrow::record_batch::RecordBatchIterator;
+    use connector_arrow::util::partition::{partitioned_append, CommitMode};
+
+    // decimals cannot be stored by all connectors
+    let decimal = DataType::Decimal128(10, 2);
+    let with_decimal = C::type_arrow_into_db(&decimal).is_some();
+
+    let mut fields = vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("at", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+    ];
+    if with_decimal {
+        fields.push(Field::new("amount", decimal, true));
+    }
+    let schema = Arc::new(Schema::new(fields));
+    let _ = conn.table_drop(table_name);
+    conn.table_create(table_name, schema.clone()).unwrap();
+
+    let batches: Vec<_> = (0..10)
+        .map(|i| {
+            let ids = Int64Array::from_iter_values(i * 100..(i + 1) * 100);
+            let ats = TimestampMicrosecondArray::from_iter_values(i * 100..(i + 1) * 100);
+            let mut columns: Vec<ArrayRef> = vec![Arc::new(ids), Arc::new(ats)];
+            if with_decimal {
+                let amounts =
+                    Decimal128Array::from_iter_values((i * 100..(i + 1) * 100).map(i128::from))
+                        .with_precision_and_scale(10, 2)
+                        .unwrap();
+                columns.push(Arc::new(amounts));
+            }
+            RecordBatch::try_new(schema.clone(), columns).unwrap()
+        })
+        .collect();
+    let count = |conn: &mut C| {
+        let query = format!("SELECT COUNT(*) FROM {}", C::quote_ident(table_name));
+        let batches = connector_arrow::query(conn, &query).unwrap();
+        let count = arrow::compute::cast(batches[0].column(0), &DataType::Int64).unwrap();
+        count.as_primitive::<Int64Type>().value(0)
+    };
+
+    for (commit, expected) in [
+        (CommitMode::PerPartition, 1000),
+        (CommitMode::AllOrNothing, 2000),
+    ] {
+        let reader = RecordBatchIterator::new(batches.clone().into_iter().map(Ok), schema.clone());
+        let rows = partitioned_append(&conn_factory, table_name, reader, 3, commit).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows.iter().sum::<usize>(), 1000);
+        assert_eq!(count(conn), expected);
+    }
+
+    // workers that all fail do not block the reader
+    let missing = format!("{table_name}_missing");
+    for commit in [CommitMode::PerPartition, CommitMode::AllOrNothing] {
+        let reader = RecordBatchIterator::new(batches.clone().into_iter().map(Ok), schema.clone());
+        partitioned_append(&conn_factory, &missing, reader, 3, commit).unwrap_err();
+    }
+
+    // ... even when they fail after the reader has filled the channel
+    let failing_factory = || -> Result<C, ConnectorError> {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        Err(ConnectorError::DataSchemaMismatch("cannot connect".into()))
+    };
+    let reader = RecordBatchIterator::new(batches.clone().into_iter().map(Ok), schema.clone());
+    let commit = CommitMode::PerPartition;
+    partitioned_append(failing_factory, table_name, reader, 3, commit).unwrap_err();
+
+    // a batch that cannot be appended fails the whole append
+    let invalid_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let ids = Arc::new(Int64Array::from(vec![Some(1), None]));
+    let invalid = RecordBatch::try_new(invalid_schema, vec![ids]).unwrap();
+    let input = batches.into_iter().chain(std::iter::once(invalid));
+    let reader = RecordBatchIterator::new(input.map(Ok), schema);
+    partitioned_append(
+        &conn_factory,
+        table_name,
+        reader,
+        3,
+        CommitMode::AllOrNothing,
+    )
+    .unwrap_err();
+    assert_eq!(count(conn), 2000);
+    let tables = conn.table_list().unwrap();
+    assert!(
+        !tables.iter().any(|t| t.contains("staging")),
+        "{:?}",
+        tables
+    );
+
+    conn.table_drop(table_name).unwrap();
+}
+
+/// Feeds a column of every [DataType], with a value and a null, through table create,
+/// append and query.
+///
+/// Each step must either succeed or fail with [ConnectorError::IncompatibleSchema]
+/// or [ConnectorError::NotSupported], but never panic.
+pub fn data_type_matrix<C>(conn: &mut C, table_name: &str, ident_quote_char: char)
+where
+    C: Connector + SchemaEdit,
+{
+    use arrow::datatypes::{IntervalUnit, UnionFields, UnionMode};
+
+    fn is_unsupported(err: &ConnectorError) -> bool {
+        matches!(
+            err,
+            ConnectorError::IncompatibleSchema { .. } | ConnectorError::NotSupported { .. }
+        )
+    }
+
+    let item = Arc::new(Field::new("item", DataType::Int32, true));
+    let data_types = vec![
+        DataType::Null,
+        DataType::Boolean,
+        DataType::Int8,
+        DataType::Int16,
+        DataType::Int32,
+        DataType::Int64,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt32,
+        DataType::UInt64,
+        DataType::Float16,
+        DataType::Float32,
+        DataType::Float64,
+        DataType::Timestamp(TimeUnit::Second, None),
+        DataType::Timestamp(TimeUnit::Millisecond, None),
+        DataType::Timestamp(TimeUnit::Microsecond, None),
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+        DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+        DataType::Date32,
+        DataType::Date64,
+        DataType::Time32(TimeUnit::Second),
+        DataType::Time32(TimeUnit::Millisecond),
+        DataType::Time64(TimeUnit::Microsecond),
+        DataType::Time64(TimeUnit::Nanosecond),
+        DataType::Duration(TimeUnit::Second),
+        DataType::Duration(TimeUnit::Millisecond),
+        DataType::Duration(TimeUnit::Microsecond),
+        DataType::Duration(TimeUnit::Nanosecond),
+        DataType::Interval(IntervalUnit::YearMonth),
+        DataType::Interval(IntervalUnit::DayTime),
+        DataType::Interval(IntervalUnit::MonthDayNano),
+        DataType::Binary,
+        DataType::FixedSizeBinary(4),
+        DataType::LargeBinary,
+        DataType::BinaryView,
+        DataType::Utf8,
+        DataType::LargeUtf8,
+        DataType::Utf8View,
+        DataType::List(item.clone()),
+        DataType::FixedSizeList(item.clone(), 2),
+        DataType::LargeList(item.clone()),
+        DataType::Struct(vec![item.clone()].into()),
+        DataType::Union(
+            UnionFields::new(vec![0], vec![item.as_ref().clone()]),
+            UnionMode::Sparse,
+        ),
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        DataType::Decimal128(10, 2),
+        DataType::Decimal256(50, 2),
+        DataType::Map(
+            Arc::new(Field::new_struct(
+                "entries",
+                vec![
+                    Field::new("keys", DataType::Utf8, false),
+                    Field::new("values", DataType::Int32, true),
+                ],
+                false,
+            )),
+            false,
+        ),
+        DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", DataType::Int32, true)),
+        ),
+    ];
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed([0; 32]);
+    for data_type in data_types {
+        let _ = conn.table_drop(table_name);
+
+        let spec = vec![crate::spec::ColumnSpec {
+            field_name: "a".to_string(),
+            is_nullable: true,
+            data_type: data_type.clone(),
+            values: vec![
+                crate::spec::ValuesSpec {
+                    gen_process: crate::spec::ValueGenProcess::Unit,
+                    repeat: 1,
+                },
+                crate::spec::ValuesSpec {
+                    gen_process: crate::spec::ValueGenProcess::Null,
+                    repeat: 1,
+                },
+            ],
+        }];
+        let (schema, mut batches) = generate_batch(spec, &mut rng);
+        let batch = batches.remove(0);
+
+        match conn.table_create(table_name, schema) {
+            Ok(()) => {}
+            Err(TableCreateError::Connector(e)) if is_unsupported(&e) => {
+                assert!(e.to_string().contains("`a`"), "{}: {}", data_type, e);
+                continue;
+            }
+            Err(e) => panic!("{}: {:?}", data_type, e),
+        }
+
+        let res = conn.append(table_name).and_then(|mut appender| {
+            appender.append(batch)?;
+            appender.finish()
+        });
+        match res {
+            Ok(_) => {
+                let (_, batches) = query_table(conn, table_name, ident_quote_char)
+                    .unwrap_or_else(|e| panic!("{}: {:?}", data_type, e));
+                let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                assert_eq!(num_rows, 2, "{}", data_type);
+                let nulls: usize = (batches.iter())
+                    .map(|b| b.column(0).logical_nulls().map_or(0, |n| n.null_count()))
+                    .sum();
+                let expected = if data_type == DataType::Null { 2 } else { 1 };
+                assert_eq!(nulls, expected, "{}", data_type);
+            }
+            Err(e) if is_unsupported(&e) => {}
+            Err(e) => panic!("{}: {:?}", data_type, e),
+        }
+    }
+    let _ = conn.table_drop(table_name);
+}
+
+/// Runs a slow query with a timeout and checks that it was cancelled early.
+pub fn cancel<C: Connector>(
+    conn: &mut C,
+    slow_query: &str,
+) -> Result<Vec<RecordBatch>, ConnectorError> {
+    use std::time::{Duration, Instant};
+
+    let cancel = conn.cancel_handle().unwrap();
+    let start = Instant::now();
+    let res = {
+        let mut stmt = conn.query(slow_query).unwrap();
+        let _timeout = cancel.timeout(Duration::from_millis(200));
+        stmt.start([])
+            .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+    };
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "{:?}",
+        start.elapsed()
+    );
+
+    // connection can be used after the cancelled query
+    let batches = connector_arrow::query(conn, "SELECT 1").unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+    // guard that is dropped before the timeout does not cancel
+    let _ = cancel.timeout(Duration::from_millis(0));
+    let timeout = cancel.timeout(Duration::from_secs(60));
+    connector_arrow::query(conn, "SELECT 1").unwrap();
+    drop(timeout);
+
+    res
+}
+
 #[allow(dead_code)]
 pub fn streaming<C: Connector>(conn: &mut C) {
     let query = "
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use arrow::array::{ArrayRef, RecordBatch};
 use arrow::datatypes::SchemaRef;
 use arrow::datatypes::{DataType, Field, Schema};
@@ -74,6 +76,14 @@ pub fn query_table<C: Connector>(
     Ok((schema, batches))
 }
 
+/// Execute a statement that does not return a result set.
+pub fn execute<C: Connector>(conn: &mut C, query: &str) -> Result<(), ConnectorError> {
+    match connector_arrow::query(conn, query) {
+        Ok(_) | Err(ConnectorError::NoResultSets) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
 pub fn query_literals<C: Connector>(conn: &mut C, queries: Vec<QueryOfSingleLiteral>) {
     let mut sql_selects = Vec::new();
     let mut expected_fields = Vec::new();